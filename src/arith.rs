@@ -1,20 +1,21 @@
 /* arith.rs
  * -------- */ 
-use crate::code::BinOp;
-use crate::vm::Vm;
-use crate::words::Word;
+use crate::code::{BinOp, sprint_binop};
+use crate::error::{ErrorType, StaxError, error};
+use crate::matrix::bind;
+use crate::na::{is_na, na};
+use crate::words::{Word, WordResult};
 
-use ndarray::{Array, Array1, Array2, array};
+use ndarray::{Array1, Array2, ArrayD, Axis, Ix2, IxDyn, Zip, array, concatenate};
 
-type ArithResult = Result<Word, String>;
+type ArithResult = WordResult;
 
-pub fn do_arith(vm: &Vm, op: BinOp, lhs: &Word, rhs: &Word) -> ArithResult {
-	match lhs {
-		Word::Int(l) 	=> { return do_int(*l, rhs, op) },
-		Word::Num(l) 	=> { return do_num(*l, rhs, op) },
-		Word::Str(l) 	=> { return do_str(&*l, rhs, op) },
-		Word::Vect(l)	=> { return do_vect{&*l, rhs, op} },
-		_ => return Ok(Word::Null)
+pub fn do_arith(op: BinOp, lhs: &Word, rhs: &Word) -> ArithResult {
+	match (lhs, rhs) {
+		(Word::Int(l), Word::Int(_) | Word::Num(_) | Word::Str(_)) 	=> { return do_int(*l, rhs, op) },
+		(Word::Num(l), Word::Int(_) | Word::Num(_) | Word::Str(_)) 	=> { return do_num(*l, rhs, op) },
+		(Word::Str(l), Word::Int(_) | Word::Num(_) | Word::Str(_)) 	=> { return do_str(&*l, rhs, op) },
+		_ => { return do_array(lhs, rhs, op) }
 	}
 }
 
@@ -23,8 +24,7 @@ fn do_int(lhs: i32, rhs: &Word, op: BinOp) -> ArithResult {
 		Word::Int(r) 	=> { return do_int_int(lhs, *r, op) },
 		Word::Num(r) 	=> { return do_num_num(lhs as f64, *r, op) },
 		Word::Str(r) 	=> { return do_str_int(&*r, lhs, op, false) },
-		Word::Vect(r)	=> { return do_vec_num(r, lhs as f64, false) },
-		_ => return Ok(Word::Null)
+		_ => { return arith_error(&Word::Int(lhs), rhs, op) }
 	}
 }
 
@@ -33,8 +33,7 @@ fn do_num(lhs: f64, rhs: &Word, op: BinOp) -> ArithResult {
 		Word::Int(r) 	=> { return do_num_num(lhs, *r as f64, op) },
 		Word::Num(r) 	=> { return do_num_num(lhs, *r, op) },
 		Word::Str(r) 	=> { return do_str_num(&*r, lhs, op, false) },
-		Word::Vect(r)	=> { return do_vec_num(r, lhs, false) },
-		_ => return Ok(Word::Null)
+		_ => { return arith_error(&Word::Num(lhs), rhs, op) }
 	}
 }
 
@@ -43,28 +42,31 @@ fn do_str(lhs: &String, rhs: &Word, op: BinOp) -> ArithResult {
 		Word::Int(r) 	=> { return do_str_int(lhs, *r, op, true) },
 		Word::Num(r) 	=> { return do_str_num(lhs, *r, op, true) },
 		Word::Str(r) 	=> { return do_str_str(lhs, &*r, op) },
-		_ => return Ok(Word::Null)
+		_ => { return arith_error(&Word::Str(Box::new(lhs.clone())), rhs, op) }
 	}
 }
 
 fn do_int_int(lhs: i32, rhs: i32, op: BinOp) -> ArithResult {
 	match op {
-		BinOp::Add	=> { return Ok(Word::Int(lhs + rhs)) },
-		BinOp::Sub	=> { return Ok(Word::Int(lhs - rhs)) },
-		BinOp::Mul	=> { return Ok(Word::Int(lhs * rhs)) },
+		BinOp::Add	=> { return int_result(lhs.checked_add(rhs), lhs, rhs, op) },
+		BinOp::Sub	=> { return int_result(lhs.checked_sub(rhs), lhs, rhs, op) },
+		BinOp::Mul	=> { return int_result(lhs.checked_mul(rhs), lhs, rhs, op) },
 		BinOp::Div	=> { return Ok(Word::Num(lhs as f64 / rhs as f64)) },
 		BinOp::Pow	=> { return Ok(Word::Num((lhs as f64).powi(rhs))) },
-		BinOp::Mod	=> { return Ok(Word::Int(lhs % rhs)) },
+		BinOp::Mod	=> {
+			if rhs == 0 { return error(ErrorType::Domain, format!("Integer remainder of {} by zero.", lhs)) }
+			return int_result(lhs.checked_rem(rhs), lhs, rhs, op)
+		},
 		BinOp::Seq  => {
-			let diff = (lhs - rhs).abs() as usize;
+			let diff = (lhs as i64 - rhs as i64).abs() as usize;
 			return Ok(Word::Vect(Box::new(Array1::<f64>::linspace(lhs as f64, rhs as f64, diff+1))))
 		},
 		BinOp::Cat  => { return Ok(Word::Vect(Box::new(array![lhs as f64, rhs as f64]))) },
 		BinOp::Rep  => {
-			if rhs < 0 { return Err(format!("Value can only be repeated a positive number of times. Given: '{}'.", rhs)) }
+			if rhs < 0 { return error(ErrorType::Domain, format!("Value can only be repeated a positive number of times. Given: '{}'.", rhs)) }
 			return Ok(Word::Vect(Box::new(Array1::from_elem(rhs as usize, lhs as f64))))
 		},
-		_ => { return Err("Binary operation not recognized!".to_string()) }
+		_ => { return error(ErrorType::Type, "Binary operation not recognized!".to_string()) }
 	}
 }
 
@@ -82,10 +84,10 @@ fn do_num_num(lhs: f64, rhs: f64, op: BinOp) -> ArithResult {
 		},
 		BinOp::Cat  => { return Ok(Word::Vect(Box::new(array![lhs, rhs]))) },
 		BinOp::Rep  => {
-			if rhs < 0.0 { return Err(format!("Value can only be repeated a positive number of times. Given: '{}'.", rhs)) }
+			if rhs < 0.0 { return error(ErrorType::Domain, format!("Value can only be repeated a positive number of times. Given: '{}'.", rhs)) }
 			return Ok(Word::Vect(Box::new(Array1::from_elem(rhs as usize, lhs))))
 		},
-		_ => { return Err("Binary operation not recognized!".to_string()) }
+		_ => { return error(ErrorType::Type, "Binary operation not recognized!".to_string()) }
 	}
 }
 
@@ -94,17 +96,17 @@ fn do_str_str(lhs: &String, rhs: &String, op: BinOp) -> ArithResult {
 		BinOp::Add 	=> { return Ok(Word::Str(Box::new(format!("{}{}", lhs, rhs)))) },
 		BinOp::Sub  => { return Ok(Word::Str(Box::new(lhs.replace(rhs, "")))) },
 		BinOp::Cat  => { 
-			return Ok(Word::StrVect(Box::new( Array1::from_vec(vec![lhs.to_string(), rhs.to_string()]) )))
+			return Ok(Word::StrVect(Box::new( Array1::from_vec(vec![Some(lhs.to_string()), Some(rhs.to_string())]) )))
 		},
 		BinOp::Del	=> { 
 			if lhs == rhs { return Ok(Word::StrVect(Box::new( Array1::from_vec(vec![]) ))) 
 			} else { return Ok(Word::Str(Box::new(lhs.to_string()))) } 
 		},
 		BinOp::Spl 	=> { 
-			let new_lhs = lhs.split(rhs).map(|s| s.to_string()).collect();
+			let new_lhs = lhs.split(rhs).map(|s| Some(s.to_string())).collect();
 			return Ok(Word::StrVect(Box::new( Array1::from_vec(new_lhs) ))) 
 		},
-		_ => { return Err(format!("Operation cannot be completed between two string objects.")) }
+		_ => { return error(ErrorType::Type, format!("Operation cannot be completed between two string objects.")) }
 	}
 }
 
@@ -116,9 +118,9 @@ fn do_str_num(lhs: &String, rhs: f64, op: BinOp, left: bool) -> ArithResult {
 		},
 		BinOp::Cat => {
 			if left { 
-				return Ok(Word::StrVect(Box::new(Array1::from_vec(vec![lhs.to_string(), format!("{}", rhs)]))))
+				return Ok(Word::StrVect(Box::new(Array1::from_vec(vec![Some(lhs.to_string()), Some(format!("{}", rhs))]))))
 			}
-			return Ok(Word::StrVect(Box::new(Array1::from_vec(vec![format!("{}", rhs), lhs.to_string()]))))
+			return Ok(Word::StrVect(Box::new(Array1::from_vec(vec![Some(format!("{}", rhs)), Some(lhs.to_string())]))))
 		},
 		_ => { return do_str_int(lhs, rhs as i32, op, left) }
 	}
@@ -131,84 +133,240 @@ fn do_str_int(lhs: &String, rhs: i32, op: BinOp, left: bool) -> ArithResult {
 			return Ok(Word::Str(Box::new(format!("{}{}", rhs, lhs)))) 
 		},
 		BinOp::Mul => {
-			if rhs < 0 { return Err(format!("String cannot be repeated a negative number of times.")) }
+			if rhs < 0 { return error(ErrorType::Domain, format!("String cannot be repeated a negative number of times.")) }
 			return Ok(Word::Str(Box::new(lhs.repeat(rhs as usize))))
 		},
 		BinOp::Cat => {
 			if left { 
-				return Ok(Word::StrVect(Box::new(Array1::from_vec(vec![lhs.to_string(), format!("{}", rhs)]))))
+				return Ok(Word::StrVect(Box::new(Array1::from_vec(vec![Some(lhs.to_string()), Some(format!("{}", rhs))]))))
 			}
-			return Ok(Word::StrVect(Box::new(Array1::from_vec(vec![format!("{}", rhs), lhs.to_string()]))))
+			return Ok(Word::StrVect(Box::new(Array1::from_vec(vec![Some(format!("{}", rhs)), Some(lhs.to_string())]))))
 		},
-		_ => { return Err(format!("Operation cannot be completed between a string and numeric object.")) }
+		_ => { return error(ErrorType::Type, format!("Operation cannot be completed between a string and numeric object.")) }
 	}
 }
 
 
-/* ADDING FUNCTIONS! */
-pub fn add_int_int(lhs: i32, rhs: i32) -> Word { return Word::Int(lhs + rhs) }
-pub fn add_num_num(lhs: f64, rhs: f64) -> Word { return Word::Num(lhs + rhs) }
-pub fn add_str_str(lhs: &String, rhs: &String) -> Word { return Word::Str(Box::new( lhs.to_string() + rhs)) }
-pub fn add_str_boo(lhs: &String, rhs: bool) -> Word { return Word::Str(Box::new( format!("{}{}", lhs, rhs))) }
-pub fn add_str_int(lhs: &String, rhs: i32)  -> Word { return Word::Str(Box::new( format!("{}{}", lhs, rhs))) }
-pub fn add_str_num(lhs: &String, rhs: f64)  -> Word { return Word::Str(Box::new( format!("{}{}", lhs, rhs))) }
-pub fn add_boo_str(lhs: bool, rhs: &String) -> Word { return Word::Str(Box::new( format!("{}{}", lhs, rhs))) }
-pub fn add_int_str(lhs: i32, rhs: &String)  -> Word { return Word::Str(Box::new( format!("{}{}", lhs, rhs))) }
-pub fn add_num_str(lhs: f64, rhs: &String)  -> Word { return Word::Str(Box::new( format!("{}{}", lhs, rhs))) }
+/* 	== ARRAYS == */
 
-pub fn add_vec_vec(lhs: &Array1<f64>, rhs: &Array1<f64>) -> Word { return Word::Vect(Box::new( lhs + rhs )) }
-pub fn add_vec_boolvec(lhs: &Array1<f64>, rhs: &Array1<bool>) -> Word {
-	let new_rhs = rhs.map(|i| (*i as isize) as f64);
-	return Word::Vect(Box::new( lhs + new_rhs ))
-}
-pub fn add_boolvec_vec(lhs: &Array1<bool>, rhs: &Array1<f64>) -> Word {
-	let new_lhs = lhs.map(|i| (*i as isize) as f64);
-	return Word::Vect(Box::new( new_lhs + rhs ))
-}
-pub fn add_strvec_strvec(lhs: &Array1<String>, rhs: &Array1<String>) -> Word { 
-	let new_rhs = rhs.broadcast(lhs.shape());
-	let res = lhs.iter().zip(new_rhs.iter()).map(|(l,r)| format!("{}{}", l, r)).collect::<Array1<String>>();
-	return Word::StrVect(Box::new(res))
-}
-pub fn add_strvec_vec(lhs: &Array1<String>, rhs: &Array1<f64>) -> Word {
-	let new_rhs = rhs.broadcast(lhs.shape());
-	let res = lhs.iter().zip(new_rhs.iter()).map(|(l,r)| format!("{}{}", l, r)).collect::<Array1<String>>();
-	return Word::StrVect(Box::new(res))
-}
-pub fn add_vec_strvec(lhs: &Array1<f64>, rhs: &Array1<String>) -> Word {
-	let new_rhs = rhs.broadcast(lhs.shape());
-	let res = lhs.iter().zip(new_rhs.iter()).map(|(l,r)| format!("{}{}", l, r)).collect::<Array1<String>>();
-	return Word::StrVect(Box::new(res))
+/* Vectors, matrices and boolean vectors (as 0/1) combine element by element, with scalars
+ * and vectors broadcast against matrices. Strings concatenate element-wise with anything. */
+fn do_array(lhs: &Word, rhs: &Word, op: BinOp) -> ArithResult {
+	match op {
+		BinOp::Cat 	=> { return do_cat(lhs, rhs) },
+		BinOp::Rep 	=> { return do_rep(lhs, rhs) },
+		BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Pow | BinOp::Mod => { },
+		_ => { return arith_error(lhs, rhs, op) }
+	}
+	if is_text(lhs) || is_text(rhs) {
+		match (text_array(lhs), text_array(rhs), &op) {
+			(Some(l), Some(r), BinOp::Add) 	=> {
+				let result = broadcast_zip(&l, &r, |a, b| match (a, b) { (Some(a), Some(b)) => Some(format!("{}{}", a, b)), _ => None })?;
+				return Ok(from_strs(result))
+			},
+			(Some(l), _, BinOp::Mul) 	=> match rhs {
+				Word::Int(n) if *n >= 0 	=> { return Ok(from_strs(l.mapv(|s| s.map(|s| s.repeat(*n as usize))))) },
+				_ => { return arith_error(lhs, rhs, op) }
+			},
+			_ => { return arith_error(lhs, rhs, op) }
+		}
+	}
+	match (num_array(lhs), num_array(rhs)) {
+		(Some(l), Some(r)) 	=> {
+			let result = match op {
+				BinOp::Add 	=> { broadcast_zip(&l, &r, |a, b| a + b)? },
+				BinOp::Sub 	=> { broadcast_zip(&l, &r, |a, b| a - b)? },
+				BinOp::Mul 	=> { broadcast_zip(&l, &r, |a, b| a * b)? },
+				BinOp::Div 	=> { broadcast_zip(&l, &r, |a, b| a / b)? },
+				BinOp::Pow 	=> { broadcast_zip(&l, &r, |a, b| a.powf(*b))? },
+				_ 			=> { broadcast_zip(&l, &r, |a, b| a % b)? }
+			};
+			return from_nums(result)
+		},
+		_ => { return arith_error(lhs, rhs, op) }
+	}
 }
-pub fn add_strvec_boolvec(lhs: &Array1<String>, rhs: &Array1<bool>) -> Word {
-	let new_rhs = rhs.broadcast(lhs.shape());
-	let res = lhs.iter().zip(new_rhs.iter()).map(|(l,r)| format!("{}{}", l, r)).collect::<Array1<String>>();
-	return Word::StrVect(Box::new(res))
+
+fn do_cat(lhs: &Word, rhs: &Word) -> ArithResult {
+	match (lhs, rhs) {
+		(Word::Mat(l), Word::Mat(r)) 	=> { return bind(Axis(0), l.view(), r.view()) },
+		(Word::Mat(l), Word::Vect(r)) 	=> { return bind(Axis(0), l.view(), r.view().insert_axis(Axis(0))) },
+		(Word::Vect(l), Word::Mat(r)) 	=> { return bind(Axis(0), l.view().insert_axis(Axis(0)), r.view()) },
+		(Word::Mat(_), _) | (_, Word::Mat(_)) 	=> { return arith_error(lhs, rhs, BinOp::Cat) },
+		_ => { }
+	}
+	if is_text(lhs) || is_text(rhs) {
+		match (text_array(lhs), text_array(rhs)) {
+			(Some(l), Some(r)) 	=> { return Ok(Word::StrVect(Box::new(l.iter().chain(r.iter()).cloned().collect()))) },
+			_ => { return arith_error(lhs, rhs, BinOp::Cat) }
+		}
+	}
+	match (bool_array(lhs), bool_array(rhs)) {
+		(Some(l), Some(r)) 	=> { return Ok(Word::BoolVect(Box::new(l.iter().chain(r.iter()).cloned().collect()))) },
+		_ => { }
+	}
+	match (num_array(lhs), num_array(rhs)) {
+		(Some(l), Some(r)) 	=> { return Ok(Word::Vect(Box::new(l.iter().chain(r.iter()).cloned().collect()))) },
+		_ => { return arith_error(lhs, rhs, BinOp::Cat) }
+	}
 }
-pub fn add_boolvec_strvec(lhs: &Array1<String>, rhs: &Array1<bool>) -> Word {
-	let new_rhs = rhs.broadcast(lhs.shape());
-	let res = lhs.iter().zip(new_rhs.iter()).map(|(l,r)| format!("{}{}", l, r)).collect::<Array1<String>>();
-	return Word::StrVect(Box::new(res))
+
+fn do_rep(lhs: &Word, rhs: &Word) -> ArithResult {
+	let n: usize;
+	match rhs {
+		Word::Int(i) if *i >= 0 	=> { n = *i as usize },
+		Word::Num(f) if *f >= 0.0 	=> { n = *f as usize },
+		Word::Int(_) | Word::Num(_) => {
+			return error(ErrorType::Domain, format!("Value can only be repeated a positive number of times. Given: '{}'.", rhs.sprint()))
+		},
+		_ => { return arith_error(lhs, rhs, BinOp::Rep) }
+	}
+	match lhs {
+		Word::Bool(b) 		=> { return Ok(Word::BoolVect(Box::new(Array1::from_elem(n, Some(*b))))) },
+		Word::BoolVect(v) 	=> { return Ok(Word::BoolVect(Box::new(tile(v, n)))) },
+		Word::Mat(m) 		=> {
+			let rows = vec![m.view(); n];
+			match concatenate(Axis(0), &rows) {
+				Ok(result) 	=> { return Ok(Word::Mat(Box::new(result))) },
+				Err(_) 		=> { return Ok(Word::Mat(Box::new(Array2::zeros((0, m.ncols()))))) }
+			}
+		},
+		Word::Str(s) 		=> { return Ok(Word::StrVect(Box::new(Array1::from_elem(n, Some(s.to_string()))))) },
+		Word::StrVect(v) 	=> { return Ok(Word::StrVect(Box::new(tile(v, n)))) },
+		Word::Vect(v) 		=> { return Ok(Word::Vect(Box::new(tile(v, n)))) },
+		_ => { return arith_error(lhs, rhs, BinOp::Rep) }
+	}
 }
-pub fn add_boolvec_boolvec(lhs: &Array1<bool>, rhs: &Array1<bool>) -> Word {
-	let new_lhs = lhs.map(|i| (*i as isize) as f64);
-	let new_rhs = rhs.map(|i| (*i as isize) as f64);
-	return Word::Vect(Box::new( new_lhs + new_rhs ))
+
+/* Applies 'f' element by element after broadcasting both arrays to a common shape. As in
+ * numpy, axes are matched from the last one and must be equal or have length 1. */
+pub fn broadcast_zip<A, B, C, F>(lhs: &ArrayD<A>, rhs: &ArrayD<B>, f: F) -> Result<ArrayD<C>, StaxError>
+	where F: Fn(&A, &B) -> C
+{
+	let n = lhs.ndim().max(rhs.ndim());
+	let mut shape = vec![0; n];
+	for i in 0 .. n {
+		let l = if i < lhs.ndim() { lhs.shape()[lhs.ndim() - 1 - i] } else { 1 };
+		let r = if i < rhs.ndim() { rhs.shape()[rhs.ndim() - 1 - i] } else { 1 };
+		if l != r && l != 1 && r != 1 {
+			return error(ErrorType::Index, format!("Objects of shape {:?} and {:?} cannot be broadcast together.", lhs.shape(), rhs.shape()))
+		}
+		shape[n - 1 - i] = if l == 1 { r } else { l };
+	}
+	match (lhs.broadcast(IxDyn(&shape)), rhs.broadcast(IxDyn(&shape))) {
+		(Some(l), Some(r)) 	=> { return Ok(Zip::from(&l).and(&r).map_collect(|a, b| f(a, b))) },
+		_ => { return error(ErrorType::Index, format!("Objects of shape {:?} and {:?} cannot be broadcast together.", lhs.shape(), rhs.shape())) }
+	}
 }
 
-pub fn add_vec_mat(lhs: &Array1<f64>, rhs: &Array2<f64>) -> Word { return Word::Mat( Box::new(lhs + rhs) ) }
-pub fn add_mat_vec(lhs: &Array2<f64>, rhs: &Array1<f64>) -> Word { return Word::Mat( Box::new(lhs + rhs) ) }
-pub fn add_mat_mat(lhs: &Array2<f64>, rhs: &Array2<f64>) -> Word { return Word::Mat( Box::new(lhs + rhs) ) }
+/* Integers do not wrap around: a result outside the range of 'int' is an Overflow error. */
+fn int_result(result: Option<i32>, lhs: i32, rhs: i32, op: BinOp) -> ArithResult {
+	match result {
+		Some(i) => { return Ok(Word::Int(i)) },
+		None 	=> { return error(ErrorType::Overflow, format!("Integer overflow in '{}' between {} and {}.", sprint_binop(&op), lhs, rhs)) }
+	}
+}
 
+fn arith_error(lhs: &Word, rhs: &Word, op: BinOp) -> ArithResult {
+	return error(ErrorType::Type, format!("'{}' cannot be completed between objects of type '{}' and '{}'.",
+		sprint_binop(&op), lhs.sprint_type(), rhs.sprint_type()))
+}
 
+fn tile<T: Clone>(v: &Array1<T>, n: usize) -> Array1<T> {
+	return v.iter().cycle().take(v.len() * n).cloned().collect()
+}
 
+fn is_text(w: &Word) -> bool {
+	match w {
+		Word::Str(_) | Word::StrVect(_) => { return true },
+		_ => { return false }
+	}
+}
 
+fn num_array(w: &Word) -> Option<ArrayD<f64>> {
+	match w {
+		Word::Bool(b) 		=> { return Some(ArrayD::from_elem(IxDyn(&[]), *b as i32 as f64)) },
+		Word::BoolVect(v) 	=> { return Some(v.mapv(|b| b.map(|b| b as i32 as f64).unwrap_or(na())).into_dyn()) },
+		Word::Int(i) 		=> { return Some(ArrayD::from_elem(IxDyn(&[]), *i as f64)) },
+		Word::Na 			=> { return Some(ArrayD::from_elem(IxDyn(&[]), na())) },
+		Word::Num(f) 		=> { return Some(ArrayD::from_elem(IxDyn(&[]), *f)) },
+		Word::Vect(v) 		=> { return Some(v.to_owned().into_dyn()) },
+		Word::Mat(m) 		=> { return Some(m.to_owned().into_dyn()) },
+		_ => { return None }
+	}
+}
 
+fn bool_array(w: &Word) -> Option<ArrayD<Option<bool>>> {
+	match w {
+		Word::Bool(b) 		=> { return Some(ArrayD::from_elem(IxDyn(&[1]), Some(*b))) },
+		Word::Na 			=> { return Some(ArrayD::from_elem(IxDyn(&[1]), None)) },
+		Word::BoolVect(v) 	=> { return Some(v.to_owned().into_dyn()) },
+		_ => { return None }
+	}
+}
 
+/* Strings, or the printed form of numbers and booleans, for string concatenation. NA stays
+ * missing rather than becoming the text "NA". */
+fn text_array(w: &Word) -> Option<ArrayD<Option<String>>> {
+	match w {
+		Word::Str(s) 		=> { return Some(ArrayD::from_elem(IxDyn(&[]), Some(s.to_string()))) },
+		Word::StrVect(v) 	=> { return Some(v.to_owned().into_dyn()) },
+		Word::Na 			=> { return Some(ArrayD::from_elem(IxDyn(&[]), None)) },
+		Word::Num(f) if is_na(*f) 	=> { return Some(ArrayD::from_elem(IxDyn(&[]), None)) },
+		Word::Bool(_) | Word::Int(_) | Word::Num(_) => { return Some(ArrayD::from_elem(IxDyn(&[]), Some(w.sprint()))) },
+		Word::BoolVect(v) 	=> { return Some(v.mapv(|b| b.map(|b| format!("{}", b))).into_dyn()) },
+		Word::Vect(v) 		=> { return Some(v.mapv(|f| if is_na(f) { None } else { Some(format!("{}", f)) }).into_dyn()) },
+		Word::Mat(m) 		=> { return Some(m.mapv(|f| Some(format!("{}", f))).into_dyn()) },
+		_ => { return None }
+	}
+}
 
+fn from_nums(a: ArrayD<f64>) -> ArithResult {
+	match a.ndim() {
+		0 => match a.iter().next().cloned().unwrap_or(0.0) {
+			f if is_na(f) 	=> { return Ok(Word::Na) },
+			f 	=> { return Ok(Word::Num(f)) }
+		},
+		1 => { return Ok(Word::Vect(Box::new(a.iter().cloned().collect()))) },
+		_ => match a.into_dimensionality::<Ix2>() {
+			Ok(m) 	=> { return Ok(Word::Mat(Box::new(m))) },
+			Err(e) 	=> { return error(ErrorType::Index, format!("Result has more than two axes. {}", e)) }
+		}
+	}
+}
 
+/* There is no string matrix, so results with more than one axis are flattened row-major. */
+fn from_strs(a: ArrayD<Option<String>>) -> Word {
+	if a.ndim() == 0 {
+		match a.iter().next().cloned().unwrap_or_default() {
+			Some(s) => { return Word::Str(Box::new(s)) },
+			None 	=> { return Word::Na }
+		}
+	}
+	return Word::StrVect(Box::new(a.iter().cloned().collect()))
+}
 
 
+#[cfg(test)]
+mod tests {
+	use super::*;
 
+	fn kind(r: ArithResult) -> ErrorType {
+		return r.unwrap_err().kind
+	}
 
+	#[test]
+	fn int_overflow() {
+		assert_eq!(kind(do_int_int(i32::MAX, 1, BinOp::Add)), ErrorType::Overflow);
+		assert_eq!(kind(do_int_int(i32::MIN, 1, BinOp::Sub)), ErrorType::Overflow);
+		assert_eq!(kind(do_int_int(1 << 16, 1 << 16, BinOp::Mul)), ErrorType::Overflow);
+		assert_eq!(kind(do_int_int(i32::MIN, -1, BinOp::Mod)), ErrorType::Overflow);
+		assert!(matches!(do_int_int(i32::MAX - 1, 1, BinOp::Add), Ok(Word::Int(i32::MAX))));
+	}
 
+	#[test]
+	fn int_mod_by_zero() {
+		assert_eq!(kind(do_int_int(1, 0, BinOp::Mod)), ErrorType::Domain);
+		assert!(matches!(do_int_int(-7, 3, BinOp::Mod), Ok(Word::Int(-1))));
+	}
+}
@@ -1,6 +1,6 @@
 /* base.rs
  * ------- */
-use crate::code::{Opcode, StackOp, BinOp, BoolOp, Comb1, Comb2, Comb3, UnOp, MathOp, AutoOp};
+use crate::code::{Opcode, GenOp, StackOp, BinOp, BoolOp, Comb1, Comb2, Comb3, Comb4, Dist, DistOp, FactorOp, FrameOp, IndexOp, IoOp, LinOp, MatOp, NaOp, RandOp, SortOp, StatOp, UnOp, MathOp, AutoOp};
 use crate::env::Env;
 use crate::words::Word;
 
@@ -55,18 +55,41 @@ pub fn create_base() -> Env {
 	insertMop(&mut map, "lte", Opcode::BoolOp(BoolOp::Lte));
 	insertMop(&mut map, "and", Opcode::BoolOp(BoolOp::And));
 	insertMop(&mut map, "or", Opcode::BoolOp(BoolOp::Or));
+	insertMop(&mut map, "xor", Opcode::BoolOp(BoolOp::Xor));
 
 	/* 	== COMBINATORS == */
 	insertMop(&mut map, "do", Opcode::Comb1(Comb1::Do));
+	insertMop(&mut map, "loop", Opcode::Comb1(Comb1::Loop));
 
 	insertMop(&mut map, "cleave", Opcode::Comb2(Comb2::Cleave));
 	insertMop(&mut map, "dip", Opcode::Comb2(Comb2::Dip));
+	insertMop(&mut map, "each", Opcode::Comb2(Comb2::Each));
+	insertMop(&mut map, "filter", Opcode::Comb2(Comb2::Filter));
 	insertMop(&mut map, "ifthen", Opcode::Comb2(Comb2::Ifthen));
+	insertMop(&mut map, "map", Opcode::Comb2(Comb2::Map));
+	insertMop(&mut map, "reduce", Opcode::Comb2(Comb2::Reduce));
+	insertMop(&mut map, "times", Opcode::Comb2(Comb2::Times));
+	insertMop(&mut map, "try", Opcode::Comb2(Comb2::Try));
+	insertMop(&mut map, "while", Opcode::Comb2(Comb2::While));
 
+	insertMop(&mut map, "fold", Opcode::Comb3(Comb3::Fold));
+	insertMop(&mut map, "groupby", Opcode::Comb3(Comb3::Groupby));
 	insertMop(&mut map, "ifelse", Opcode::Comb3(Comb3::Ifelse));
+	insertMop(&mut map, "primrec", Opcode::Comb3(Comb3::Primrec));
+	insertMop(&mut map, "scan", Opcode::Comb3(Comb3::Scan));
+	insertMop(&mut map, "tailrec", Opcode::Comb3(Comb3::Tailrec));
+	insertMop(&mut map, "zipwith", Opcode::Comb3(Comb3::Zipwith));
+
+	insertMop(&mut map, "binrec", Opcode::Comb4(Comb4::Binrec));
+	insertMop(&mut map, "genrec", Opcode::Comb4(Comb4::Genrec));
+	insertMop(&mut map, "linrec", Opcode::Comb4(Comb4::Linrec));
+
+	insertMop(&mut map, "break", Opcode::GenOp(GenOp::Break));
 
 	/* 	== UNARY OPERATORS == */
+	insertMop(&mut map, "not", Opcode::UnOp(UnOp::Not));
 	insertMop(&mut map, "print", Opcode::UnOp(UnOp::Print));
+	insertMop(&mut map, "throw", Opcode::UnOp(UnOp::Throw));
 
 	/* 	== MATH OPERATIONS == */
 	insertMop(&mut map, "abs", Opcode::MathOp(MathOp::Abs));
@@ -78,34 +101,195 @@ pub fn create_base() -> Env {
 	insertMop(&mut map, "atanh", Opcode::MathOp(MathOp::Atanh));
 	insertMop(&mut map, "cbrt", Opcode::MathOp(MathOp::Cbrt));
 	insertMop(&mut map, "ceil", Opcode::MathOp(MathOp::Ceil));
+	insertMop(&mut map, "cor", Opcode::MathOp(MathOp::Cor));
 	insertMop(&mut map, "cos", Opcode::MathOp(MathOp::Cos));
 	insertMop(&mut map, "cosh", Opcode::MathOp(MathOp::Cosh));
+	insertMop(&mut map, "cov", Opcode::MathOp(MathOp::Cov));
+	insertMop(&mut map, "cummax", Opcode::MathOp(MathOp::Cummax));
+	insertMop(&mut map, "cummin", Opcode::MathOp(MathOp::Cummin));
+	insertMop(&mut map, "cumprod", Opcode::MathOp(MathOp::Cumprod));
+	insertMop(&mut map, "cumsum", Opcode::MathOp(MathOp::Cumsum));
 	insertMop(&mut map, "exp", Opcode::MathOp(MathOp::Exp));
 	insertMop(&mut map, "floor", Opcode::MathOp(MathOp::Floor));
 	insertMop(&mut map, "fract", Opcode::MathOp(MathOp::Fract));
+	insertMop(&mut map, "iqr", Opcode::MathOp(MathOp::Iqr));
+	insertMop(&mut map, "kurtosis", Opcode::MathOp(MathOp::Kurtosis));
 	insertMop(&mut map, "ln", Opcode::MathOp(MathOp::Ln));
 	insertMop(&mut map, "log10", Opcode::MathOp(MathOp::Log10));
 	insertMop(&mut map, "log2", Opcode::MathOp(MathOp::Log2));
+	insertMop(&mut map, "mad", Opcode::MathOp(MathOp::Mad));
 	insertMop(&mut map, "max", Opcode::MathOp(MathOp::Max));
 	insertMop(&mut map, "mean", Opcode::MathOp(MathOp::Mean));
+	insertMop(&mut map, "median", Opcode::MathOp(MathOp::Median));
 	insertMop(&mut map, "min", Opcode::MathOp(MathOp::Min));
+	insertMop(&mut map, "mode", Opcode::MathOp(MathOp::Mode));
+	insertMop(&mut map, "prod", Opcode::MathOp(MathOp::Prod));
+	insertMop(&mut map, "quantile", Opcode::MathOp(MathOp::Quantile));
+	insertMop(&mut map, "range", Opcode::MathOp(MathOp::Range));
 	insertMop(&mut map, "recip", Opcode::MathOp(MathOp::Recip));
 	insertMop(&mut map, "round0", Opcode::MathOp(MathOp::Round0));
 	insertMop(&mut map, "sd", Opcode::MathOp(MathOp::Sd));
 	insertMop(&mut map, "sign", Opcode::MathOp(MathOp::Sign));
 	insertMop(&mut map, "sin", Opcode::MathOp(MathOp::Sin));
 	insertMop(&mut map, "sinh", Opcode::MathOp(MathOp::Sinh));
+	insertMop(&mut map, "skewness", Opcode::MathOp(MathOp::Skewness));
 	insertMop(&mut map, "sqrt", Opcode::MathOp(MathOp::Sqrt));
+	insertMop(&mut map, "sum", Opcode::MathOp(MathOp::Sum));
+	insertMop(&mut map, "summary", Opcode::MathOp(MathOp::Summary));
 	insertMop(&mut map, "tan", Opcode::MathOp(MathOp::Tan));
 	insertMop(&mut map, "tanh", Opcode::MathOp(MathOp::Tanh));
 	insertMop(&mut map, "trunc", Opcode::MathOp(MathOp::Trunc));
 	insertMop(&mut map, "var", Opcode::MathOp(MathOp::Var));
+	insertMop(&mut map, "wmean", Opcode::MathOp(MathOp::Wmean));
+
+	/* 	== LINEAR ALGEBRA == */
+	insertMop(&mut map, "chol", Opcode::LinOp(LinOp::Chol));
+	insertMop(&mut map, "det", Opcode::LinOp(LinOp::Det));
+	insertMop(&mut map, "eigen", Opcode::LinOp(LinOp::Eigen));
+	insertMop(&mut map, "inv", Opcode::LinOp(LinOp::Inv));
+	insertMop(&mut map, "matmul", Opcode::LinOp(LinOp::Matmul));
+	insertMop(&mut map, "mrank", Opcode::LinOp(LinOp::Rank));
+	insertMop(&mut map, "qr", Opcode::LinOp(LinOp::Qr));
+	insertMop(&mut map, "solve", Opcode::LinOp(LinOp::Solve));
+	insertMop(&mut map, "svd", Opcode::LinOp(LinOp::Svd));
+	insertMop(&mut map, "trace", Opcode::LinOp(LinOp::Trace));
+	insertMop(&mut map, "transpose", Opcode::LinOp(LinOp::Transpose));
+
+	/* 	== MATRICES == */
+	insertMop(&mut map, "cbind", Opcode::MatOp(MatOp::Cbind));
+	insertMop(&mut map, "col", Opcode::MatOp(MatOp::Col));
+	insertMop(&mut map, "colmeans", Opcode::MatOp(MatOp::Colmeans));
+	insertMop(&mut map, "colsds", Opcode::MatOp(MatOp::Colsds));
+	insertMop(&mut map, "colsums", Opcode::MatOp(MatOp::Colsums));
+	insertMop(&mut map, "diag", Opcode::MatOp(MatOp::Diag));
+	insertMop(&mut map, "dim", Opcode::MatOp(MatOp::Dim));
+	insertMop(&mut map, "eye", Opcode::MatOp(MatOp::Eye));
+	insertMop(&mut map, "mat", Opcode::MatOp(MatOp::Mat));
+	insertMop(&mut map, "ones", Opcode::MatOp(MatOp::Ones));
+	insertMop(&mut map, "rbind", Opcode::MatOp(MatOp::Rbind));
+	insertMop(&mut map, "reshape", Opcode::MatOp(MatOp::Reshape));
+	insertMop(&mut map, "row", Opcode::MatOp(MatOp::Row));
+	insertMop(&mut map, "rowmeans", Opcode::MatOp(MatOp::Rowmeans));
+	insertMop(&mut map, "rowsds", Opcode::MatOp(MatOp::Rowsds));
+	insertMop(&mut map, "rowsums", Opcode::MatOp(MatOp::Rowsums));
+	insertMop(&mut map, "zeros", Opcode::MatOp(MatOp::Zeros));
+
+	/* 	== INDEXING == */
+	insertMop(&mut map, "dict", Opcode::IndexOp(IndexOp::Dict));
+	insertMop(&mut map, "get", Opcode::IndexOp(IndexOp::Get));
+	insertMop(&mut map, "keys", Opcode::IndexOp(IndexOp::Keys));
+
+	/* 	== FRAMES == */
+	insertMop(&mut map, "add_col", Opcode::FrameOp(FrameOp::AddCol));
+	insertMop(&mut map, "drop_col", Opcode::FrameOp(FrameOp::DropCol));
+	insertMop(&mut map, "frame", Opcode::FrameOp(FrameOp::Frame));
+	insertMop(&mut map, "head", Opcode::FrameOp(FrameOp::Head));
+	insertMop(&mut map, "ncol", Opcode::FrameOp(FrameOp::Ncol));
+	insertMop(&mut map, "nrow", Opcode::FrameOp(FrameOp::Nrow));
+	insertMop(&mut map, "subset", Opcode::FrameOp(FrameOp::Subset));
+	insertMop(&mut map, "tail", Opcode::FrameOp(FrameOp::Tail));
+
+	/* 	== FACTORS == */
+	insertMop(&mut map, "as_str", Opcode::FactorOp(FactorOp::AsStr));
+	insertMop(&mut map, "droplevels", Opcode::FactorOp(FactorOp::Droplevels));
+	insertMop(&mut map, "factor", Opcode::FactorOp(FactorOp::Factor));
+	insertMop(&mut map, "levels", Opcode::FactorOp(FactorOp::Levels));
+	insertMop(&mut map, "relevel", Opcode::FactorOp(FactorOp::Relevel));
+
+	/* 	== FILES == */
+	insertMop(&mut map, "read_csv", Opcode::IoOp(IoOp::ReadCsv));
+	insertMop(&mut map, "read_delim", Opcode::IoOp(IoOp::ReadDelim));
+	insertMop(&mut map, "read_tsv", Opcode::IoOp(IoOp::ReadTsv));
+	insertMop(&mut map, "write_csv", Opcode::IoOp(IoOp::WriteCsv));
+	insertMop(&mut map, "write_delim", Opcode::IoOp(IoOp::WriteDelim));
+	insertMop(&mut map, "write_tsv", Opcode::IoOp(IoOp::WriteTsv));
+
+	/* 	== DISTRIBUTIONS == */
+	insertMop(&mut map, "dbeta", Opcode::DistOp(DistOp::Density(Dist::Beta)));
+	insertMop(&mut map, "dbinom", Opcode::DistOp(DistOp::Density(Dist::Binom)));
+	insertMop(&mut map, "dchisq", Opcode::DistOp(DistOp::Density(Dist::Chisq)));
+	insertMop(&mut map, "dexp", Opcode::DistOp(DistOp::Density(Dist::Exp)));
+	insertMop(&mut map, "df", Opcode::DistOp(DistOp::Density(Dist::F)));
+	insertMop(&mut map, "dgamma", Opcode::DistOp(DistOp::Density(Dist::Gamma)));
+	insertMop(&mut map, "dgeom", Opcode::DistOp(DistOp::Density(Dist::Geom)));
+	insertMop(&mut map, "dnorm", Opcode::DistOp(DistOp::Density(Dist::Norm)));
+	insertMop(&mut map, "dpois", Opcode::DistOp(DistOp::Density(Dist::Pois)));
+	insertMop(&mut map, "dt", Opcode::DistOp(DistOp::Density(Dist::T)));
+	insertMop(&mut map, "dunif", Opcode::DistOp(DistOp::Density(Dist::Unif)));
+
+	insertMop(&mut map, "pbeta", Opcode::DistOp(DistOp::Cdf(Dist::Beta)));
+	insertMop(&mut map, "pbinom", Opcode::DistOp(DistOp::Cdf(Dist::Binom)));
+	insertMop(&mut map, "pchisq", Opcode::DistOp(DistOp::Cdf(Dist::Chisq)));
+	insertMop(&mut map, "pexp", Opcode::DistOp(DistOp::Cdf(Dist::Exp)));
+	insertMop(&mut map, "pf", Opcode::DistOp(DistOp::Cdf(Dist::F)));
+	insertMop(&mut map, "pgamma", Opcode::DistOp(DistOp::Cdf(Dist::Gamma)));
+	insertMop(&mut map, "pgeom", Opcode::DistOp(DistOp::Cdf(Dist::Geom)));
+	insertMop(&mut map, "pnorm", Opcode::DistOp(DistOp::Cdf(Dist::Norm)));
+	insertMop(&mut map, "ppois", Opcode::DistOp(DistOp::Cdf(Dist::Pois)));
+	insertMop(&mut map, "pt", Opcode::DistOp(DistOp::Cdf(Dist::T)));
+	insertMop(&mut map, "punif", Opcode::DistOp(DistOp::Cdf(Dist::Unif)));
+
+	insertMop(&mut map, "qbeta", Opcode::DistOp(DistOp::Quantile(Dist::Beta)));
+	insertMop(&mut map, "qbinom", Opcode::DistOp(DistOp::Quantile(Dist::Binom)));
+	insertMop(&mut map, "qchisq", Opcode::DistOp(DistOp::Quantile(Dist::Chisq)));
+	insertMop(&mut map, "qexp", Opcode::DistOp(DistOp::Quantile(Dist::Exp)));
+	insertMop(&mut map, "qf", Opcode::DistOp(DistOp::Quantile(Dist::F)));
+	insertMop(&mut map, "qgamma", Opcode::DistOp(DistOp::Quantile(Dist::Gamma)));
+	insertMop(&mut map, "qgeom", Opcode::DistOp(DistOp::Quantile(Dist::Geom)));
+	insertMop(&mut map, "qnorm", Opcode::DistOp(DistOp::Quantile(Dist::Norm)));
+	insertMop(&mut map, "qpois", Opcode::DistOp(DistOp::Quantile(Dist::Pois)));
+	insertMop(&mut map, "qt", Opcode::DistOp(DistOp::Quantile(Dist::T)));
+	insertMop(&mut map, "qunif", Opcode::DistOp(DistOp::Quantile(Dist::Unif)));
+
+	insertMop(&mut map, "rbeta", Opcode::DistOp(DistOp::Random(Dist::Beta)));
+	insertMop(&mut map, "rbinom", Opcode::DistOp(DistOp::Random(Dist::Binom)));
+	insertMop(&mut map, "rchisq", Opcode::DistOp(DistOp::Random(Dist::Chisq)));
+	insertMop(&mut map, "rexp", Opcode::DistOp(DistOp::Random(Dist::Exp)));
+	insertMop(&mut map, "rf", Opcode::DistOp(DistOp::Random(Dist::F)));
+	insertMop(&mut map, "rgamma", Opcode::DistOp(DistOp::Random(Dist::Gamma)));
+	insertMop(&mut map, "rgeom", Opcode::DistOp(DistOp::Random(Dist::Geom)));
+	insertMop(&mut map, "rnorm", Opcode::DistOp(DistOp::Random(Dist::Norm)));
+	insertMop(&mut map, "rpois", Opcode::DistOp(DistOp::Random(Dist::Pois)));
+	insertMop(&mut map, "rt", Opcode::DistOp(DistOp::Random(Dist::T)));
+	insertMop(&mut map, "runif", Opcode::DistOp(DistOp::Random(Dist::Unif)));
+
+	/* 	== MISSING VALUES == */
+	insertMop(&mut map, "drop_na", Opcode::NaOp(NaOp::DropNa));
+	insertMop(&mut map, "fill_na", Opcode::NaOp(NaOp::FillNa));
+	insertMop(&mut map, "is_na", Opcode::NaOp(NaOp::IsNa));
+	insertMop(&mut map, "na_rm", Opcode::NaOp(NaOp::NaRm));
+
+	/* 	== RANDOM NUMBERS == */
+	insertMop(&mut map, "choose", Opcode::RandOp(RandOp::Choose));
+	insertMop(&mut map, "sample", Opcode::RandOp(RandOp::Sample));
+	insertMop(&mut map, "seed", Opcode::RandOp(RandOp::Seed));
+	insertMop(&mut map, "shuffle", Opcode::RandOp(RandOp::Shuffle));
+
+	/* 	== SORTING == */
+	insertMop(&mut map, "duplicated", Opcode::SortOp(SortOp::Duplicated));
+	insertMop(&mut map, "order", Opcode::SortOp(SortOp::Order));
+	insertMop(&mut map, "rank", Opcode::SortOp(SortOp::Rank));
+	insertMop(&mut map, "rev", Opcode::SortOp(SortOp::Rev));
+	insertMop(&mut map, "sort", Opcode::SortOp(SortOp::Sort));
+	insertMop(&mut map, "sort_by", Opcode::SortOp(SortOp::SortBy));
+	insertMop(&mut map, "sort_desc", Opcode::SortOp(SortOp::SortDesc));
+	insertMop(&mut map, "unique", Opcode::SortOp(SortOp::Unique));
+
+	/* 	== STATISTICS == */
+	insertMop(&mut map, "chisq_test", Opcode::StatOp(StatOp::ChisqTest));
+	insertMop(&mut map, "cor_test", Opcode::StatOp(StatOp::CorTest));
+	insertMop(&mut map, "ks_test", Opcode::StatOp(StatOp::KsTest));
+	insertMop(&mut map, "lm", Opcode::StatOp(StatOp::Lm));
+	insertMop(&mut map, "predict", Opcode::StatOp(StatOp::Predict));
+	insertMop(&mut map, "prop_test", Opcode::StatOp(StatOp::PropTest));
+	insertMop(&mut map, "ttest", Opcode::StatOp(StatOp::Ttest));
+	insertMop(&mut map, "wilcox_test", Opcode::StatOp(StatOp::WilcoxTest));
 
 
 	/* 	== AUTO OPERATIONS == */
 	insertMop(&mut map, "input", Opcode::AutoOp(AutoOp::Input));
 
-	return Env { dict: map, inst: vec![], ip: 0 }
+	return Env { dict: map, inst: vec![], ip: 0, reuse: false }
 }
 
 
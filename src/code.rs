@@ -22,8 +22,20 @@ pub enum Opcode {
     Comb1(Comb1),
     Comb2(Comb2),
     Comb3(Comb3),
+    Comb4(Comb4),
+    DistOp(DistOp),
+    FactorOp(FactorOp),
+    FrameOp(FrameOp),
+    IndexOp(IndexOp),
+    IoOp(IoOp),
+    LinOp(LinOp),
+    MatOp(MatOp),
+    StatOp(StatOp),
     UnOp(UnOp),
     MathOp(MathOp),
+    NaOp(NaOp),
+    RandOp(RandOp),
+    SortOp(SortOp),
     AutoOp(AutoOp),
     Set(Box<String>),
     SetProg(Box<String>),
@@ -68,12 +80,14 @@ pub enum BoolOp {
     Eqt,
     Neq,
     And,
-    Or
+    Or,
+    Xor
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Comb1 {
     Do,
+    Loop,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -81,11 +95,31 @@ pub enum Comb2 {
     Ifthen,
     Dip,
     Cleave,
+    Each,
+    Filter,
+    Map,
+    Reduce,
+    Times,
+    Try,
+    While,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Comb3 {
+    Fold,
+    Groupby,
     Ifelse,
+    Primrec,
+    Scan,
+    Tailrec,
+    Zipwith,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Comb4 {
+    Binrec,
+    Genrec,
+    Linrec,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -93,13 +127,114 @@ pub enum ConstCode {
     Int(i32),
     True,
     False,
+    Na,
     Null
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum Dist {
+    Beta,
+    Binom,
+    Chisq,
+    Exp,
+    F,
+    Gamma,
+    Geom,
+    Norm,
+    Pois,
+    T,
+    Unif,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DistOp {
+    Cdf(Dist),
+    Density(Dist),
+    Quantile(Dist),
+    Random(Dist),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FactorOp {
+    AsStr,
+    Droplevels,
+    Factor,
+    Levels,
+    Relevel,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameOp {
+    AddCol,
+    DropCol,
+    Frame,
+    Head,
+    Ncol,
+    Nrow,
+    Subset,
+    Tail,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum GenOp {
+    Break,
     NewLine,
     AddLine(u8),
+    Loc(u32),       // compile-time only: span index of the following opcode
+    Spans(u32),     // first opcode of a block: id of its span table in 'Vm.spans'
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndexOp {
+    Dict,
+    Get,
+    Keys,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum IoOp {
+    ReadCsv,
+    ReadDelim,
+    ReadTsv,
+    WriteCsv,
+    WriteDelim,
+    WriteTsv,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LinOp {
+    Chol,
+    Det,
+    Eigen,
+    Inv,
+    Matmul,
+    Qr,
+    Rank,
+    Solve,
+    Svd,
+    Trace,
+    Transpose,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MatOp {
+    Cbind,
+    Col,
+    Colmeans,
+    Colsds,
+    Colsums,
+    Diag,
+    Dim,
+    Eye,
+    Mat,
+    Ones,
+    Rbind,
+    Reshape,
+    Row,
+    Rowmeans,
+    Rowsds,
+    Rowsums,
+    Zeros,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -113,29 +248,75 @@ pub enum MathOp {
     Atanh,
     Cbrt,
     Ceil,
+    Cor,
     Cos,
     Cosh,
+    Cov,
+    Cummax,
+    Cummin,
+    Cumprod,
+    Cumsum,
     Exp,
     Floor,
     Fract,
+    Iqr,
+    Kurtosis,
     Ln,
     Log10,
     Log2,
+    Mad,
     Max,
     Mean,
+    Median,
     Min,
+    Mode,
     Neg,
+    Prod,
+    Quantile,
+    Range,
     Recip,
     Round0,
     Sd,
     Sign,
     Sin,
     Sinh,
+    Skewness,
     Sqrt,
+    Sum,
+    Summary,
     Tan,
     Tanh,
     Trunc,
-    Var
+    Var,
+    Wmean
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NaOp {
+    DropNa,
+    FillNa,
+    IsNa,
+    NaRm,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RandOp {
+    Choose,
+    Sample,
+    Seed,
+    Shuffle,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SortOp {
+    Duplicated,
+    Order,
+    Rank,
+    Rev,
+    Sort,
+    SortBy,
+    SortDesc,
+    Unique,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -153,9 +334,23 @@ pub enum StackOp {
     Clear
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatOp {
+    ChisqTest,
+    CorTest,
+    KsTest,
+    Lm,
+    Predict,
+    PropTest,
+    Ttest,
+    WilcoxTest,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum UnOp {
-    Print
+    Not,
+    Print,
+    Throw
 }
 
 /*  == PRINTING  ==   */
@@ -176,11 +371,23 @@ pub fn sprint_opcode(code: &Opcode) -> String {
         Opcode::BinOp(b)    => { return format!("binop {} '{}'", sprint_binop(b), sprint_binop_sym(b)) },
         Opcode::BoolOp(b)   => { return format!("boolop {} '{}'", sprint_boolop(b), sprint_boolop_sym(b)) },
         Opcode::StackOp(s)  => { return format!("stackop {}", sprint_stackop(s)) },
+        Opcode::FactorOp(f) => { return format!("factorop {}", sprint_factorop(f)) },
+        Opcode::FrameOp(f)  => { return format!("frameop {}", sprint_frameop(f)) },
+        Opcode::IndexOp(i)  => { return format!("indexop {}", sprint_indexop(i)) },
+        Opcode::IoOp(i)     => { return format!("ioop {}", sprint_ioop(i)) },
+        Opcode::LinOp(l)    => { return format!("linop {}", sprint_linop(l)) },
+        Opcode::MatOp(m)    => { return format!("matop {}", sprint_matop(m)) },
+        Opcode::StatOp(s)   => { return format!("statop {}", sprint_statop(s)) },
         Opcode::MathOp(m)   => { return format!("mathop {}", sprint_mathop(m)) },
+        Opcode::NaOp(n)     => { return format!("naop {}", sprint_naop(n)) },
+        Opcode::RandOp(r)   => { return format!("randop {}", sprint_randop(r)) },
+        Opcode::SortOp(s)   => { return format!("sortop {}", sprint_sortop(s)) },
         Opcode::UnOp(u)     => { return format!("unop {}", sprint_unop(u)) },
         Opcode::Comb1(c)    => { return format!("comb1 {}", sprint_comb1(c)) },
         Opcode::Comb2(c)    => { return format!("comb2 {}", sprint_comb2(c)) },
         Opcode::Comb3(c)    => { return format!("comb3 {}", sprint_comb3(c)) },
+        Opcode::Comb4(c)    => { return format!("comb4 {}", sprint_comb4(c)) },
+        Opcode::DistOp(d)   => { return format!("distop {}", sprint_distop(d)) },
         Opcode::AutoOp(a)   => { return format!("autoop {}", sprint_autoop(a)) },
         Opcode::Set(s)      => { return format!("set '{}'", *s) },
         Opcode::SetProg(s)  => { return format!("set '{}'", *s) },
@@ -188,6 +395,11 @@ pub fn sprint_opcode(code: &Opcode) -> String {
     }
 }
 
+/* First line of 'sprint_opcode', without the body of quotes and programs. */
+pub fn sprint_opcode_short(code: &Opcode) -> String {
+    return sprint_opcode(code).lines().next().unwrap_or("").trim().to_string()
+}
+
 pub fn sprint_autoop(code: &AutoOp) -> String {
     match code {
         AutoOp::Input       => { return format!("input") },
@@ -236,6 +448,7 @@ pub fn sprint_boolop(code: &BoolOp) -> String {
         BoolOp::Neq           => { return format!("neq") }, 
         BoolOp::And           => { return format!("and") },
         BoolOp::Or            => { return format!("or") },
+        BoolOp::Xor           => { return format!("xor") },
     }
 }
 
@@ -249,12 +462,14 @@ pub fn sprint_boolop_sym(code: &BoolOp) -> String {
         BoolOp::Neq           => { return format!("!=") }, 
         BoolOp::And           => { return format!("&") },
         BoolOp::Or            => { return format!("|") },
+        BoolOp::Xor           => { return format!("xor") },
     }
 }
 
 pub fn sprint_comb1(code: &Comb1) -> String {
     match code {
         Comb1::Do      => { return format!("do") },
+        Comb1::Loop    => { return format!("loop") },
     }
 }
 
@@ -262,13 +477,34 @@ pub fn sprint_comb2(code: &Comb2) -> String {
     match code {
         Comb2::Ifthen   => { return format!("ifthen") },
         Comb2::Dip      => { return format!("dip") },
-        Comb2::Cleave   => { return format!("cleave") }
+        Comb2::Cleave   => { return format!("cleave") },
+        Comb2::Each     => { return format!("each") },
+        Comb2::Filter   => { return format!("filter") },
+        Comb2::Map      => { return format!("map") },
+        Comb2::Reduce   => { return format!("reduce") },
+        Comb2::Times    => { return format!("times") },
+        Comb2::Try      => { return format!("try") },
+        Comb2::While    => { return format!("while") }
     }
 }
 
 pub fn sprint_comb3(code: &Comb3) -> String {
     match code {
+        Comb3::Fold        => { return format!("fold") },
+        Comb3::Groupby     => { return format!("groupby") },
         Comb3::Ifelse      => { return format!("ifelse") },
+        Comb3::Primrec     => { return format!("primrec") },
+        Comb3::Scan        => { return format!("scan") },
+        Comb3::Tailrec     => { return format!("tailrec") },
+        Comb3::Zipwith     => { return format!("zipwith") },
+    }
+}
+
+pub fn sprint_comb4(code: &Comb4) -> String {
+    match code {
+        Comb4::Binrec      => { return format!("binrec") },
+        Comb4::Genrec      => { return format!("genrec") },
+        Comb4::Linrec      => { return format!("linrec") },
     }
 }
 
@@ -278,6 +514,7 @@ pub fn sprint_constcode(code: &ConstCode) -> String {
         //ConstCode::Num(f)      => { return format!("num '{}'", f) },
         ConstCode::True        => { return format!("bool 'true'") },
         ConstCode::False       => { return format!("bool 'false'") },
+        ConstCode::Na          => { return format!("na 'NA'") },
         ConstCode::Null        => { return format!("none ") },
     }
 }
@@ -287,14 +524,123 @@ pub fn sprint_constval(code: &ConstCode) -> String {
         ConstCode::Int(i)      => { return format!("{}", i) },
         ConstCode::True        => { return format!("true") },
         ConstCode::False       => { return format!("false") },
+        ConstCode::Na          => { return format!("NA") },
         ConstCode::Null        => { return format!("none") },
     }
 }
 
+pub fn sprint_dist(code: &Dist) -> String {
+    match code {
+        Dist::Beta          => { return format!("beta") },
+        Dist::Binom         => { return format!("binom") },
+        Dist::Chisq         => { return format!("chisq") },
+        Dist::Exp           => { return format!("exp") },
+        Dist::F             => { return format!("f") },
+        Dist::Gamma         => { return format!("gamma") },
+        Dist::Geom          => { return format!("geom") },
+        Dist::Norm          => { return format!("norm") },
+        Dist::Pois          => { return format!("pois") },
+        Dist::T             => { return format!("t") },
+        Dist::Unif          => { return format!("unif") },
+    }
+}
+
+pub fn sprint_distop(code: &DistOp) -> String {
+    match code {
+        DistOp::Cdf(d)      => { return format!("p{}", sprint_dist(d)) },
+        DistOp::Density(d)  => { return format!("d{}", sprint_dist(d)) },
+        DistOp::Quantile(d) => { return format!("q{}", sprint_dist(d)) },
+        DistOp::Random(d)   => { return format!("r{}", sprint_dist(d)) },
+    }
+}
+
 pub fn sprint_genop(code: &GenOp) -> String {
     match code {
+        GenOp::Break        => { return format!("break") },
         GenOp::NewLine      => { return format!("newline") },
-        GenOp::AddLine(u)   => { return format!("addline'{}'", u)}  
+        GenOp::AddLine(u)   => { return format!("addline'{}'", u)},
+        GenOp::Loc(u)       => { return format!("loc '{}'", u) },
+        GenOp::Spans(u)     => { return format!("spans '{}'", u) }
+    }
+}
+
+pub fn sprint_factorop(code: &FactorOp) -> String {
+    match code {
+        FactorOp::AsStr         => { return format!("as_str") },
+        FactorOp::Droplevels    => { return format!("droplevels") },
+        FactorOp::Factor        => { return format!("factor") },
+        FactorOp::Levels        => { return format!("levels") },
+        FactorOp::Relevel       => { return format!("relevel") },
+    }
+}
+
+pub fn sprint_frameop(code: &FrameOp) -> String {
+    match code {
+        FrameOp::AddCol     => { return format!("add_col") },
+        FrameOp::DropCol    => { return format!("drop_col") },
+        FrameOp::Frame      => { return format!("frame") },
+        FrameOp::Head       => { return format!("head") },
+        FrameOp::Ncol       => { return format!("ncol") },
+        FrameOp::Nrow       => { return format!("nrow") },
+        FrameOp::Subset     => { return format!("subset") },
+        FrameOp::Tail       => { return format!("tail") },
+    }
+}
+
+pub fn sprint_indexop(code: &IndexOp) -> String {
+    match code {
+        IndexOp::Dict       => { return format!("dict") },
+        IndexOp::Get        => { return format!("get") },
+        IndexOp::Keys       => { return format!("keys") },
+    }
+}
+
+pub fn sprint_ioop(code: &IoOp) -> String {
+    match code {
+        IoOp::ReadCsv       => { return format!("read_csv") },
+        IoOp::ReadDelim     => { return format!("read_delim") },
+        IoOp::ReadTsv       => { return format!("read_tsv") },
+        IoOp::WriteCsv      => { return format!("write_csv") },
+        IoOp::WriteDelim    => { return format!("write_delim") },
+        IoOp::WriteTsv      => { return format!("write_tsv") },
+    }
+}
+
+pub fn sprint_linop(code: &LinOp) -> String {
+    match code {
+        LinOp::Chol         => { return format!("chol") },
+        LinOp::Det          => { return format!("det") },
+        LinOp::Eigen        => { return format!("eigen") },
+        LinOp::Inv          => { return format!("inv") },
+        LinOp::Matmul       => { return format!("matmul") },
+        LinOp::Qr           => { return format!("qr") },
+        LinOp::Rank         => { return format!("mrank") },
+        LinOp::Solve        => { return format!("solve") },
+        LinOp::Svd          => { return format!("svd") },
+        LinOp::Trace        => { return format!("trace") },
+        LinOp::Transpose    => { return format!("transpose") },
+    }
+}
+
+pub fn sprint_matop(code: &MatOp) -> String {
+    match code {
+        MatOp::Cbind        => { return format!("cbind") },
+        MatOp::Col          => { return format!("col") },
+        MatOp::Colmeans     => { return format!("colmeans") },
+        MatOp::Colsds       => { return format!("colsds") },
+        MatOp::Colsums      => { return format!("colsums") },
+        MatOp::Diag         => { return format!("diag") },
+        MatOp::Dim          => { return format!("dim") },
+        MatOp::Eye          => { return format!("eye") },
+        MatOp::Mat          => { return format!("mat") },
+        MatOp::Ones         => { return format!("ones") },
+        MatOp::Rbind        => { return format!("rbind") },
+        MatOp::Reshape      => { return format!("reshape") },
+        MatOp::Row          => { return format!("row") },
+        MatOp::Rowmeans     => { return format!("rowmeans") },
+        MatOp::Rowsds       => { return format!("rowsds") },
+        MatOp::Rowsums      => { return format!("rowsums") },
+        MatOp::Zeros        => { return format!("zeros") },
     }
 }
 
@@ -309,29 +655,78 @@ pub fn sprint_mathop(code: &MathOp) -> String {
         MathOp::Atanh       => { return format!("atanh") },
         MathOp::Cbrt        => { return format!("cbrt") },
         MathOp::Ceil        => { return format!("ceil") },
+        MathOp::Cor         => { return format!("cor") },
         MathOp::Cos         => { return format!("cos") },
         MathOp::Cosh        => { return format!("cosh") },
+        MathOp::Cov         => { return format!("cov") },
+        MathOp::Cummax      => { return format!("cummax") },
+        MathOp::Cummin      => { return format!("cummin") },
+        MathOp::Cumprod     => { return format!("cumprod") },
+        MathOp::Cumsum      => { return format!("cumsum") },
         MathOp::Exp         => { return format!("exp") },
         MathOp::Floor       => { return format!("floor") },
         MathOp::Fract       => { return format!("fract") },
+        MathOp::Iqr         => { return format!("iqr") },
+        MathOp::Kurtosis    => { return format!("kurtosis") },
         MathOp::Ln          => { return format!("ln") },
         MathOp::Log10       => { return format!("log10") },
         MathOp::Log2        => { return format!("log2") },
+        MathOp::Mad         => { return format!("mad") },
         MathOp::Max         => { return format!("max") },
         MathOp::Mean        => { return format!("mean") },
+        MathOp::Median      => { return format!("median") },
         MathOp::Min         => { return format!("min") },
+        MathOp::Mode        => { return format!("mode") },
         MathOp::Neg         => { return format!("neg") },
+        MathOp::Prod        => { return format!("prod") },
+        MathOp::Quantile    => { return format!("quantile") },
+        MathOp::Range       => { return format!("range") },
         MathOp::Recip       => { return format!("recip") },
         MathOp::Round0      => { return format!("round0") },
         MathOp::Sd          => { return format!("sd") },
         MathOp::Sign        => { return format!("sign") },
         MathOp::Sin         => { return format!("sin") },
         MathOp::Sinh        => { return format!("sinh") },
+        MathOp::Skewness    => { return format!("skewness") },
         MathOp::Sqrt        => { return format!("sqrt") },
+        MathOp::Sum         => { return format!("sum") },
+        MathOp::Summary     => { return format!("summary") },
         MathOp::Tan         => { return format!("tan") },
         MathOp::Tanh        => { return format!("tanh") },
         MathOp::Trunc       => { return format!("trunc") },
         MathOp::Var         => { return format!("var") },
+        MathOp::Wmean       => { return format!("wmean") },
+    }
+}
+
+pub fn sprint_naop(code: &NaOp) -> String {
+    match code {
+        NaOp::DropNa        => { return format!("drop_na") },
+        NaOp::FillNa        => { return format!("fill_na") },
+        NaOp::IsNa          => { return format!("is_na") },
+        NaOp::NaRm          => { return format!("na_rm") },
+    }
+}
+
+pub fn sprint_randop(code: &RandOp) -> String {
+    match code {
+        RandOp::Choose      => { return format!("choose") },
+        RandOp::Sample      => { return format!("sample") },
+        RandOp::Seed        => { return format!("seed") },
+        RandOp::Shuffle     => { return format!("shuffle") },
+    }
+}
+
+pub fn sprint_sortop(code: &SortOp) -> String {
+    match code {
+        SortOp::Duplicated  => { return format!("duplicated") },
+        SortOp::Order       => { return format!("order") },
+        SortOp::Rank        => { return format!("rank") },
+        SortOp::Rev         => { return format!("rev") },
+        SortOp::Sort        => { return format!("sort") },
+        SortOp::SortBy      => { return format!("sort_by") },
+        SortOp::SortDesc    => { return format!("sort_desc") },
+        SortOp::Unique      => { return format!("unique") },
     }
 }
 
@@ -351,9 +746,24 @@ pub fn sprint_stackop(code: &StackOp) -> String {
     }
 }
 
+pub fn sprint_statop(code: &StatOp) -> String {
+    match code {
+        StatOp::ChisqTest   => { return format!("chisq_test") },
+        StatOp::CorTest     => { return format!("cor_test") },
+        StatOp::KsTest      => { return format!("ks_test") },
+        StatOp::Lm          => { return format!("lm") },
+        StatOp::Predict     => { return format!("predict") },
+        StatOp::PropTest    => { return format!("prop_test") },
+        StatOp::Ttest       => { return format!("ttest") },
+        StatOp::WilcoxTest  => { return format!("wilcox_test") },
+    }
+}
+
 pub fn sprint_unop(code: &UnOp) -> String {
     match code {
+        UnOp::Not           => { return format!("not") },
         UnOp::Print         => { return format!("print") },
+        UnOp::Throw         => { return format!("throw") },
     }
 }
 
@@ -414,7 +824,7 @@ pub fn get_prec_bool(op: &BoolOp) -> u8 {
         BoolOp::Grt | BoolOp::Lst | BoolOp::Gte | BoolOp::Lte  => { return 13 },
         BoolOp::Eqt | BoolOp::Neq  => { return 12 },
         BoolOp::And => { return 11 },
-        BoolOp::Or  => { return 10 },
+        BoolOp::Or | BoolOp::Xor  => { return 10 },
         _ => { return 15 }
     }
 }
\ No newline at end of file
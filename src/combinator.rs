@@ -1,19 +1,26 @@
 /* combinator
  * ---------- */
-use crate::code::{Comb1, Comb2, Comb3, sprint_comb1, sprint_comb2, sprint_comb3};
+use crate::code::{Comb1, Comb2, Comb3, Comb4, Opcode, sprint_comb1, sprint_comb2, sprint_comb3, sprint_comb4};
 use crate::code::Comb1::*;
 use crate::code::Comb2::*;
 use crate::code::Comb3::*;
+use crate::code::Comb4::*;
+use crate::error::{ErrorType, StaxError, error};
+use crate::factor::{Factor, to_factor};
+use crate::frame::{Frame, column_len, take_rows};
 use crate::vm::Vm;
-use crate::words::{Word, BoolResult};
+use crate::words::{Word, BoolResult, bool_word, num_word, str_word, toF64, tighten};
+
+use ndarray::Array1;
 
 
 pub fn do_comb1(vm: &mut Vm, op: Comb1) -> BoolResult {
 	match vm.stack.pop() {
 		Some(arg) => match op {
-			Do => { return vm.exec_word(arg) }
+			Do => { return vm.exec_word(arg) },
+			Loop => { return do_loop(vm, arg) }
 		},
-		None => { return Err(format!("Combinator '{}' requires at least 1 item on top of the stack. None found.", sprint_comb1(&op))) }
+		None => { return error(ErrorType::Underflow, format!("Combinator '{}' requires at least 1 item on top of the stack. None found.", sprint_comb1(&op))) }
 	}
 }
 
@@ -29,11 +36,18 @@ pub fn do_comb2(vm: &mut Vm, op: Comb2) -> BoolResult {
 					}
 					return vm.push_const(arg1);
 				},
-				Cleave => { return do_cleave(vm, arg1, arg2) },
+				Cleave => { return vm.without_loops(|vm| do_cleave(vm, arg1, arg2)) },
+				Each => { return do_each(vm, arg1, arg2) },
+				Filter => { return do_filter(vm, arg1, arg2) },
+				Map => { return do_map(vm, arg1, arg2) },
+				Reduce => { return do_reduce(vm, arg1, arg2) },
+				Times => { return do_times(vm, arg1, arg2) },
+				Try => { return do_try(vm, arg1, arg2) },
+				While => { return do_while(vm, arg1, arg2) },
 			},
-			None => { return Err(format!("Combinator '{}' requires at least 2 items on top of the stack. Only 1 found.", sprint_comb2(&op))) }
+			None => { return error(ErrorType::Underflow, format!("Combinator '{}' requires at least 2 items on top of the stack. Only 1 found.", sprint_comb2(&op))) }
 		},
-		None => { return Err(format!("Combinator '{}' requires at least 2 items on top of the stack. None found.", sprint_comb2(&op))) }
+		None => { return error(ErrorType::Underflow, format!("Combinator '{}' requires at least 2 items on top of the stack. None found.", sprint_comb2(&op))) }
 	}
 }
 
@@ -42,13 +56,33 @@ pub fn do_comb3(vm: &mut Vm, op: Comb3) -> BoolResult {
 		Some(arg3) => match vm.stack.pop() {
 			Some(arg2) => match vm.stack.pop() {
 				Some(arg1) => match op {
+					Fold => { return do_fold(vm, arg1, arg2, arg3, false) },
+					Groupby => { return do_groupby(vm, arg1, arg2, arg3) },
 					Ifelse => { return do_ifelse(vm, arg1, arg2, arg3) },
+					Scan => { return do_fold(vm, arg1, arg2, arg3, true) },
+					Zipwith => { return do_zipwith(vm, arg1, arg2, arg3) },
+					Primrec => { return vm.without_loops(|vm| do_primrec(vm, arg1, arg2, arg3)) },
+					Tailrec => { return vm.without_loops(|vm| do_tailrec(vm, arg1, arg2, arg3)) },
 				},
-				None => { return Err(format!("Combinator '{}' requires at least 3 items on the stack. Only 2 found.", sprint_comb3(&op))) }
+				None => { return error(ErrorType::Underflow, format!("Combinator '{}' requires at least 3 items on the stack. Only 2 found.", sprint_comb3(&op))) }
 			},
-			None => { return Err(format!("Combinator '{}' requires at least 3 items on the stack. Only 1 found.", sprint_comb3(&op))) }
+			None => { return error(ErrorType::Underflow, format!("Combinator '{}' requires at least 3 items on the stack. Only 1 found.", sprint_comb3(&op))) }
 		},
-		None => { return Err(format!("Combinator '{}' requires at least 3 items on the stack. None found.", sprint_comb3(&op))) }
+		None => { return error(ErrorType::Underflow, format!("Combinator '{}' requires at least 3 items on the stack. None found.", sprint_comb3(&op))) }
+	}
+}
+
+pub fn do_comb4(vm: &mut Vm, op: Comb4) -> BoolResult {
+	let n = vm.stack.len();
+	if n < 4 { return error(ErrorType::Underflow, format!("Combinator '{}' requires at least 4 items on the stack. Only {} found.", sprint_comb4(&op), n)) }
+	let arg4 = vm.stack.pop().unwrap();
+	let arg3 = vm.stack.pop().unwrap();
+	let arg2 = vm.stack.pop().unwrap();
+	let arg1 = vm.stack.pop().unwrap();
+	match op {
+		Binrec => { return vm.without_loops(|vm| do_binrec(vm, &arg1, &arg2, &arg3, &arg4)) },
+		Genrec => { return vm.without_loops(|vm| do_genrec(vm, arg1, arg2, arg3, arg4)) },
+		Linrec => { return vm.without_loops(|vm| do_linrec(vm, &arg1, &arg2, &arg3, &arg4)) },
 	}
 }
 
@@ -56,7 +90,7 @@ pub fn do_cleave(vm: &mut Vm, fun1: Word, fun2: Word) -> BoolResult {
 	let top: Word;
 	match vm.stack.last() {
 		Some(x) => { top = x.clone() },
-		None 	=> { return Err(format!("'cleave' requires at least 3 items on top of the stack. Only 2 found.")) }
+		None 	=> { return error(ErrorType::Underflow, format!("'cleave' requires at least 3 items on top of the stack. Only 2 found.")) }
 	}
 	match vm.exec_word(fun1) {
 		Ok(_) 	=> { },
@@ -72,8 +106,8 @@ pub fn do_cleave(vm: &mut Vm, fun1: Word, fun2: Word) -> BoolResult {
 pub fn do_ifelse(vm: &mut Vm, cond: Word, then: Word, elsew: Word) -> BoolResult {
 	match cond {
 		Word::Bool(b) => {
-			if b { return vm.exec_word(then) }
-			return vm.exec_word(elsew)
+			if b { return vm.exec_tail(then) }
+			return vm.exec_tail(elsew)
 		},
 		Word::Quote(q) => {
 			let result = vm.run_opcodes(q.to_vec());
@@ -81,24 +115,24 @@ pub fn do_ifelse(vm: &mut Vm, cond: Word, then: Word, elsew: Word) -> BoolResult
 				Ok(_) => match vm.stack.pop() {
 					Some(w) => match w {
 						Word::Bool(b) => {
-							if b { return vm.exec_word(then) }
-							return vm.exec_word(elsew)
+							if b { return vm.exec_tail(then) }
+							return vm.exec_tail(elsew)
 						},
-						_ => { return Err(format!("Condition for 'ifelse' did not evaluate to a boolean <true/false> value.")) }
+						_ => { return error(ErrorType::Type, format!("Condition for 'ifelse' did not evaluate to a boolean <true/false> value.")) }
 					},
-					None => { return Err(format!("Condition for 'ifelse' did not evaluate to a value. Expression could not be tested.")) }
+					None => { return error(ErrorType::Arity, format!("Condition for 'ifelse' did not evaluate to a value. Expression could not be tested.")) }
 				},
 				Err(e) => { return Err(e) }
 			}
 		},
-		_ => { return Err(format!("Condition for 'ifelse' did not evaluate to a boolean <true/false> value.")) }
+		_ => { return error(ErrorType::Type, format!("Condition for 'ifelse' did not evaluate to a boolean <true/false> value.")) }
 	}
 }
 
 pub fn do_ifthen(vm: &mut Vm, cond: Word, then: Word) -> BoolResult {
 	match cond {
 		Word::Bool(b) => {
-			if b { return vm.exec_word(then) }
+			if b { return vm.exec_tail(then) }
 			return Ok(false)
 		},
 		Word::Quote(q) => {
@@ -107,18 +141,478 @@ pub fn do_ifthen(vm: &mut Vm, cond: Word, then: Word) -> BoolResult {
 				Ok(_) => match vm.stack.pop() {
 					Some(w) => match w {
 						Word::Bool(b) => {
-							if b { return vm.exec_word(then) }
+							if b { return vm.exec_tail(then) }
 							return Ok(false)
 						},
-						_ => { return Err(format!("Condition for 'ifthen' did not evaluate to a boolean <true/false> value.")) }
+						_ => { return error(ErrorType::Type, format!("Condition for 'ifthen' did not evaluate to a boolean <true/false> value.")) }
 					},
-					None => { return Err(format!("Condition for 'ifthen' did not evaluate to a value. Expression could not be tested.")) }
+					None => { return error(ErrorType::Arity, format!("Condition for 'ifthen' did not evaluate to a value. Expression could not be tested.")) }
 				},
 				Err(e) => { return Err(e) }
 			}
 		},
-		_ => { return Err(format!("Condition for 'ifthen' did not evaluate to a boolean <true/false> value.")) }
+		_ => { return error(ErrorType::Type, format!("Condition for 'ifthen' did not evaluate to a boolean <true/false> value.")) }
+	}
+}
+
+pub fn do_try(vm: &mut Vm, body: Word, handler: Word) -> BoolResult {
+	/* On error the stack is put back as it was before 'body' ran, items it consumed included. */
+	let saved = vm.stack.clone();
+	let nenvs = vm.envs.len();
+	match vm.exec_word(body) {
+		Ok(_) 	=> { return Ok(false) },
+		Err(e) 	=> {
+			vm.stack = saved;
+			vm.envs.truncate(nenvs);
+			vm.ep = nenvs;
+			vm.push_const(e.to_word())?;
+			return vm.exec_word(handler)
+		}
+	}
+}
+
+
+/* 	== LOOPS == */
+fn loop_ops(w: Word, name: &str) -> Result<Vec<Opcode>, StaxError> {
+	match w {
+		Word::Quote(q) | Word::Program(q) => { return Ok(*q) },
+		Word::MacroOp(op) => { return Ok(vec![op]) },
+		_ => { return error(ErrorType::Type, format!("'{}' requires quotes, but found an object of type '{}'.", name, w.sprint_type())) }
+	}
+}
+
+/* Runs the loop 'f' and stops any 'break' from leaving it. Combinators that are not loops
+ * run their quotes through 'Vm::without_loops', so a 'break' always belongs to the innermost
+ * enclosing 'loop', 'times', 'while' or 'each'. */
+fn in_loop<F>(vm: &mut Vm, mut f: F) -> BoolResult where F: FnMut(&mut Vm) -> BoolResult {
+	vm.loops += 1;
+	let result = f(vm);
+	vm.loops -= 1;
+	vm.brk = false;
+	match result {
+		Ok(_) 	=> { return Ok(false) },
+		Err(e) 	=> { return Err(e) }
+	}
+}
+
+pub fn do_loop(vm: &mut Vm, body: Word) -> BoolResult {
+	let mut body = loop_ops(body, "loop")?;
+	return in_loop(vm, |vm| {
+		loop {
+			vm.run_inplace(&mut body)?;
+			if vm.brk { return Ok(false) }
+		}
+	})
+}
+
+pub fn do_times(vm: &mut Vm, n: Word, body: Word) -> BoolResult {
+	let count: usize;
+	match n {
+		Word::Int(_) | Word::Num(_) => {
+			let f = toF64(&n);
+			if f < 0.0 || f.is_nan() { return error(ErrorType::Domain, format!("'times' requires a non-negative count. Given: '{}'.", f)) }
+			count = f as usize;
+		},
+		_ => { return error(ErrorType::Type, format!("'times' requires a numeric count, but found an object of type '{}'.", n.sprint_type())) }
+	}
+	let mut body = loop_ops(body, "times")?;
+	return in_loop(vm, |vm| {
+		for _ in 0..count {
+			vm.run_inplace(&mut body)?;
+			if vm.brk { break }
+		}
+		return Ok(false)
+	})
+}
+
+pub fn do_while(vm: &mut Vm, cond: Word, body: Word) -> BoolResult {
+	let mut cond = loop_ops(cond, "while")?;
+	let mut body = loop_ops(body, "while")?;
+	return in_loop(vm, |vm| {
+		loop {
+			vm.run_inplace(&mut cond)?;
+			if vm.brk { return Ok(false) }
+			match vm.stack.pop() {
+				Some(Word::Bool(b)) => { if !b { return Ok(false) } },
+				Some(_) => { return error(ErrorType::Type, format!("Condition for 'while' did not evaluate to a boolean <true/false> value.")) },
+				None 	=> { return error(ErrorType::Arity, format!("Condition for 'while' did not evaluate to a value. Expression could not be tested.")) }
+			}
+			vm.run_inplace(&mut body)?;
+			if vm.brk { return Ok(false) }
+		}
+	})
+}
+
+
+/* 	== RECURSION == */
+
+/* Runs the condition 'cond' on a copy of the stack, as Joy does, and returns its result. */
+fn test_cond(vm: &mut Vm, cond: &Word, name: &str) -> Result<bool, StaxError> {
+	let mut s = vm.run_copystack(cond.clone())?;
+	match s.pop() {
+		Some(Word::Bool(b)) => { return Ok(b) },
+		Some(_) => { return error(ErrorType::Type, format!("Condition for '{}' did not evaluate to a boolean <true/false> value.", name)) },
+		None 	=> { return error(ErrorType::Arity, format!("Condition for '{}' did not evaluate to a value. Expression could not be tested.", name)) }
+	}
+}
+
+fn quote_opcode(w: Word, name: &str) -> Result<Opcode, StaxError> {
+	match w {
+		Word::Quote(q) | Word::Program(q) => { return Ok(Opcode::Quote(q)) },
+		Word::MacroOp(op) => { return Ok(Opcode::Quote(Box::new(vec![op]))) },
+		_ => { return error(ErrorType::Type, format!("'{}' requires quotes, but found an object of type '{}'.", name, w.sprint_type())) }
+	}
+}
+
+pub fn do_binrec(vm: &mut Vm, cond: &Word, then: &Word, rec1: &Word, rec2: &Word) -> BoolResult {
+	if test_cond(vm, cond, "binrec")? { return vm.exec_word(then.clone()) }
+	vm.exec_word(rec1.clone())?;
+	let second: Word;
+	match vm.stack.pop() {
+		Some(w) => { second = w },
+		None 	=> { return error(ErrorType::Underflow, format!("'binrec' requires the first recursion quote to leave 2 items on the stack.")) }
+	}
+	do_binrec(vm, cond, then, rec1, rec2)?;
+	vm.push_const(second)?;
+	do_binrec(vm, cond, then, rec1, rec2)?;
+	return vm.exec_word(rec2.clone())
+}
+
+pub fn do_genrec(vm: &mut Vm, cond: Word, then: Word, rec1: Word, rec2: Word) -> BoolResult {
+	if test_cond(vm, &cond, "genrec")? { return vm.exec_word(then) }
+	vm.exec_word(rec1.clone())?;
+	let again = vec![
+		quote_opcode(cond, "genrec")?,
+		quote_opcode(then, "genrec")?,
+		quote_opcode(rec1, "genrec")?,
+		quote_opcode(rec2.clone(), "genrec")?,
+		Opcode::Comb4(Comb4::Genrec)
+	];
+	vm.push_const(Word::Quote(Box::new(again)))?;
+	return vm.exec_word(rec2)
+}
+
+pub fn do_linrec(vm: &mut Vm, cond: &Word, then: &Word, rec1: &Word, rec2: &Word) -> BoolResult {
+	if test_cond(vm, cond, "linrec")? { return vm.exec_word(then.clone()) }
+	vm.exec_word(rec1.clone())?;
+	do_linrec(vm, cond, then, rec1, rec2)?;
+	return vm.exec_word(rec2.clone())
+}
+
+pub fn do_primrec(vm: &mut Vm, x: Word, init: Word, comb: Word) -> BoolResult {
+	/* Pushes the members of 'x' (n, n-1, ..., 1 for integers), runs 'init' and then folds with 'comb'. */
+	let n: usize;
+	match x {
+		Word::Int(_) | Word::Num(_) => {
+			let f = toF64(&x);
+			if f < 0.0 || f.fract() != 0.0 { return error(ErrorType::Domain, format!("'primrec' requires a non-negative integer. Given: '{}'.", f)) }
+			n = f as usize;
+			for i in 0..n { vm.push_const(Word::Int((n - i) as i32))?; }
+		},
+		Word::List(l) => {
+			n = l.len();
+			for w in l.into_iter() { vm.push_const(w)?; }
+		},
+		Word::Quote(q) => {
+			let l = vm.run_newstack(q.to_vec())?;
+			n = l.len();
+			for w in l.into_iter() { vm.push_const(w)?; }
+		},
+		Word::Vect(v) => {
+			n = v.len();
+			for f in v.iter() { vm.push_const(num_word(*f))?; }
+		},
+		Word::StrVect(v) => {
+			n = v.len();
+			for s in v.iter() { vm.push_const(str_word(s))?; }
+		},
+		_ => { return error(ErrorType::Type, format!("'primrec' cannot be completed on objects of type '{}'.", x.sprint_type())) }
+	}
+	vm.exec_word(init)?;
+	for _ in 0..n { vm.exec_word(comb.clone())?; }
+	return Ok(false)
+}
+
+pub fn do_tailrec(vm: &mut Vm, cond: Word, then: Word, rec1: Word) -> BoolResult {
+	/* Iterates instead of recursing, so the env stack stays the same depth. */
+	loop {
+		if test_cond(vm, &cond, "tailrec")? { return vm.exec_word(then) }
+		vm.exec_word(rec1.clone())?;
+	}
+}
+
+
+/* 	== SEQUENCES == */
+
+/* Splits a collection into its elements. Quotes are evaluated on a new stack, as in 'do_math'. */
+fn elements(vm: &mut Vm, coll: &Word, name: &str) -> Result<Vec<Word>, StaxError> {
+	match coll {
+		Word::BoolVect(v) 	=> { return Ok(v.iter().map(bool_word).collect()) },
+		Word::List(l) 		=> { return Ok(l.to_vec()) },
+		Word::Quote(q) 		=> { return vm.run_newstack(q.to_vec()) },
+		Word::StrVect(v) 	=> { return Ok(v.iter().map(str_word).collect()) },
+		Word::Vect(v) 		=> { return Ok(v.iter().map(|f| num_word(*f)).collect()) },
+		_ => { return error(ErrorType::Type, format!("'{}' cannot be completed on objects of type '{}'.", name, coll.sprint_type())) }
+	}
+}
+
+/* Runs 'fun' in isolation on 'args' and returns the item it leaves on top. */
+fn apply(vm: &mut Vm, args: Vec<Word>, fun: &Word, name: &str) -> Result<Word, StaxError> {
+	match vm.run_withstack(args, fun.clone())?.pop() {
+		Some(w) => { return Ok(w) },
+		None 	=> { return error(ErrorType::Arity, format!("Quote given to '{}' did not leave a value on the stack.", name)) }
+	}
+}
+
+pub fn do_each(vm: &mut Vm, coll: Word, fun: Word) -> BoolResult {
+	/* Like Joy's 'step': each element is pushed onto the main stack before 'fun' runs. It is a
+	 * loop like 'times', so 'fun' runs in the caller's scope and 'break' skips the remaining
+	 * elements. */
+	let items = elements(vm, &coll, "each")?;
+	let mut body = loop_ops(fun, "each")?;
+	return in_loop(vm, |vm| {
+		for w in items.iter() {
+			vm.push_const(w.clone())?;
+			vm.run_inplace(&mut body)?;
+			if vm.brk { break }
+		}
+		return Ok(false)
+	})
+}
+
+pub fn do_filter(vm: &mut Vm, coll: Word, pred: Word) -> BoolResult {
+	let mut keep = Vec::new();
+	for w in elements(vm, &coll, "filter")? {
+		match apply(vm, vec![w.clone()], &pred, "filter")? {
+			Word::Bool(b) 	=> { if b { keep.push(w) } },
+			_ => { return error(ErrorType::Type, format!("Predicate for 'filter' did not evaluate to a boolean <true/false> value.")) }
+		}
+	}
+	match coll {
+		Word::BoolVect(_) 	=> { return vm.push_const(Word::BoolVect(Box::new(keep.iter().map(|w| match w { Word::Bool(b) => Some(*b), _ => None }).collect::<Array1<Option<bool>>>()))) },
+		Word::StrVect(_) 	=> { return vm.push_const(Word::StrVect(Box::new(keep.into_iter().map(|w| match w { Word::Str(s) => Some(*s), _ => None }).collect::<Array1<Option<String>>>()))) },
+		Word::Vect(_) 		=> { return vm.push_const(Word::Vect(Box::new(keep.iter().map(|w| toF64(w)).collect::<Array1<f64>>()))) },
+		_ => { return vm.push_const(Word::List(Box::new(keep))) }
+	}
+}
+
+pub fn do_fold(vm: &mut Vm, coll: Word, init: Word, fun: Word, scan: bool) -> BoolResult {
+	let name = if scan { "scan" } else { "fold" };
+	let mut acc = init;
+	let mut steps = Vec::new();
+	for w in elements(vm, &coll, name)? {
+		acc = apply(vm, vec![acc, w], &fun, name)?;
+		if scan { steps.push(acc.clone()) }
+	}
+	if scan { return vm.push_const(tighten(steps)) }
+	return vm.push_const(acc)
+}
+
+pub fn do_map(vm: &mut Vm, coll: Word, fun: Word) -> BoolResult {
+	let mut result = Vec::new();
+	for w in elements(vm, &coll, "map")? {
+		result.push(apply(vm, vec![w], &fun, "map")?);
+	}
+	/* An empty vector maps to an empty vector of the same type, and anything else to '[]'. */
+	if result.len() == 0 {
+		match coll {
+			Word::BoolVect(_) | Word::StrVect(_) | Word::Vect(_) 	=> { return vm.push_const(coll) },
+			_ => { return vm.push_const(Word::List(Box::new(result))) }
+		}
+	}
+	return vm.push_const(tighten(result))
+}
+
+pub fn do_reduce(vm: &mut Vm, coll: Word, fun: Word) -> BoolResult {
+	let mut items = elements(vm, &coll, "reduce")?.into_iter();
+	let mut acc: Word;
+	match items.next() {
+		Some(w) => { acc = w },
+		None 	=> { return error(ErrorType::Domain, format!("'reduce' requires a collection with at least 1 element.")) }
+	}
+	for w in items {
+		acc = apply(vm, vec![acc, w], &fun, "reduce")?;
+	}
+	return vm.push_const(acc)
+}
+
+pub fn do_zipwith(vm: &mut Vm, lhs: Word, rhs: Word, fun: Word) -> BoolResult {
+	let left  = elements(vm, &lhs, "zipwith")?;
+	let right = elements(vm, &rhs, "zipwith")?;
+	if left.len() != right.len() {
+		return error(ErrorType::Index, format!("'zipwith' requires collections of equal length. Given: {} and {}.", left.len(), right.len()))
+	}
+	let mut result = Vec::with_capacity(left.len());
+	for (l, r) in left.into_iter().zip(right.into_iter()) {
+		result.push(apply(vm, vec![l, r], &fun, "zipwith")?);
+	}
+	return vm.push_const(tighten(result))
+}
+
+
+/* 	== GROUPING == */
+
+/* 'data groups [fun] groupby' runs 'fun' on the elements of 'data' in each group, as R's
+ * 'tapply' does, and leaves out elements whose group is NA. The result is a frame with one row
+ * per group in level order: a vector gives the columns "group" and "value", and a frame gives
+ * its grouping column and 'fun' applied to each of its numeric and logical columns. Text and
+ * factor columns are left out, as 'aggregate' would fail on them. The groups of a frame may
+ * also be given as the name of one of its columns. */
+pub fn do_groupby(vm: &mut Vm, data: Word, groups: Word, fun: Word) -> BoolResult {
+	let data = match data {
+		Word::Quote(q) 	=> { tighten(vm.run_newstack(q.to_vec())?) },
+		_ => { data }
+	};
+	let (key, groups) = match (&data, groups) {
+		(Word::Frame(f), Word::Str(s)) 	=> match f.column(&s) {
+			Some(c) => { (*s.clone(), c.clone()) },
+			None 	=> { return error(ErrorType::Index, format!("Column \"{}\" not found.", s)) }
+		},
+		(_, Word::Quote(q)) 	=> { (format!("group"), tighten(vm.run_newstack(q.to_vec())?)) },
+		(_, w) 	=> { (format!("group"), w) }
+	};
+	let factor = to_factor(&groups, "groupby")?;
+	let n = match &data {
+		Word::Frame(f) 	=> { f.nrow() },
+		w => match column_len(w) {
+			Some(n) => { n },
+			None 	=> { return error(ErrorType::Type, format!("'groupby' cannot be completed on objects of type '{}'.", w.sprint_type())) }
+		}
+	};
+	if factor.len() != n {
+		return error(ErrorType::Index, format!("'groupby' requires one group per element. Given: {} and {}.", factor.len(), n))
+	}
+	let mut rows: Vec<Vec<usize>> = vec![Vec::new(); factor.levels.len()];
+	for (i, c) in factor.codes.iter().enumerate() {
+		if let Some(c) = c { rows[*c].push(i) }
 	}
+
+	let levels = Factor { codes: (0 .. rows.len()).map(Some).collect(), levels: factor.levels.clone() };
+	let mut names = vec![key.clone()];
+	let mut columns = vec![Word::Factor(Box::new(levels))];
+	let values: Vec<(String, &Word)> = match &data {
+		Word::Frame(f) 	=> {
+			f.names.iter().zip(f.columns.iter())
+				.filter(|(name, column)| **name != key && !matches!(column, Word::StrVect(_) | Word::Factor(_)))
+				.map(|(name, column)| (name.clone(), column))
+				.collect()
+		},
+		w => { vec![(format!("value"), w)] }
+	};
+	for (name, column) in values.into_iter() {
+		let mut results = Vec::with_capacity(rows.len());
+		for r in rows.iter() {
+			results.push(apply(vm, vec![take_rows(column, r)], &fun, "groupby")?);
+		}
+		names.push(name);
+		columns.push(tighten(results));
+	}
+	return vm.push_const(Word::Frame(Box::new(Frame::new(names, columns)?)))
 }
 
 
+#[cfg(test)]
+mod tests {
+	use crate::compare::equal_words;
+	use crate::error::ErrorType;
+	use crate::vm::tests::run;
+	use crate::words::Word;
+
+	fn check(src: &str, expected: Vec<Word>) {
+		let stack = run(src).unwrap();
+		assert!(stack.len() == expected.len() && stack.iter().zip(expected.iter()).all(|(a, b)| equal_words(a, b)), "{}: {:?}", src, stack);
+	}
+
+	#[test]
+	fn try_restores_stack() {
+		let x = Word::Str(Box::new("x".to_string()));
+		check("1 2 [zap zap \"x\" throw] [] try", vec![Word::Int(1), Word::Int(2), x.clone()]);
+		check("1 [2 3 \"x\" throw] [] try", vec![Word::Int(1), x]);
+		check("1 [2 add] [] try", vec![Word::Int(3)]);
+	}
+
+	#[test]
+	fn loops() {
+		check("i = 0; 5 [i = i + 1] times i", vec![Word::Int(5)]);
+		check("0 0 [1 add] times", vec![Word::Int(0)]);
+		check("i = 0; [i 3 lst swap zap] [i = i + 1] while i", vec![Word::Int(3)]);
+		check("i = 0; [i = i + 1; i 4 eq swap zap [break] [] ifelse] loop i", vec![Word::Int(4)]);
+		assert!(run("-1 [] times").is_err());
+	}
+
+	#[test]
+	fn break_innermost() {
+		/* Each inner loop stops at its first pass; the outer loop runs to the end. */
+		check("n = 0; 3 [4 [n = n + 1; break] times] times n", vec![Word::Int(3)]);
+		check("n = 0; 3 [[n = n + 1; break] loop] times n", vec![Word::Int(3)]);
+		check("[1 2 3 4] [3 eq [break] [] ifelse] each", vec![Word::Int(1), Word::Int(2), Word::Int(3)]);
+		check("n = 0; 2 [[1 2 3] [zap; n = n + 1; break] each] times n", vec![Word::Int(2)]);
+		/* 'try', 'ifelse' and 'do' run their quotes in place, so 'break' passes through them. */
+		check("n = 0; 3 [n = n + 1; [break] [] try] times n", vec![Word::Int(1)]);
+	}
+
+	#[test]
+	fn break_outside_loop() {
+		let kind = |src: &str| run(src).unwrap_err().kind;
+		assert_eq!(kind("break"), ErrorType::Syntax);
+		assert_eq!(kind("3 [[1 2] [break] map] times"), ErrorType::Syntax);
+		assert_eq!(kind("3 [1 [true] [break] [] [] linrec] times"), ErrorType::Syntax);
+	}
+
+	#[test]
+	fn map_empty() {
+		check("[] [2 mul] map", vec![Word::List(Box::new(vec![]))]);
+		check("[3 4] [2 mul] map", vec![Word::Vect(Box::new(ndarray::array![6.0, 8.0]))]);
+	}
+
+	#[test]
+	fn recursion() {
+		check("5 [1] [mul] primrec", vec![Word::Int(120)]);
+		check("[1 2 3] [0] [add] primrec", vec![Word::Int(6)]);
+		check("5 [0 eq] [zap 1] [dup 1 sub] [mul] linrec", vec![Word::Int(120)]);
+		check("10 [2 lst] [] [dup 1 sub swap 2 sub] [add] binrec", vec![Word::Int(55)]);
+		check("5 [0 eq] [zap 1] [dup 1 sub] [do mul] genrec", vec![Word::Int(120)]);
+		check("100000 [0 eq] [] [1 sub] tailrec", vec![Word::Int(0)]);
+		let kind = |src: &str| run(src).unwrap_err().kind;
+		assert_eq!(kind("\"a\" [1] [mul] primrec"), ErrorType::Type);
+		assert_eq!(kind("-1 [1] [mul] primrec"), ErrorType::Domain);
+		assert_eq!(kind("5 [1] [] [] [] linrec"), ErrorType::Type);
+		assert_eq!(kind("5 [zap] [] [1 sub] tailrec"), ErrorType::Arity);
+	}
+
+	fn grouped(src: &str) -> crate::frame::Frame {
+		match run(src).unwrap().pop() {
+			Some(Word::Frame(f)) 	=> { return *f },
+			w => { panic!("{}: {:?}", src, w) }
+		}
+	}
+
+	fn levels(w: &Word) -> Vec<String> {
+		match w {
+			Word::Factor(f) 	=> { return f.labels().into_iter().map(|l| l.unwrap()).collect() },
+			_ => { panic!("not a factor") }
+		}
+	}
+
+	#[test]
+	fn groupby_vector() {
+		/* Groups come in level order and the NA group is left out. */
+		let f = grouped("[1 2 3 4 5] [\"b\" \"a\" \"b\" NA \"b\"] [sum] groupby");
+		assert_eq!(f.names, vec!["group", "value"]);
+		assert_eq!(levels(&f.columns[0]), vec!["a", "b"]);
+		assert!(equal_words(&f.columns[1], &Word::Vect(Box::new(ndarray::array![2.0, 9.0]))));
+		assert_eq!(run("[1 2 3] [1 2] [sum] groupby").unwrap_err().kind, ErrorType::Index);
+	}
+
+	#[test]
+	fn groupby_frame() {
+		/* The text column is left out; 'spread' is a user-defined aggregator. */
+		let f = grouped("spread := dup max swap min sub; \
+			f = [\"g\" \"x\" \"s\"] [[2 1 2 1] [1 2 3 7] [\"p\" \"q\" \"r\" \"s\"]] frame; \
+			f \"g\" [spread] groupby");
+		assert_eq!(f.names, vec!["g", "x"]);
+		assert_eq!(levels(&f.columns[0]), vec!["1", "2"]);
+		assert!(equal_words(&f.columns[1], &Word::Vect(Box::new(ndarray::array![5.0, 2.0]))));
+		assert_eq!(run("[\"g\"] [[1 2]] frame \"h\" [sum] groupby").unwrap_err().kind, ErrorType::Index);
+	}
+}
@@ -1,26 +1,58 @@
 /* compare.rs
  * ---------- */
-use crate::code::BoolOp;
+use crate::arith::broadcast_zip;
+use crate::code::{BoolOp, GenOp, Opcode};
 use crate::code::BoolOp::*;
-use crate::words::Word;
+use crate::error::{ErrorType, error};
+use crate::na::{is_na, na};
+use crate::words::{Word, WordResult};
 
-type BoolResult = Result<Word, String>;
+use ndarray::{Array1, ArrayD, IxDyn};
+
+type BoolResult = WordResult;
 
 pub fn do_compare(op: BoolOp, lhs: Word, rhs: Word) -> BoolResult {
-	match lhs {
-		Word::Int(l) 	=> { return do_int(l, rhs, op) },
-		Word::Num(l) 	=> { return do_num(l, rhs, op) },
-		Word::Str(l) 	=> { return do_str(*l, rhs, op) },
-		_ => return Ok(Word::Null)
+	match op {
+		And | Or | Xor 	=> { return do_logic(lhs, rhs, op) },
+		_ => { }
+	}
+	if let (Word::Factor(_), _) | (_, Word::Factor(_)) = (&lhs, &rhs) { return do_factor(lhs, rhs, op) }
+	match (&lhs, &rhs) {
+		(Word::Int(l), _) 	=> { return do_int(*l, rhs, op) },
+		(Word::Num(l), _) 	=> { return do_num(*l, rhs, op) },
+		(Word::Str(l), Word::Str(_)) 	=> { return do_str(*l.clone(), rhs, op) },
+		_ => { return do_elementwise(lhs, rhs, op) }
 	}
 }
 
+pub fn do_not(w: Word) -> BoolResult {
+	match w {
+		Word::Bool(b) 		=> { return Ok(Word::Bool(!b)) },
+		Word::BoolVect(v) 	=> { return Ok(Word::BoolVect(Box::new(v.mapv(|b| b.map(|b| !b))))) },
+		Word::Na 			=> { return Ok(Word::Na) },
+		_ => { return error(ErrorType::Type, format!("'not' cannot be completed on objects of type '{}'.", w.sprint_type())) }
+	}
+}
+
+
+/* Factors compare by their labels. Their levels carry no order, so only 'eq' and 'neq' apply. */
+fn do_factor(lhs: Word, rhs: Word, op: BoolOp) -> BoolResult {
+	match op {
+		Eqt | Neq 	=> { },
+		_ => { return error(ErrorType::Type, format!("Factors are unordered and can only be compared with '==' and '!='.")) }
+	}
+	let labels = |w: Word| match w {
+		Word::Factor(f) 	=> { Word::StrVect(Box::new(Array1::from_vec(f.labels()))) },
+		_ => { w }
+	};
+	return do_compare(op, labels(lhs), labels(rhs))
+}
 
 fn do_int(lhs: i32, rhs: Word, op: BoolOp) -> BoolResult {
 	match rhs {
 		Word::Int(r) 	=> { return do_int_int(lhs, r, op) },
 		Word::Num(r) 	=> { return do_num_num(lhs as f64, r, op) },
-		_ => return Ok(Word::Null)
+		_ => { return do_elementwise(Word::Int(lhs), rhs, op) }
 	}
 }
 
@@ -28,14 +60,14 @@ fn do_num(lhs: f64, rhs: Word, op: BoolOp) -> BoolResult {
 	match rhs {
 		Word::Int(r) 	=> { return do_num_num(lhs, r as f64, op) },
 		Word::Num(r) 	=> { return do_num_num(lhs, r, op) },
-		_ => return Ok(Word::Null)
+		_ => { return do_elementwise(Word::Num(lhs), rhs, op) }
 	}
 }
 
 fn do_str(lhs: String, rhs: Word, op: BoolOp) -> BoolResult {
 	match rhs {
 		Word::Str(r) 	=> { return do_str_str(lhs, *r, op) },
-		_ => return Ok(Word::Null)
+		_ => { return do_elementwise(Word::Str(Box::new(lhs)), rhs, op) }
 	}
 }
 
@@ -47,11 +79,12 @@ fn do_int_int(lhs: i32, rhs: i32, op: BoolOp) -> BoolResult {
 		Lte	=> { return Ok(Word::Bool(lhs <= rhs)) },
 		Eqt	=> { return Ok(Word::Bool(lhs == rhs)) },
 		Neq	=> { return Ok(Word::Bool(lhs != rhs)) },
-		_ => { return Err("Binary operation not recognized!".to_string()) }
+		_ => { return error(ErrorType::Type, "Binary operation not recognized!".to_string()) }
 	}
 }
 
 fn do_num_num(lhs: f64, rhs: f64, op: BoolOp) -> BoolResult {
+	if is_na(lhs) || is_na(rhs) { return Ok(Word::Na) }
 	match op {
 		Grt	=> { return Ok(Word::Bool(lhs > rhs)) },
 		Lst	=> { return Ok(Word::Bool(lhs < rhs)) },
@@ -59,7 +92,7 @@ fn do_num_num(lhs: f64, rhs: f64, op: BoolOp) -> BoolResult {
 		Lte	=> { return Ok(Word::Bool(lhs <= rhs)) },
 		Eqt	=> { return Ok(Word::Bool(lhs == rhs)) },
 		Neq	=> { return Ok(Word::Bool(lhs != rhs)) },
-		_ => { return Err("Binary operation not recognized!".to_string()) }
+		_ => { return error(ErrorType::Type, "Binary operation not recognized!".to_string()) }
 	}
 }
 
@@ -71,7 +104,232 @@ fn do_str_str(lhs: String, rhs: String, op: BoolOp) -> BoolResult {
 		Lte	=> { return Ok(Word::Bool(lhs <= rhs)) },
 		Eqt	=> { return Ok(Word::Bool(lhs == rhs)) },
 		Neq	=> { return Ok(Word::Bool(lhs != rhs)) },
-		_ => { return Err(format!("Operation cannot be completed between two string objects.")) }
+		_ => { return error(ErrorType::Type, format!("Operation cannot be completed between two string objects.")) }
+	}
+}
+
+
+/* 	== LOGIC == */
+
+/* Three-valued logic as in R: NA is an unknown value, so 'false and NA' is false and
+ * 'true or NA' is true, while anything else involving NA is NA. */
+fn do_logic(lhs: Word, rhs: Word, op: BoolOp) -> BoolResult {
+	match (bool_array(&lhs), bool_array(&rhs)) {
+		(Some(l), Some(r)) 	=> {
+			let apply = |a: &Option<bool>, b: &Option<bool>| match (op.clone(), *a, *b) {
+				(And, Some(false), _) | (And, _, Some(false)) 	=> { Some(false) },
+				(Or, Some(true), _) | (Or, _, Some(true)) 		=> { Some(true) },
+				(_, Some(x), Some(y)) 	=> { Some(match op { And => x && y, Or => x || y, _ => x != y }) },
+				_ => { None }
+			};
+			return broadcast_with(&l, &r, apply)
+		},
+		_ => { return error(ErrorType::Type, format!("'{}' cannot be completed between objects of type '{}' and '{}'.",
+			sprint_logic(&op), lhs.sprint_type(), rhs.sprint_type())) }
 	}
 }
 
+fn sprint_logic(op: &BoolOp) -> &'static str {
+	match op {
+		And => { return "and" },
+		Or 	=> { return "or" },
+		_ 	=> { return "xor" }
+	}
+}
+
+
+/* 	== ELEMENT-WISE == */
+
+/* Compares arrays (and scalars against arrays) element by element with ndarray broadcasting.
+ * There is no boolean matrix, so results with more than one axis are flattened row-major.
+ * Comparisons involving NA are NA. */
+fn do_elementwise(lhs: Word, rhs: Word, op: BoolOp) -> BoolResult {
+	match (num_array(&lhs), num_array(&rhs)) {
+		(Some(l), Some(r)) 	=> {
+			return broadcast_with(&l, &r, |a, b| if is_na(*a) || is_na(*b) { None } else { Some(compare(&op, a, b)) })
+		},
+		_ => { }
+	}
+	match (str_array(&lhs), str_array(&rhs)) {
+		(Some(l), Some(r)) 	=> { return broadcast_with(&l, &r, |a, b| compare_some(&op, a, b)) },
+		_ => { }
+	}
+	match (bool_array(&lhs), bool_array(&rhs)) {
+		(Some(l), Some(r)) 	=> { return broadcast_with(&l, &r, |a, b| compare_some(&op, a, b)) },
+		_ => { }
+	}
+	match op {
+		Eqt => { return Ok(Word::Bool(equal_words(&lhs, &rhs))) },
+		Neq => { return Ok(Word::Bool(!equal_words(&lhs, &rhs))) },
+		_ 	=> { return error(ErrorType::Type, format!("Objects of type '{}' and '{}' cannot be ordered.", lhs.sprint_type(), rhs.sprint_type())) }
+	}
+}
+
+fn compare<T: PartialOrd>(op: &BoolOp, a: &T, b: &T) -> bool {
+	match op {
+		Grt	=> { return a > b },
+		Lst	=> { return a < b },
+		Gte	=> { return a >= b },
+		Lte	=> { return a <= b },
+		Eqt	=> { return a == b },
+		_	=> { return a != b }
+	}
+}
+
+fn compare_some<T: PartialOrd>(op: &BoolOp, a: &Option<T>, b: &Option<T>) -> Option<bool> {
+	match (a, b) {
+		(Some(a), Some(b)) 	=> { return Some(compare(op, a, b)) },
+		_ => { return None }
+	}
+}
+
+fn broadcast_with<T, F>(lhs: &ArrayD<T>, rhs: &ArrayD<T>, f: F) -> BoolResult
+	where F: Fn(&T, &T) -> Option<bool>
+{
+	let result = broadcast_zip(lhs, rhs, f)?;
+	if result.ndim() == 0 {
+		match result.iter().next() {
+			Some(Some(b)) 	=> { return Ok(Word::Bool(*b)) },
+			_ => { return Ok(Word::Na) }
+		}
+	}
+	return Ok(Word::BoolVect(Box::new(result.iter().cloned().collect())))
+}
+
+fn num_array(w: &Word) -> Option<ArrayD<f64>> {
+	match w {
+		Word::Int(i) 	=> { return Some(ArrayD::from_elem(IxDyn(&[]), *i as f64)) },
+		Word::Na 		=> { return Some(ArrayD::from_elem(IxDyn(&[]), na())) },
+		Word::Num(f) 	=> { return Some(ArrayD::from_elem(IxDyn(&[]), *f)) },
+		Word::Vect(v) 	=> { return Some(v.to_owned().into_dyn()) },
+		Word::Mat(m) 	=> { return Some(m.to_owned().into_dyn()) },
+		_ => { return None }
+	}
+}
+
+fn str_array(w: &Word) -> Option<ArrayD<Option<String>>> {
+	match w {
+		Word::Str(s) 		=> { return Some(ArrayD::from_elem(IxDyn(&[]), Some(s.to_string()))) },
+		Word::StrVect(v) 	=> { return Some(v.to_owned().into_dyn()) },
+		Word::Na 			=> { return Some(ArrayD::from_elem(IxDyn(&[]), None)) },
+		_ => { return None }
+	}
+}
+
+fn bool_array(w: &Word) -> Option<ArrayD<Option<bool>>> {
+	match w {
+		Word::Bool(b) 		=> { return Some(ArrayD::from_elem(IxDyn(&[]), Some(*b))) },
+		Word::Na 			=> { return Some(ArrayD::from_elem(IxDyn(&[]), None)) },
+		Word::BoolVect(v) 	=> { return Some(v.to_owned().into_dyn()) },
+		_ => { return None }
+	}
+}
+
+
+/* 	== STRUCTURAL EQUALITY == */
+
+/* Deep equality used by 'eq' and 'neq' on lists, dicts and quotes. Numbers compare by value
+ * across Int and Num, and quotes ignore the span markers the compiler adds. */
+pub fn equal_words(lhs: &Word, rhs: &Word) -> bool {
+	match (lhs, rhs) {
+		(Word::Null, Word::Null) 				=> { return true },
+		(Word::Bool(l), Word::Bool(r)) 			=> { return l == r },
+		(Word::BoolVect(l), Word::BoolVect(r)) 	=> { return l == r },
+		(Word::Dict(l), Word::Dict(r)) 			=> {
+			return l.len() == r.len() && l.iter().all(|(k, v)| match r.get(k) {
+				Some(w) => { equal_words(v, w) },
+				None 	=> { false }
+			})
+		},
+		(Word::Factor(l), Word::Factor(r)) 		=> { return l.levels == r.levels && l.codes == r.codes },
+		(Word::Frame(l), Word::Frame(r)) 		=> {
+			return l.names == r.names && l.columns.iter().zip(r.columns.iter()).all(|(a, b)| equal_words(a, b))
+		},
+		(Word::Int(l), Word::Int(r)) 			=> { return l == r },
+		(Word::Int(l), Word::Num(r)) 			=> { return *l as f64 == *r },
+		(Word::Num(l), Word::Int(r)) 			=> { return *l == *r as f64 },
+		(Word::Num(l), Word::Num(r)) 			=> { return l == r },
+		(Word::List(l), Word::List(r)) 			=> {
+			return l.len() == r.len() && l.iter().zip(r.iter()).all(|(a, b)| equal_words(a, b))
+		},
+		(Word::MacroOp(l), Word::MacroOp(r)) 	=> { return l == r },
+		(Word::Na, Word::Na) 					=> { return true },
+		(Word::Mat(l), Word::Mat(r)) 			=> { return l == r },
+		(Word::Program(l), Word::Program(r)) 	=> { return equal_ops(l, r) },
+		(Word::Quote(l), Word::Quote(r)) 		=> { return equal_ops(l, r) },
+		(Word::Str(l), Word::Str(r)) 			=> { return l == r },
+		(Word::StrVect(l), Word::StrVect(r)) 	=> { return l == r },
+		(Word::Sym(l), Word::Sym(r)) 			=> { return l == r },
+		(Word::Vect(l), Word::Vect(r)) 			=> { return l == r },
+		_ => { return false }
+	}
+}
+
+fn equal_ops(lhs: &Vec<Opcode>, rhs: &Vec<Opcode>) -> bool {
+	let marker = |op: &&Opcode| match op {
+		Opcode::GenOp(GenOp::Spans(_)) | Opcode::GenOp(GenOp::Loc(_)) | Opcode::GenOp(GenOp::AddLine(_)) => false,
+		_ => true
+	};
+	let l: Vec<&Opcode> = lhs.iter().filter(marker).collect();
+	let r: Vec<&Opcode> = rhs.iter().filter(marker).collect();
+	if l.len() != r.len() { return false }
+	return l.iter().zip(r.iter()).all(|(a, b)| match (a, b) {
+		(Opcode::Quote(x), Opcode::Quote(y)) | (Opcode::Prog(x), Opcode::Prog(y)) => { equal_ops(x, y) },
+		_ => { a == b }
+	})
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vm::tests::run;
+	use ndarray::array;
+
+	fn nums(v: Array1<f64>) -> Word { return Word::Vect(Box::new(v)) }
+
+	fn bools(w: Word) -> Vec<Option<bool>> {
+		match w {
+			Word::BoolVect(v) 	=> { return v.to_vec() },
+			w => { panic!("not a vec<bool>: {:?}", w) }
+		}
+	}
+
+	#[test]
+	fn elementwise() {
+		let v = nums(array![1.0, na(), 5.0]);
+		assert_eq!(bools(do_compare(Grt, v.clone(), Word::Int(3)).unwrap()), vec![Some(false), None, Some(true)]);
+		assert_eq!(bools(do_compare(Lte, Word::Num(1.0), v).unwrap()), vec![Some(true), None, Some(true)]);
+		/* A row vector broadcasts over the rows of a matrix; the result is flattened row-major. */
+		let m = Word::Mat(Box::new(array![[1.0, 2.0], [3.0, 4.0]]));
+		assert_eq!(bools(do_compare(Eqt, m, nums(array![1.0, 4.0])).unwrap()), vec![Some(true), Some(false), Some(false), Some(true)]);
+		let s = Word::StrVect(Box::new(array![Some(format!("a")), None, Some(format!("c"))]));
+		assert_eq!(bools(do_compare(Neq, s, Word::Str(Box::new(format!("a")))).unwrap()), vec![Some(false), None, Some(true)]);
+		assert!(do_compare(Lst, nums(array![1.0, 2.0]), nums(array![1.0, 2.0, 3.0])).is_err());
+	}
+
+	#[test]
+	fn logic() {
+		let t = Word::Bool(true);
+		let f = Word::Bool(false);
+		assert!(matches!(do_compare(Xor, t.clone(), f.clone()).unwrap(), Word::Bool(true)));
+		assert!(matches!(do_compare(And, f.clone(), Word::Na).unwrap(), Word::Bool(false)));
+		assert!(matches!(do_compare(Or, t.clone(), Word::Na).unwrap(), Word::Bool(true)));
+		assert!(matches!(do_compare(And, t.clone(), Word::Na).unwrap(), Word::Na));
+		let v = Word::BoolVect(Box::new(array![Some(true), Some(false), None]));
+		assert_eq!(bools(do_compare(Or, v.clone(), f).unwrap()), vec![Some(true), Some(false), None]);
+		assert_eq!(bools(do_not(v).unwrap()), vec![Some(false), Some(true), None]);
+		assert_eq!(do_compare(And, t, Word::Int(1)).unwrap_err().kind, ErrorType::Type);
+		assert_eq!(do_not(Word::Int(1)).unwrap_err().kind, ErrorType::Type);
+	}
+
+	#[test]
+	fn structural() {
+		let last = |src: &str| run(src).unwrap().pop().unwrap();
+		assert!(matches!(last("[1 [2 \"x\"]] [1 [2 \"x\"]] eq"), Word::Bool(true)));
+		assert!(matches!(last("[1 2] [1 3] neq"), Word::Bool(true)));
+		assert!(matches!(last("[dup mul] [dup mul] eq"), Word::Bool(true)));
+		assert!(equal_words(&Word::Int(2), &Word::Num(2.0)));
+		assert_eq!(run("[1 2] [1 3] lst").unwrap_err().kind, ErrorType::Type);
+	}
+}
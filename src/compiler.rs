@@ -1,34 +1,79 @@
 /* compiler.rs
  * ----------- */
 use crate::code::{Opcode, ConstCode, GenOp, MathOp};
+use crate::error::{ErrorType, StaxError, error};
 use crate::expr::{Expr, walk_expr, walk_expr_assign};
-use crate::lexer::{Lexer, Prec, Token, sprint_token};
+use crate::lexer::{Lexer, Prec, Span, Token, sprint_token};
 use crate::words::{BoolResult};
 
 
-type ParseResult = Result<Expr, String>;
+type ParseResult = Result<Expr, StaxError>;
 
 struct Compiler {
+	ended 	: 	bool,	// an assignment consumed the stop token of the enclosing 'parse_until'
 	indent	: 	u8,
 	stop 	: 	Token
 }
 
-pub fn compile(lexer: &mut Lexer) -> Result<Vec<Vec<Opcode>>, String> {
+pub fn compile(lexer: &mut Lexer) -> Result<Vec<Vec<Opcode>>, StaxError> {
 	lexer.next_token()?;
 	lexer.next_token()?;
 	let mut compiler = Compiler::new();
 	let mut results  = Vec::new(); 
 	while !lexer.is_match(&vec![Token::Eof]) {
 		let expr = compiler.parse_until(lexer, &vec![Token::Eol, Token::Semicolon])?;
-		results.push(expr);
+		results.push(attach_spans(lexer, expr));
 	}
 	return Ok(results)
 }
 
+/* Replaces the 'Loc' markers emitted while parsing with a span table parallel to the block.
+ * The block then starts with a 'Spans' opcode naming its table. Nested blocks get their own. */
+fn attach_spans(lexer: &mut Lexer, ops: Vec<Opcode>) -> Vec<Opcode> {
+	if ops.len() == 0 { return ops }
+	let id = lexer.tables.len();
+	lexer.tables.push(vec![]);
+	let mut last : Span;
+	match ops.iter().find(|op| match op { Opcode::GenOp(GenOp::Loc(_)) => true, _ => false }) {
+		Some(Opcode::GenOp(GenOp::Loc(u)))	=> { last = lexer.spans[*u as usize].clone() },
+		_ 	=> { last = Span{ file: lexer.file(), line: 0, col: 0 } }
+	}
+	let mut code  = vec![Opcode::GenOp(GenOp::Spans((lexer.table_base + id) as u32))];
+	let mut table = vec![last.clone()];
+	for op in ops {
+		match op {
+			Opcode::GenOp(GenOp::Loc(u)) => { last = lexer.spans[u as usize].clone(); continue },
+			Opcode::Quote(q)	=> { code.push(Opcode::Quote(Box::new(attach_spans(lexer, *q)))) },
+			Opcode::Prog(q)		=> { code.push(Opcode::Prog(Box::new(attach_spans(lexer, *q)))) },
+			_ 	=> { code.push(op) }
+		}
+		table.push(last.clone());
+	}
+	lexer.tables[id] = table;
+	return code
+}
+
+
+/* An assignment ends at ';', at the end of its line, or at the end of the enclosing statement,
+ * quote or group, whichever comes first. In the last case 'ended' tells the enclosing
+ * 'parse_until' that its stop token was consumed. */
+fn assign_stop(stop: &Vec<Token>) -> Vec<Token> {
+	let mut result = stop.clone();
+	for tok in [Token::Eol, Token::Semicolon] {
+		if !result.contains(&tok) { result.push(tok) }
+	}
+	return result
+}
+
+/* Prefixes 'expr' with the span of the current token. */
+fn located(lexer: &mut Lexer, expr: Expr) -> Expr {
+	return Expr::LeftCode(Opcode::GenOp(GenOp::Loc(lexer.record_span())), Box::new(expr))
+}
 
 impl Compiler {
 	pub fn new() -> Self {
 		return Compiler {
+			ended 	: false,
 			indent	: 0,
 			stop 	: Token::Blank
 		}
@@ -36,7 +81,7 @@ impl Compiler {
 
 	/* == MAIN PARSING FUNCTIONS == */
 
-	fn parse_binary(&mut self, lexer: &mut Lexer, lhs: Expr, prec: Prec, stop: &Vec<Token>) -> Result<(Expr, Prec), String> {
+	fn parse_binary(&mut self, lexer: &mut Lexer, lhs: Expr, prec: Prec, stop: &Vec<Token>) -> Result<(Expr, Prec), StaxError> {
 		match lexer.current.clone() {
 			Token::White(_) | Token::Blank => {
 				lexer.next_token()?;
@@ -44,6 +89,7 @@ impl Compiler {
 				return self.parse_binary(lexer, lhs, prec, stop)
 			},
 			Token::Eol => {
+				if lexer.is_match(stop) { return Ok((lhs, prec)) } // newline ends the statement
 				let mut nlines = 1;
 				'outer: loop {
 					lexer.next_token()?;
@@ -64,26 +110,32 @@ impl Compiler {
 				), prec, stop)			
 			},
 			Token::Equal 	=> {
+				let loc = Opcode::GenOp(GenOp::Loc(lexer.record_span()));
 				lexer.next_token()?;
-				let mut results = self.parse_until(lexer, &vec![Token::Eol, Token::Semicolon])?;
+				let mut results = self.parse_until(lexer, &assign_stop(stop))?;
+				self.ended = stop.contains(&self.stop);
+				results.push(loc);
 				results.append( &mut walk_expr_assign(lhs, Token::Equal) );
 				return Ok((Expr::Group(results), prec))
 			},
 			Token::EqualProg 	=> {
+				let loc = Opcode::GenOp(GenOp::Loc(lexer.record_span()));
 				lexer.next_token()?;
 				let mut results = vec![Opcode::Quote(Box::new(
-					self.parse_until(lexer, &vec![Token::Eol, Token::Semicolon])?
-				))];
+					self.parse_until(lexer, &assign_stop(stop))?
+				)), loc];
+				self.ended = stop.contains(&self.stop);
 				results.append( &mut walk_expr_assign(lhs, Token::EqualProg) );
 				return Ok((Expr::Group(results), prec))
 			},
 			Token::Infix(op, op_prec) => {
 				if op_prec > prec {
+					let loc = Opcode::GenOp(GenOp::Loc(lexer.record_span()));
 					lexer.next_token()?;
-					if lexer.is_match(stop) { return Err(format!("Stop token was reached before infix operation was finished parsing.")) }
+					self.operand(lexer, stop)?;
 					let rhs = self.parse_primary(lexer, false, stop)?;
 					let (expr, new_prec) = self.parse_binary(lexer, rhs, op_prec, stop)?;
-					let new_code = Expr::Binary(op.clone(), Box::new(lhs), Box::new(expr));
+					let new_code = Expr::Binary(op.clone(), Box::new(lhs), Box::new(Expr::RightCode(loc, Box::new(expr))));
 					if new_prec <= prec { return Ok((new_code, new_prec)) }
 					return self.parse_binary(lexer, new_code, prec, stop)
 				} else {
@@ -98,7 +150,7 @@ impl Compiler {
 	fn parse_primary(&mut self, lexer: &mut Lexer, first: bool, stop: &Vec<Token>) -> ParseResult {
 		let exp : Expr;
 		match lexer.current.clone() {
-			Token::White(_) | Token::Blank	=> {
+			Token::White(_) | Token::Blank | Token::Semicolon	=> { // ';' separates statements inside quotes and groups
 				lexer.next_token()?;
 				if lexer.is_match(stop) { return Ok(Expr::Blank) }
 				return self.parse_primary(lexer, first, stop)
@@ -127,53 +179,58 @@ impl Compiler {
 			},
 			Token::Lparen 	=> {
 				lexer.next_token()?;
-				exp = Expr::Primary(self.parse_until(lexer, &vec![Token::Rparen])?);
+				let group = Expr::Primary(self.parse_until(lexer, &vec![Token::Rparen])?);
+				return self.ending_at(lexer, group, stop)
 			},
 			Token::Lbrack 	=> {
 				lexer.next_token()?;
 				let quote = self.parse_until(lexer, &vec![Token::Rbrack])?;
-				exp = Expr::Single(Opcode::Quote(Box::new(quote)));
+				return self.ending_at(lexer, Expr::Single(Opcode::Quote(Box::new(quote))), stop)
 			},
 			//TODO: LBRACE
 			Token::Minus 	=> {
+				let loc = Opcode::GenOp(GenOp::Loc(lexer.record_span()));
 				lexer.next_token()?;
-				match &lexer.current {
+				match lexer.current.clone() {
 					Token::Const(op)	=> match op {
 						Opcode::Const(c)	=> match c {
-							ConstCode::Int(i)	=> { return Ok(Expr::Single(Opcode::Const(ConstCode::Int(-i)))) },
-							ConstCode::True 	=> { return Ok(Expr::Single(Opcode::Const(ConstCode::False))) },
-							ConstCode::False 	=> { return Ok(Expr::Single(Opcode::Const(ConstCode::True))) },
-							ConstCode::Null 	=> { return Ok(Expr::Single(Opcode::Const(ConstCode::Null))) },
+							ConstCode::Int(i)	=> { exp = Expr::Single(Opcode::Const(ConstCode::Int(-i))) },
+							ConstCode::True 	=> { exp = Expr::Single(Opcode::Const(ConstCode::False)) },
+							ConstCode::False 	=> { exp = Expr::Single(Opcode::Const(ConstCode::True)) },
+							ConstCode::Na 		=> { exp = Expr::Single(Opcode::Const(ConstCode::Na)) },
+							ConstCode::Null 	=> { exp = Expr::Single(Opcode::Const(ConstCode::Null)) },
 						},
-						_ 	=> { return Err(format!("Opcode misplaced by lexer!")) }
+						_ 	=> { return error(ErrorType::Syntax, format!("Opcode misplaced by lexer!")) }
 					},
-					Token::Num(f)	=> { return Ok(Expr::Single(Opcode::Num(-f))) },
+					Token::Num(f)	=> { exp = Expr::Single(Opcode::Num(-f)) },
 					Token::Sym(s)	=> {
-						return Ok(Expr::Double(Opcode::Sym(s.clone()), Opcode::MathOp(MathOp::Neg)))
+						let neg = Expr::Double(Opcode::Sym(s), Opcode::MathOp(MathOp::Neg));
+						exp = Expr::LeftCode(loc, Box::new(neg))
 					},
-					_ => { return Err("yep".to_string()) }// ?What to do here??}
+					_ => { return error(ErrorType::Syntax, format!("Unary '-' must be followed by a number, constant or symbol.")) }
 				}
 			},
 			Token::Const(c)	=> { exp = Expr::Single(c) },
 			Token::Num(f)	=> { exp = Expr::Single(Opcode::Num(f)) },
 			Token::Str(s)	=> { exp = Expr::Single(Opcode::Str(s)) },
-			Token::Sym(s)	=> { exp = Expr::Single(Opcode::Sym(s)) },
+			Token::Sym(s)	=> { exp = located(lexer, Expr::Single(Opcode::Sym(s))) },
 
 			Token::Infix(o,_)	=> {
+				let loc = Opcode::GenOp(GenOp::Loc(lexer.record_span()));
 				lexer.next_token()?;
-				if lexer.is_match(stop) { return Err(format!("Stop token was reached before infix operation was finished parsing.")) }
+				self.operand(lexer, stop)?;
 				let res = self.parse_primary(lexer, false, stop)?;  // ?? Should parse binary after so no stop?
-				let rhs = walk_expr(Expr::RightCode(o.clone(), Box::new(res)));
+				let rhs = walk_expr(Expr::RightCode(o.clone(), Box::new(Expr::RightCode(loc, Box::new(res)))));
 				if first { return Ok( Expr::Primary(rhs) ) }
 				return Ok( Expr::Single(Opcode::Quote(Box::new(rhs))) )
 			},
 
-			_ => { return Err(format!("Token not recognized for parsing!")) }
+			_ => { return error(ErrorType::Syntax, format!("Token not recognized for parsing!")) }
 		}
 		return self.ending(lexer, exp, stop)
 	}
 
-	pub fn parse_until(&mut self, lexer: &mut Lexer, stop: &Vec<Token>) -> Result<Vec<Opcode>, String> {
+	pub fn parse_until(&mut self, lexer: &mut Lexer, stop: &Vec<Token>) -> Result<Vec<Opcode>, StaxError> {
 		let mut results = Vec::new();
 		'outer: while !lexer.is_match(stop) {
 			lexer.skipWhite()?;
@@ -188,6 +245,10 @@ impl Compiler {
 			}
 			//lexer.next_token()?; // TODO: Check!
 			if lexer.current == Token::Eof { return Ok(results) }
+			if self.ended {
+				self.ended = false;
+				return Ok(results)
+			}
 		}
 		self.stop = lexer.current.clone();
 		if lexer.current == Token::Eol {
@@ -207,8 +268,30 @@ impl Compiler {
 
 
 	/* 	== HELPER FUNCTIONS == */
+
+	/* Moves to the right operand of an infix operator. A newline ends a statement, except
+	 * right after an operator, where the expression carries on to the next line. An operator
+	 * with no operand before the stop token or the end of input is a syntax error. */
+	fn operand(&mut self, lexer: &mut Lexer, stop: &Vec<Token>) -> BoolResult {
+		loop {
+			match lexer.current {
+				Token::Eol | Token::White(_) 	=> { lexer.next_token()?; },
+				_ => { break }
+			}
+		}
+		if lexer.is_match(stop) || lexer.current == Token::Eof {
+			return error(ErrorType::Syntax, format!("Stop token was reached before infix operation was finished parsing."))
+		}
+		return Ok(false)
+	}
+
 	fn ending(&mut self, lexer: &mut Lexer, expr: Expr, stop: &Vec<Token>) -> ParseResult {
 		lexer.next_token()?;
+		return self.ending_at(lexer, expr, stop)
+	}
+
+	/* Like 'ending' but for primaries whose closing token 'parse_until' already consumed. */
+	fn ending_at(&mut self, lexer: &mut Lexer, expr: Expr, stop: &Vec<Token>) -> ParseResult {
 		match lexer.current {
 			Token::White(_) | Token::Blank	=> { lexer.next_token()?; },
 			Token::Lparen => { 
@@ -221,7 +304,7 @@ impl Compiler {
 				self.stop = Token::Blank;
 				// TODO: Check here for assignment!
 				let exp = Expr::Call(results, Box::new(expr));
-				return self.ending(lexer, exp, stop)
+				return self.ending_at(lexer, exp, stop)
 			},
 			// TODO: Lbrack, Lbrace, (period?, infix?)
 			_ => { }
@@ -238,3 +321,63 @@ impl Compiler {
 
 }
 
+
+
+#[cfg(test)]
+mod tests {
+	use crate::code::Opcode;
+	use crate::compare::equal_words;
+	use crate::error::ErrorType;
+	use crate::vm::tests::run;
+	use crate::words::Word;
+
+	fn check(src: &str, expected: Vec<Word>) {
+		let stack = run(src).unwrap();
+		assert!(stack.len() == expected.len() && stack.iter().zip(expected.iter()).all(|(a, b)| equal_words(a, b)), "{:?}: {:?}", src, stack);
+	}
+
+	#[test]
+	fn assignment_ends() {
+		check("x = 1; y = 2; x y", vec![Word::Int(1), Word::Int(2)]);
+		check("x = 1\ny = x 1 add\ny", vec![Word::Int(2)]);
+		check("[x = 5; x] do", vec![Word::Int(5)]);
+		check("[x = 5\n x] do", vec![Word::Int(5)]);
+		check("[x = 5] do 7", vec![Word::Int(7)]);
+		check("[f := 2 mul; 3 f] do", vec![Word::Int(6)]);
+		check("i = 0; 3 [i = i + 1] times i", vec![Word::Int(3)]);
+	}
+
+	#[test]
+	fn semicolon_in_quotes() {
+		check("[1; 2] do", vec![Word::Int(1), Word::Int(2)]);
+		check("(1; 2)", vec![Word::Int(1), Word::Int(2)]);
+		match &run("[[1] [2 3]; 4]").unwrap()[..] {
+			[Word::Quote(q)] 	=> { assert_eq!(q.iter().filter(|op| matches!(op, Opcode::Quote(_) | Opcode::Const(_))).count(), 3) },
+			s => { panic!("{:?}", s) }
+		}
+	}
+
+	/* A closing bracket is followed directly by the next token, which must not be skipped. */
+	#[test]
+	fn after_bracket() {
+		check("(1 + 2)4", vec![Word::Int(3), Word::Int(4)]);
+		check("(1 + 2);4", vec![Word::Int(3), Word::Int(4)]);
+		check("x = [1 2]; y = 3; y", vec![Word::Int(3)]);
+		assert_eq!(run("[1]2").unwrap().len(), 2);
+	}
+
+	#[test]
+	fn newline_ends_statement() {
+		check("1 + 2\n3", vec![Word::Int(3), Word::Int(3)]);
+		check("1 +\n2", vec![Word::Int(3)]);
+		check("x = 1 +\n\n    2\nx", vec![Word::Int(3)]);
+		check("[1 +\n2] do", vec![Word::Int(3)]);
+	}
+
+	#[test]
+	fn missing_operand() {
+		for src in ["1 +", "1 +\n", "1 + ; 2", "[1 +]", "(2 *)"] {
+			assert_eq!(run(src).unwrap_err().kind, ErrorType::Syntax, "{:?}", src);
+		}
+	}
+}
@@ -0,0 +1,594 @@
+/* dist.rs
+ * ------- */
+use crate::code::{Dist, DistOp, sprint_distop};
+use crate::error::{ErrorType, error};
+use crate::random::Rng;
+use crate::vm::Vm;
+use crate::words::{BoolResult, Word, list2array1, toF64};
+
+use ndarray::Array1;
+use std::f64::consts::PI;
+use std::f64::{INFINITY, NAN};
+
+const EPS: f64 = 1e-15;
+const MAX_ITER: usize = 300;
+
+/* Density, distribution, quantile and random-draw words, named as in R ('dnorm', 'pnorm',
+ * 'qnorm', 'rnorm', ...). The first argument is followed by the parameters of the distribution,
+ * and any of them can be a vector, in which case the others are recycled. For random draws the
+ * first argument is the number of draws. Invalid parameters give NaN, as in R.
+ *
+ * Parameters with a default in R may be left out from the end: '5 rnorm' and '1.96 pnorm' use
+ * a mean of 0 and a standard deviation of 1. The word takes as many numbers (or vectors) from
+ * the top of the stack as the full form has, so with other numbers underneath, all parameters
+ * must be given. */
+pub fn do_dist(vm: &mut Vm, op: DistOp) -> BoolResult {
+	let dist = match &op { DistOp::Cdf(d) | DistOp::Density(d) | DistOp::Quantile(d) | DistOp::Random(d) => d.clone() };
+	let defaults = defaults(&dist);
+	let full = 1 + nparams(&dist);
+	let required = full - defaults.len();
+	let numeric = vm.stack.iter().rev().take(full).take_while(|w| matches!(w, Word::Int(_) | Word::Num(_) | Word::Vect(_) | Word::List(_) | Word::Quote(_))).count();
+	let nargs = numeric.max(required);
+	if vm.stack.len() < nargs {
+		return error(ErrorType::Underflow, format!("'{}' requires at least {} objects on top of the stack. Only {} found.", sprint_distop(&op), nargs, vm.stack.len()))
+	}
+	let mut args = vm.stack.split_off(vm.stack.len() - nargs);
+	args.extend(defaults[defaults.len() + nargs - full ..].iter().map(|d| Word::Num(*d)));
+	let mut cols: Vec<Array1<f64>> = Vec::with_capacity(full);
+	let mut vector = false;
+	for w in args.iter() {
+		match w {
+			Word::Int(_) | Word::Num(_) 	=> { cols.push(Array1::from_elem(1, toF64(w))) },
+			Word::Vect(v) 	=> { vector = true; cols.push(*v.clone()) },
+			Word::List(l) 	=> { vector = true; cols.push(list2array1(l)) },
+			Word::Quote(q) 	=> { vector = true; cols.push(list2array1(&vm.run_newstack(q.to_vec())?)) },
+			_ => { return error(ErrorType::Type, format!("'{}' cannot be completed on objects of type '{}'.", sprint_distop(&op), w.sprint_type())) }
+		}
+	}
+	if let DistOp::Random(_) = op {
+		let n = match args[0] {
+			Word::Int(i) if i >= 0 	=> { i as usize },
+			_ => { return error(ErrorType::Type, format!("'{}' requires a non-negative number of draws of type 'int'. Given: '{}'.", sprint_distop(&op), args[0].sprint())) }
+		};
+		if cols[1..].iter().any(|c| c.len() != 1 && c.len() != n) {
+			return error(ErrorType::Index, format!("'{}' requires parameters of length 1 or {}.", sprint_distop(&op), n))
+		}
+		let mut draws = Array1::<f64>::zeros(n);
+		for i in 0 .. n {
+			let a: Vec<f64> = cols[1..].iter().map(|c| if c.len() == 1 { c[0] } else { c[i] }).collect();
+			draws[i] = draw(&dist, &a, &mut vm.rng);
+		}
+		return vm.push_const(Word::Vect(Box::new(draws)))
+	}
+	let n = if cols.iter().any(|c| c.len() == 0) { 0 } else { cols.iter().map(|c| c.len()).max().unwrap() };
+	if cols.iter().any(|c| c.len() != 1 && c.len() != n) {
+		return error(ErrorType::Index, format!("'{}' requires arguments of length 1 or {}.", sprint_distop(&op), n))
+	}
+	let eval = |i: usize| {
+		let a: Vec<f64> = cols.iter().map(|c| if c.len() == 1 { c[0] } else { c[i] }).collect();
+		match &op {
+			DistOp::Cdf(_) 		=> { cdf(&dist, a[0], &a[1..]) },
+			DistOp::Density(_) 	=> { density(&dist, a[0], &a[1..]) },
+			_ 	=> { quantile(&dist, a[0], &a[1..]) }
+		}
+	};
+	if !vector { return vm.push_const(Word::Num(eval(0))) }
+	return vm.push_const(Word::Vect(Box::new((0 .. n).map(eval).collect())))
+}
+
+pub fn nparams(dist: &Dist) -> usize {
+	match dist {
+		Dist::Beta | Dist::Binom | Dist::F | Dist::Gamma | Dist::Norm | Dist::Unif 	=> { return 2 },
+		_ => { return 1 }
+	}
+}
+
+/* R's defaults for the trailing parameters of a distribution. */
+fn defaults(dist: &Dist) -> &'static [f64] {
+	match dist {
+		Dist::Norm | Dist::Unif => { return &[0.0, 1.0] },
+		Dist::Exp | Dist::Gamma => { return &[1.0] },
+		_ => { return &[] }
+	}
+}
+
+
+/* 	== DENSITIES == */
+
+/* Parameters follow R's defaults: 'gamma' and 'exp' take a rate, 'geom' counts the failures
+ * before the first success. */
+pub fn density(dist: &Dist, x: f64, p: &[f64]) -> f64 {
+	if x.is_nan() || p.iter().any(|v| v.is_nan()) { return NAN }
+	match dist {
+		Dist::Beta 	=> {
+			let (a, b) = (p[0], p[1]);
+			if a <= 0.0 || b <= 0.0 { return NAN }
+			if x < 0.0 || x > 1.0 { return 0.0 }
+			return (xlogy(a - 1.0, x) + xlogy(b - 1.0, 1.0 - x) - ln_beta(a, b)).exp()
+		},
+		Dist::Binom => {
+			let (n, q) = (p[0], p[1]);
+			if !is_count(n) || q < 0.0 || q > 1.0 { return NAN }
+			if !is_count(x) || x > n { return 0.0 }
+			return (ln_choose(n, x) + xlogy(x, q) + xlogy(n - x, 1.0 - q)).exp()
+		},
+		Dist::Chisq => {
+			if p[0] <= 0.0 { return NAN }
+			return density(&Dist::Gamma, x, &[p[0] / 2.0, 0.5])
+		},
+		Dist::Exp 	=> {
+			if p[0] <= 0.0 { return NAN }
+			if x < 0.0 { return 0.0 }
+			return p[0] * (-p[0] * x).exp()
+		},
+		Dist::F 	=> {
+			let (m, n) = (p[0], p[1]);
+			if m <= 0.0 || n <= 0.0 { return NAN }
+			if x < 0.0 { return 0.0 }
+			if x == 0.0 { return if m < 2.0 { INFINITY } else if m == 2.0 { 1.0 } else { 0.0 } }
+			let ln = 0.5 * (m * (m * x).ln() + n * n.ln() - (m + n) * (m * x + n).ln()) - x.ln() - ln_beta(m / 2.0, n / 2.0);
+			return ln.exp()
+		},
+		Dist::Gamma => {
+			let (a, r) = (p[0], p[1]);
+			if a <= 0.0 || r <= 0.0 { return NAN }
+			if x < 0.0 { return 0.0 }
+			if x == 0.0 { return if a < 1.0 { INFINITY } else if a == 1.0 { r } else { 0.0 } }
+			return (a * r.ln() + (a - 1.0) * x.ln() - r * x - ln_gamma(a)).exp()
+		},
+		Dist::Geom 	=> {
+			let q = p[0];
+			if q <= 0.0 || q > 1.0 { return NAN }
+			if !is_count(x) { return 0.0 }
+			return (q.ln() + xlogy(x, 1.0 - q)).exp()
+		},
+		Dist::Norm 	=> {
+			let (m, s) = (p[0], p[1]);
+			if s <= 0.0 { return NAN }
+			let z = (x - m) / s;
+			return (-0.5 * z * z).exp() / (s * (2.0 * PI).sqrt())
+		},
+		Dist::Pois 	=> {
+			let l = p[0];
+			if l < 0.0 { return NAN }
+			if !is_count(x) { return 0.0 }
+			return (xlogy(x, l) - l - ln_gamma(x + 1.0)).exp()
+		},
+		Dist::T 	=> {
+			let n = p[0];
+			if n <= 0.0 { return NAN }
+			let ln = ln_gamma((n + 1.0) / 2.0) - ln_gamma(n / 2.0) - 0.5 * (n * PI).ln() - (n + 1.0) / 2.0 * (x * x / n).ln_1p();
+			return ln.exp()
+		},
+		Dist::Unif 	=> {
+			let (a, b) = (p[0], p[1]);
+			if a >= b { return NAN }
+			if x < a || x > b { return 0.0 }
+			return 1.0 / (b - a)
+		}
+	}
+}
+
+
+/* 	== DISTRIBUTION FUNCTIONS == */
+
+pub fn cdf(dist: &Dist, x: f64, p: &[f64]) -> f64 {
+	if x.is_nan() || p.iter().any(|v| v.is_nan()) { return NAN }
+	match dist {
+		Dist::Beta 	=> {
+			if p[0] <= 0.0 || p[1] <= 0.0 { return NAN }
+			return inc_beta(p[0], p[1], x.max(0.0).min(1.0))
+		},
+		Dist::Binom => {
+			let (n, q) = (p[0], p[1]);
+			if !is_count(n) || q < 0.0 || q > 1.0 { return NAN }
+			let k = x.floor();
+			if k < 0.0 { return 0.0 }
+			if k >= n { return 1.0 }
+			return inc_beta(n - k, k + 1.0, 1.0 - q)
+		},
+		Dist::Chisq => {
+			if p[0] <= 0.0 { return NAN }
+			return inc_gamma(p[0] / 2.0, x.max(0.0) / 2.0)
+		},
+		Dist::Exp 	=> {
+			if p[0] <= 0.0 { return NAN }
+			if x <= 0.0 { return 0.0 }
+			return -(-p[0] * x).exp_m1()
+		},
+		Dist::F 	=> {
+			let (m, n) = (p[0], p[1]);
+			if m <= 0.0 || n <= 0.0 { return NAN }
+			if x <= 0.0 { return 0.0 }
+			if x.is_infinite() { return 1.0 }
+			return inc_beta(m / 2.0, n / 2.0, m * x / (m * x + n))
+		},
+		Dist::Gamma => {
+			if p[0] <= 0.0 || p[1] <= 0.0 { return NAN }
+			return inc_gamma(p[0], x.max(0.0) * p[1])
+		},
+		Dist::Geom 	=> {
+			let q = p[0];
+			if q <= 0.0 || q > 1.0 { return NAN }
+			let k = x.floor();
+			if k < 0.0 { return 0.0 }
+			return -((k + 1.0) * (-q).ln_1p()).exp_m1()
+		},
+		Dist::Norm 	=> {
+			if p[1] <= 0.0 { return NAN }
+			return norm_cdf((x - p[0]) / p[1])
+		},
+		Dist::Pois 	=> {
+			let l = p[0];
+			if l < 0.0 { return NAN }
+			let k = x.floor();
+			if k < 0.0 { return 0.0 }
+			if l == 0.0 { return 1.0 }
+			return gamma_upper(k + 1.0, l)
+		},
+		Dist::T 	=> { return t_cdf(x, p[0]) },
+		Dist::Unif 	=> {
+			let (a, b) = (p[0], p[1]);
+			if a >= b { return NAN }
+			return ((x - a) / (b - a)).max(0.0).min(1.0)
+		}
+	}
+}
+
+/* Standard normal distribution function. */
+pub fn norm_cdf(z: f64) -> f64 {
+	if z.is_nan() { return NAN }
+	return 0.5 * erfc(-z / 2.0_f64.sqrt())
+}
+
+/* Complementary error function from the incomplete gamma function, accurate in the tails. */
+fn erfc(x: f64) -> f64 {
+	if x < 0.0 { return 1.0 + inc_gamma(0.5, x * x) }
+	return gamma_upper(0.5, x * x)
+}
+
+/* Cumulative distribution function of Student's t with 'df' degrees of freedom. */
+pub fn t_cdf(t: f64, df: f64) -> f64 {
+	if t.is_nan() || df <= 0.0 { return f64::NAN }
+	if t.is_infinite() { return if t > 0.0 { 1.0 } else { 0.0 } }
+	let tail = 0.5 * inc_beta(df / 2.0, 0.5, df / (df + t * t));
+	if t > 0.0 { return 1.0 - tail }
+	return tail
+}
+
+
+/* 	== QUANTILE FUNCTIONS == */
+
+pub fn quantile(dist: &Dist, q: f64, p: &[f64]) -> f64 {
+	if q.is_nan() || q < 0.0 || q > 1.0 || p.iter().any(|v| v.is_nan()) { return NAN }
+	if !valid(dist, p) { return NAN }
+	match dist {
+		Dist::Exp 	=> { return -(-q).ln_1p() / p[0] },
+		Dist::Geom 	=> {
+			if q == 1.0 { return INFINITY }
+			if p[0] == 1.0 { return 0.0 }
+			return ((-q).ln_1p() / (-p[0]).ln_1p() - 1.0 - 1e-12).ceil().max(0.0)
+		},
+		Dist::Norm 	=> { return p[0] + p[1] * norm_quantile(q) },
+		Dist::Unif 	=> { return p[0] + q * (p[1] - p[0]) },
+		Dist::Binom | Dist::Pois 	=> { return discrete_quantile(dist, q, p) },
+		_ => { return continuous_quantile(dist, q, p) }
+	}
+}
+
+fn valid(dist: &Dist, p: &[f64]) -> bool {
+	match dist {
+		Dist::Binom => { return is_count(p[0]) && p[1] >= 0.0 && p[1] <= 1.0 },
+		Dist::Geom 	=> { return p[0] > 0.0 && p[0] <= 1.0 },
+		Dist::Norm 	=> { return p[1] > 0.0 },
+		Dist::Pois 	=> { return p[0] >= 0.0 },
+		Dist::Unif 	=> { return p[0] < p[1] },
+		_ => { return p.iter().all(|v| *v > 0.0) }
+	}
+}
+
+/* Lowest and highest values of the support of 'dist'. */
+fn support(dist: &Dist, p: &[f64]) -> (f64, f64) {
+	match dist {
+		Dist::Beta 	=> { return (0.0, 1.0) },
+		Dist::Binom => { return (0.0, p[0]) },
+		Dist::Norm | Dist::T 	=> { return (-INFINITY, INFINITY) },
+		Dist::Unif 	=> { return (p[0], p[1]) },
+		_ => { return (0.0, INFINITY) }
+	}
+}
+
+/* Inverts the distribution function by bisection, after growing a bracket for unbounded
+ * supports. */
+fn continuous_quantile(dist: &Dist, q: f64, p: &[f64]) -> f64 {
+	let (lo_s, hi_s) = support(dist, p);
+	if q == 0.0 { return lo_s }
+	if q == 1.0 { return hi_s }
+	let (mut lo, mut hi) = (lo_s.max(-1.0), hi_s.min(1.0));
+	while lo_s.is_infinite() && cdf(dist, lo, p) > q { lo *= 2.0 }
+	while hi_s.is_infinite() && cdf(dist, hi, p) < q { hi *= 2.0 }
+	for _ in 0 .. 2000 {
+		let mid = 0.5 * (lo + hi);
+		if mid <= lo || mid >= hi { break }
+		if cdf(dist, mid, p) < q { lo = mid } else { hi = mid }
+	}
+	return 0.5 * (lo + hi)
+}
+
+/* Smallest integer 'k' with 'cdf(k) >= q', allowing for rounding in the distribution function. */
+fn discrete_quantile(dist: &Dist, q: f64, p: &[f64]) -> f64 {
+	let (_, hi_s) = support(dist, p);
+	if q == 1.0 { return hi_s }
+	let target = q * (1.0 - 64.0 * f64::EPSILON);
+	let mut hi = 1.0;
+	while hi < hi_s && cdf(dist, hi, p) < target { hi *= 2.0 }
+	let mut hi = hi.min(hi_s);
+	let mut lo = -1.0;
+	while hi - lo > 1.0 {
+		let mid = ((lo + hi) / 2.0).floor();
+		if cdf(dist, mid, p) >= target { hi = mid } else { lo = mid }
+	}
+	return hi
+}
+
+/* Standard normal quantile: Acklam's rational approximation, refined with one Halley step. */
+pub fn norm_quantile(q: f64) -> f64 {
+	const A: [f64; 6] = [-3.969683028665376e+01, 2.209460984245205e+02, -2.759285104469687e+02, 1.383577518672690e+02, -3.066479806614716e+01, 2.506628277459239e+00];
+	const B: [f64; 5] = [-5.447609879822406e+01, 1.615858368580409e+02, -1.556989798598866e+02, 6.680131188771972e+01, -1.328068155288572e+01];
+	const C: [f64; 6] = [-7.784894002430293e-03, -3.223964580411365e-01, -2.400758277161838e+00, -2.549732539343734e+00, 4.374664141464968e+00, 2.938163982698783e+00];
+	const D: [f64; 4] = [7.784695709041462e-03, 3.224671290700398e-01, 2.445134137142996e+00, 3.754408661907416e+00];
+	if q <= 0.0 { return -INFINITY }
+	if q >= 1.0 { return INFINITY }
+	let tail = |r: f64| (((((C[0] * r + C[1]) * r + C[2]) * r + C[3]) * r + C[4]) * r + C[5]) / ((((D[0] * r + D[1]) * r + D[2]) * r + D[3]) * r + 1.0);
+	let x = if q < 0.02425 {
+		tail((-2.0 * q.ln()).sqrt())
+	} else if q > 1.0 - 0.02425 {
+		-tail((-2.0 * (1.0 - q).ln()).sqrt())
+	} else {
+		let u = q - 0.5;
+		let r = u * u;
+		(((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * u / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+	};
+	let e = norm_cdf(x) - q;
+	let u = e * (2.0 * PI).sqrt() * (x * x / 2.0).exp();
+	return x - u / (1.0 + x * u / 2.0)
+}
+
+
+/* 	== RANDOM DRAWS == */
+
+/* Continuous distributions are drawn from normal and gamma variates, discrete ones by
+ * inverting the distribution function. */
+pub fn draw(dist: &Dist, p: &[f64], rng: &mut Rng) -> f64 {
+	if p.iter().any(|v| v.is_nan()) || !valid(dist, p) { return NAN }
+	match dist {
+		Dist::Beta 	=> {
+			let x = gamma_draw(p[0], rng);
+			return x / (x + gamma_draw(p[1], rng))
+		},
+		Dist::Chisq => { return 2.0 * gamma_draw(p[0] / 2.0, rng) },
+		Dist::Exp 	=> { return -rng.uniform().ln() / p[0] },
+		Dist::F 	=> { return (2.0 * gamma_draw(p[0] / 2.0, rng) / p[0]) / (2.0 * gamma_draw(p[1] / 2.0, rng) / p[1]) },
+		Dist::Gamma => { return gamma_draw(p[0], rng) / p[1] },
+		Dist::Norm 	=> { return p[0] + p[1] * norm_quantile(rng.uniform()) },
+		Dist::T 	=> {
+			let z = norm_quantile(rng.uniform());
+			return z / (2.0 * gamma_draw(p[0] / 2.0, rng) / p[0]).sqrt()
+		},
+		Dist::Unif 	=> { return p[0] + (p[1] - p[0]) * rng.uniform() },
+		_ => { return quantile(dist, rng.uniform(), p) }
+	}
+}
+
+/* Gamma variate with unit rate (Marsaglia and Tsang). Shapes below 1 are boosted by one. */
+fn gamma_draw(a: f64, rng: &mut Rng) -> f64 {
+	if a < 1.0 { return gamma_draw(a + 1.0, rng) * rng.uniform().powf(1.0 / a) }
+	let d = a - 1.0 / 3.0;
+	let c = 1.0 / (9.0 * d).sqrt();
+	loop {
+		let x = norm_quantile(rng.uniform());
+		let v = (1.0 + c * x).powi(3);
+		if v <= 0.0 { continue }
+		if rng.uniform().ln() < 0.5 * x * x + d - d * v + d * v.ln() { return d * v }
+	}
+}
+
+
+/* 	== SPECIAL FUNCTIONS == */
+
+/* Natural log of the gamma function (Lanczos approximation, g = 7). */
+pub fn ln_gamma(x: f64) -> f64 {
+	const G: [f64; 9] = [
+		0.99999999999980993, 676.5203681218851, -1259.1392167224028,
+		771.32342877765313, -176.61502916214059, 12.507343278686905,
+		-0.13857109526572012, 9.9843695780195716e-6, 1.5056327351493116e-7
+	];
+	if x < 0.5 { return (PI / (PI * x).sin()).abs().ln() - ln_gamma(1.0 - x) }
+	let x = x - 1.0;
+	let mut a = G[0];
+	let t = x + 7.5;
+	for i in 1 .. 9 { a += G[i] / (x + i as f64) }
+	return 0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + a.ln()
+}
+
+pub fn ln_beta(a: f64, b: f64) -> f64 {
+	return ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b)
+}
+
+fn ln_choose(n: f64, k: f64) -> f64 {
+	return ln_gamma(n + 1.0) - ln_gamma(k + 1.0) - ln_gamma(n - k + 1.0)
+}
+
+/* 'x * ln(y)', taken as 0 when 'x' is 0. */
+fn xlogy(x: f64, y: f64) -> f64 {
+	if x == 0.0 { return 0.0 }
+	return x * y.ln()
+}
+
+fn is_count(x: f64) -> bool {
+	return x >= 0.0 && x.fract() == 0.0 && x.is_finite()
+}
+
+/* Regularized incomplete beta function I_x(a, b), by Lentz's continued fraction. */
+pub fn inc_beta(a: f64, b: f64, x: f64) -> f64 {
+	if x.is_nan() || a <= 0.0 || b <= 0.0 { return f64::NAN }
+	if x <= 0.0 { return 0.0 }
+	if x >= 1.0 { return 1.0 }
+	let front = (a * x.ln() + b * (1.0 - x).ln() - ln_beta(a, b)).exp();
+	if x > (a + 1.0) / (a + b + 2.0) { return 1.0 - front * beta_cf(b, a, 1.0 - x) / b }
+	return front * beta_cf(a, b, x) / a
+}
+
+fn beta_cf(a: f64, b: f64, x: f64) -> f64 {
+	let tiny = 1e-300;
+	let mut c = 1.0;
+	let mut d = 1.0 - (a + b) * x / (a + 1.0);
+	if d.abs() < tiny { d = tiny }
+	d = 1.0 / d;
+	let mut f = d;
+	for m in 1 .. MAX_ITER {
+		let m = m as f64;
+		let num = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+		d = 1.0 + num * d;
+		if d.abs() < tiny { d = tiny }
+		c = 1.0 + num / c;
+		if c.abs() < tiny { c = tiny }
+		d = 1.0 / d;
+		f *= d * c;
+		let num = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+		d = 1.0 + num * d;
+		if d.abs() < tiny { d = tiny }
+		c = 1.0 + num / c;
+		if c.abs() < tiny { c = tiny }
+		d = 1.0 / d;
+		let delta = d * c;
+		f *= delta;
+		if (delta - 1.0).abs() < EPS { break }
+	}
+	return f
+}
+
+/* Regularized lower incomplete gamma function P(a, x). */
+pub fn inc_gamma(a: f64, x: f64) -> f64 {
+	if x.is_nan() || a <= 0.0 { return NAN }
+	if x <= 0.0 { return 0.0 }
+	if x.is_infinite() { return 1.0 }
+	if x < a + 1.0 { return gamma_series(a, x) }
+	return 1.0 - gamma_cf(a, x)
+}
+
+/* Regularized upper incomplete gamma function Q(a, x) = 1 - P(a, x). */
+pub fn gamma_upper(a: f64, x: f64) -> f64 {
+	if x.is_nan() || a <= 0.0 { return NAN }
+	if x <= 0.0 { return 1.0 }
+	if x.is_infinite() { return 0.0 }
+	if x < a + 1.0 { return 1.0 - gamma_series(a, x) }
+	return gamma_cf(a, x)
+}
+
+fn gamma_series(a: f64, x: f64) -> f64 {
+	let mut ap = a;
+	let mut del = 1.0 / a;
+	let mut sum = del;
+	for _ in 0 .. MAX_ITER * 10 {
+		ap += 1.0;
+		del *= x / ap;
+		sum += del;
+		if del.abs() < sum.abs() * EPS { break }
+	}
+	return sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+}
+
+fn gamma_cf(a: f64, x: f64) -> f64 {
+	let tiny = 1e-300;
+	let mut b = x + 1.0 - a;
+	let mut c = 1.0 / tiny;
+	let mut d = 1.0 / b;
+	let mut h = d;
+	for i in 1 .. MAX_ITER * 10 {
+		let an = -(i as f64) * (i as f64 - a);
+		b += 2.0;
+		d = an * d + b;
+		if d.abs() < tiny { d = tiny }
+		c = b + an / c;
+		if c.abs() < tiny { c = tiny }
+		d = 1.0 / d;
+		let delta = d * c;
+		h *= delta;
+		if (delta - 1.0).abs() < EPS { break }
+	}
+	return (-x + a * x.ln() - ln_gamma(a)).exp() * h
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn close(a: f64, b: f64) -> bool {
+		return (a - b).abs() < 1e-10
+	}
+
+	#[test]
+	fn inc_beta_closed_forms() {
+		/* I_x(a, 1) = x^a and I_x(1, b) = 1 - (1 - x)^b. */
+		assert!(close(inc_beta(3.0, 1.0, 0.4), 0.4f64.powi(3)));
+		assert!(close(inc_beta(1.0, 4.0, 0.3), 1.0 - 0.7f64.powi(4)));
+		assert!(close(inc_beta(2.0, 3.0, 0.5), 11.0 / 16.0));
+		for (a, b, x) in [(0.5, 2.5, 0.2), (7.0, 3.0, 0.9), (30.0, 0.5, 0.95)] {
+			assert!(close(inc_beta(a, b, x), 1.0 - inc_beta(b, a, 1.0 - x)));
+		}
+	}
+
+	#[test]
+	fn t_cdf_closed_forms() {
+		/* One degree of freedom is the Cauchy distribution, and two has an algebraic form. */
+		for t in [-3.0, -0.5, 0.0, 1.0, 12.0] {
+			assert!(close(t_cdf(t, 1.0), 0.5 + f64::atan(t) / std::f64::consts::PI));
+			assert!(close(t_cdf(t, 2.0), 0.5 + t / (2.0 * (2.0 + t * t).sqrt())));
+		}
+	}
+
+	#[test]
+	fn default_parameters() {
+		use crate::vm::tests::run;
+		let num = |src: &str| match run(src).unwrap()[..] { [Word::Num(f)] => f, _ => panic!("{}", src) };
+		assert!(close(num("0 dnorm"), 1.0 / (2.0 * PI).sqrt()));
+		assert!(close(num("1.5 pnorm"), num("1.5 0 1 pnorm")));
+		assert!(close(num("0.25 qunif"), 0.25));
+		assert!(close(num("2 pexp"), 1.0 - (-2.0f64).exp()));
+		assert!(close(num("1 2 dgamma"), num("1 2 1 dgamma")));
+		let draws = |src: &str| match run(src).unwrap().pop() { Some(Word::Vect(v)) => *v, _ => panic!("{}", src) };
+		assert_eq!(draws("3 seed 5 runif"), draws("3 seed 5 0 1 runif"));
+		assert_eq!(draws("3 seed 5 rnorm"), draws("3 seed 5 0 1 rnorm"));
+		assert_eq!(draws("3 seed 5 rexp"), draws("3 seed 5 1 rexp"));
+		assert_eq!(draws("5 runif").len(), 5);
+		/* Only parameters with a default can be left out. */
+		assert_eq!(run("5 rbinom").unwrap_err().kind, ErrorType::Underflow);
+		assert_eq!(run("\"a\" 5 rt").unwrap_err().kind, ErrorType::Type);
+	}
+
+	#[test]
+	fn words() {
+		use crate::vm::tests::run;
+		let num = |src: &str| match run(src).unwrap()[..] { [Word::Num(f)] => f, _ => panic!("{}", src) };
+		assert!(close(num("2 4 0.5 dbinom"), 6.0 / 16.0));
+		assert!(close(num("2 4 0.5 pbinom"), 11.0 / 16.0));
+		assert!(close(num("2 3 dpois"), 4.5 * (-3.0f64).exp()));
+		assert!(close(num("2 2 pchisq"), 1.0 - (-1.0f64).exp()));
+		assert!((num("1.96 pnorm") - 0.9750021).abs() < 1e-7);
+		assert!((num("0.975 qnorm") - 1.959964).abs() < 1e-6);
+		/* Quantiles invert the distribution functions. */
+		assert!(close(num("0.9 5 qt 5 pt"), 0.9));
+		assert!(close(num("0.5 2 3 qbeta 2 3 pbeta"), 0.5));
+		assert!(num("0 0 -1 dnorm").is_nan());
+		assert!(num("3 2 1 dunif").is_nan());
+		match run("[0 1 2] 2 pexp").unwrap().pop() {
+			Some(Word::Vect(v)) 	=> {
+				assert_eq!(v.len(), 3);
+				assert!(close(v[1], 1.0 - (-2.0f64).exp()));
+			},
+			_ => { panic!("not a vector") }
+		}
+	}
+}
@@ -11,7 +11,8 @@ use std::collections::HashMap;
 pub struct Env {
 	pub dict 	: HashMap<String, Word>,
 	pub inst 	: Vec<Opcode>,
-	pub ip 		: usize
+	pub ip 		: usize,
+	pub reuse 	: bool
 }
 
 impl Env {
@@ -19,7 +20,8 @@ impl Env {
 		return Env {
 			dict 	: HashMap::new(),
 			inst	: inst,
-			ip 		: 0
+			ip 		: 0,
+			reuse 	: false
 		}
 	}
 
@@ -1,7 +1,13 @@
 /* error.rs
- * -------- */ 
+ * -------- */
+use crate::code::{Opcode, sprint_opcode_short};
+use crate::lexer::Span;
 use crate::parser::Parser;
 use crate::text_format::*;
+use crate::words::Word;
+
+use std::error::Error;
+use std::fmt;
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -10,37 +16,152 @@ pub enum Message {
 	Message,
 	Note,
 	Traceback(String),
-	Warning 
+	Warning
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ErrorType {
+	Arity,
+	Domain,
 	Index,
+	Io,
 	Overflow,
-	Type
+	Syntax,
+	Type,
+	Undefined,
+	Underflow,
+	User
 }
 
 impl ErrorType {
 	fn sprint(&self) -> String {
 		match self {
+			ErrorType::Arity 		=> { return "ARITY".to_string() },
+			ErrorType::Domain 		=> { return "DOMAIN".to_string() },
 			ErrorType::Index 		=> { return "INDEX".to_string() },
+			ErrorType::Io 			=> { return "IO".to_string() },
 			ErrorType::Overflow 	=> { return "OVERFLOW".to_string() },
+			ErrorType::Syntax 		=> { return "SYNTAX".to_string() },
 			ErrorType::Type 		=> { return "TYPE".to_string() },
+			ErrorType::Undefined 	=> { return "UNDEFINED".to_string() },
+			ErrorType::Underflow 	=> { return "UNDERFLOW".to_string() },
+			ErrorType::User 		=> { return "USER".to_string() },
 		}
 	}
 }
 
+/* Error raised while compiling or evaluating stax code. 'op' and 'loc' are filled in
+ * by the Vm for the innermost instruction that failed, and 'trace' holds the calling
+ * words from the innermost outwards. 'value' is the word given to 'throw'. */
+#[derive(Debug, Clone)]
+pub struct StaxError {
+	pub kind 	: ErrorType,
+	pub msg 	: String,
+	pub op 		: Option<Opcode>,
+	pub loc 	: Option<Span>,
+	pub trace 	: Vec<Frame>,
+	pub value 	: Option<Word>
+}
 
-pub fn format_message(msg: String, typ: Message, parser: &Parser, tok: Option<u16>) -> String {
-	let header : String;
+#[derive(Debug, Clone)]
+pub struct Frame {
+	pub name 	: String,
+	pub loc 	: Option<Span>
+}
+
+impl StaxError {
+	pub fn new(kind: ErrorType, msg: String) -> Self {
+		return StaxError {
+			kind 	: kind,
+			msg 	: msg,
+			op 		: None,
+			loc 	: None,
+			trace 	: Vec::new(),
+			value 	: None
+		}
+	}
+
+	pub fn thrown(w: Word) -> Self {
+		let msg = match &w {
+			Word::Str(s) 	=> { s.to_string() },
+			_ 	=> { w.sprint() }
+		};
+		let mut err = StaxError::new(ErrorType::User, msg);
+		err.value = Some(w);
+		return err
+	}
+
+	/* The word handed to a 'try' handler: the thrown value, or the error message. */
+	pub fn to_word(&self) -> Word {
+		match &self.value {
+			Some(w) => { return w.clone() },
+			None 	=> { return Word::Str(Box::new(format!("{}", self))) }
+		}
+	}
+
+	pub fn sprint(&self) -> String {
+		return format_error(self)
+	}
+}
+
+impl fmt::Display for StaxError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} ERROR: {}", self.kind.sprint(), self.msg)
+	}
+}
+
+impl Error for StaxError { }
+
+pub fn error<T>(kind: ErrorType, msg: String) -> Result<T, StaxError> {
+	return Err(StaxError::new(kind, msg))
+}
+
+
+fn format_header(msg: String, typ: Message) -> String {
 	match typ {
-		Message::Custom(s)		=> { header = format!("{}: {}", bold(green(s)), msg) },
-		Message::Error(e) 		=> { header = bold(format!("{}: {}", red(format!("{} ERROR", e.sprint())), msg)) },
-		Message::Message  		=> { header = msg },
-		Message::Note 	 		=> { header = format!("{}: {}", bold(cyan("NOTE".to_string())), msg) },
-		Message::Traceback(s)	=> { header = format!("{}: {}", bold(format!("{}, '{}'", red("TRACEBACK".to_string()), s)), msg) },
-		Message::Warning		=> { header = bold(format!("{}: {}", yellow("WARNING".to_string()), msg)) }
+		Message::Custom(s)		=> { return format!("{}: {}", bold(green(s)), msg) },
+		Message::Error(e) 		=> { return bold(format!("{}: {}", red(format!("{} ERROR", e.sprint())), msg)) },
+		Message::Message  		=> { return msg },
+		Message::Note 	 		=> { return format!("{}: {}", bold(cyan("NOTE".to_string())), msg) },
+		Message::Traceback(s)	=> { return format!("{}: {}", bold(format!("{}, '{}'", red("TRACEBACK".to_string()), s)), msg) },
+		Message::Warning		=> { return bold(format!("{}: {}", yellow("WARNING".to_string()), msg)) }
 	}
+}
+
+fn format_pointer(loc: &Option<Span>) -> String {
+	match loc {
+		Some(s) => { return format!("    {} {}", blue("-->".to_string()), s.sprint()) },
+		None 	=> { return format!("    {} <unknown>", blue("-->".to_string())) }
+	}
+}
+
+pub fn format_error(err: &StaxError) -> String {
+	let mut lines = vec![format_header(err.msg.clone(), Message::Error(err.kind))];
+	match &err.op {
+		Some(o) => { lines.push(format!("{}, in '{}'", format_pointer(&err.loc), sprint_opcode_short(o))) },
+		None 	=> { }
+	}
+	let mut i = 0;
+	while i < err.trace.len() {
+		let frame = &err.trace[i];
+		lines.push(format_header("called from".to_string(), Message::Traceback(frame.name.clone())));
+		lines.push(format_pointer(&frame.loc));
+		/* Deep recursion repeats the same few frames, so collapse identical runs. */
+		let mut n = 1;
+		while i + n < err.trace.len() && same_frame(frame, &err.trace[i + n]) { n += 1 }
+		if n > 1 { lines.push(format!("    ... repeated {} more times", n - 1)) }
+		i += n;
+	}
+	return lines.join("\n")
+}
+
+fn same_frame(a: &Frame, b: &Frame) -> bool {
+	let loc = |f: &Frame| f.loc.as_ref().map(|s| s.sprint());
+	return a.name == b.name && loc(a) == loc(b)
+}
+
+pub fn format_message(msg: String, typ: Message, parser: &Parser, tok: Option<u16>) -> String {
+	let header = format_header(msg, typ);
 	match tok {
 		Some(u) => {
 			let (start, _, wordn) = parser.extract_token(u);
@@ -54,4 +175,4 @@ pub fn format_message(msg: String, typ: Message, parser: &Parser, tok: Option<u1
 		},
 		None => { return header }
 	}
-}
\ No newline at end of file
+}
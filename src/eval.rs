@@ -3,24 +3,37 @@
 use crate::arith::do_arith;
 use crate::auto::do_auto;
 use crate::code::{Opcode, ConstCode};
-use crate::combinator::{do_comb1, do_comb2, do_comb3};
+use crate::combinator::{do_comb1, do_comb2, do_comb3, do_comb4};
 use crate::compare::do_compare;
+use crate::dist::do_dist;
+use crate::error::{ErrorType, error};
+use crate::factor::do_factor;
+use crate::frame::do_frame;
 use crate::genop::do_general;
+use crate::index::do_index;
+use crate::io::do_io;
+use crate::linalg::do_linalg;
 use crate::math::do_math;
+use crate::matrix::do_matrix;
+use crate::na::do_na;
 use crate::parser::Parser;
+use crate::random::do_random;
+use crate::sort::do_sort;
 use crate::stackop::do_stack;
+use crate::stats::do_stats;
 use crate::unop::do_un;
 use crate::vm::Vm;
-use crate::words::Word;
+use crate::words::{BoolResult, Word};
 
 use std::mem;
 
-pub fn eval_inst(op: Opcode, vm: &mut Vm) -> Result<bool, String> {
+pub fn eval_inst(op: Opcode, vm: &mut Vm) -> BoolResult {
 	match op {
 		Opcode::Const(c) => match c {
 			ConstCode::Int(i) 	=> { return vm.push_const(Word::Int(i)) },
 			ConstCode::True 	=> { return vm.push_const(Word::Bool(true)) },
 			ConstCode::False	=> { return vm.push_const(Word::Bool(false)) },
+			ConstCode::Na		=> { return vm.push_const(Word::Na) },
 			ConstCode::Null		=> { return vm.push_const(Word::Null) },
 		},
 		Opcode::Num(f) => { vm.push_const(Word::Num(f)) },
@@ -31,7 +44,7 @@ pub fn eval_inst(op: Opcode, vm: &mut Vm) -> Result<bool, String> {
 					let word = w.clone();
 					return vm.run_word(word) 
 				},
-				None => { return Err(format!("Object '{}' could not be found.", s)) }
+				None => { return error(ErrorType::Undefined, format!("Object '{}' could not be found.", s)) }
 			}
 			
 		}, // TODO!!
@@ -40,13 +53,13 @@ pub fn eval_inst(op: Opcode, vm: &mut Vm) -> Result<bool, String> {
 		Opcode::BinOp(o) 	=> {
 			match vm.stack.pop() {
 				Some(rhs) => match vm.stack.pop() {
-					Some(lhs) => match do_arith(vm, o, &lhs, &rhs) {
+					Some(lhs) => match do_arith(o, &lhs, &rhs) {
 						Ok(x)  => { vm.push_const_nocheck(x); return Ok(false) },
 						Err(e) => { return Err(e) }
 					},
-					None => { return Err(format!("Operation requires 2 objects on top of the stack. Only 1 found.")) }
+					None => { return error(ErrorType::Underflow, format!("Operation requires 2 objects on top of the stack. Only 1 found.")) }
 				},
-				None => { return Err(format!("Operation requires 2 objects on top of the stack. None found.")) }
+				None => { return error(ErrorType::Underflow, format!("Operation requires 2 objects on top of the stack. None found.")) }
 			}
 		},
 		Opcode::BoolOp(o) => {
@@ -56,9 +69,9 @@ pub fn eval_inst(op: Opcode, vm: &mut Vm) -> Result<bool, String> {
 						Ok(x)  => { vm.push_const_nocheck(x); return Ok(false) },
 						Err(e) => { return Err(e) }
 					},
-					None => { return Err(format!("Operation requires 2 objects on top of the stack. Only 1 found.")) }
+					None => { return error(ErrorType::Underflow, format!("Operation requires 2 objects on top of the stack. Only 1 found.")) }
 				},
-				None => { return Err(format!("Operation requires 2 objects on top of the stack. None found.")) }
+				None => { return error(ErrorType::Underflow, format!("Operation requires 2 objects on top of the stack. None found.")) }
 			}
 		},
 		Opcode::UnOp(o) => {
@@ -70,13 +83,25 @@ pub fn eval_inst(op: Opcode, vm: &mut Vm) -> Result<bool, String> {
 					},
 					Err(e) => { return Err(e) }
 				},
-				None => { return Err(format!("Operation requires at least 1 object on top of the stack. None found.")) }
+				None => { return error(ErrorType::Underflow, format!("Operation requires at least 1 object on top of the stack. None found.")) }
 			}
 		},
 		Opcode::StackOp(o) 	=> { return do_stack(vm, o) },
 		Opcode::Comb1(c) 	=> { return do_comb1(vm, c) },
 		Opcode::Comb2(c) 	=> { return do_comb2(vm, c) },
 		Opcode::Comb3(c) 	=> { return do_comb3(vm, c) },
+		Opcode::Comb4(c) 	=> { return do_comb4(vm, c) },
+		Opcode::DistOp(d) 	=> { return do_dist(vm, d) },
+		Opcode::FactorOp(f) 	=> { return do_factor(vm, f) },
+		Opcode::FrameOp(f) 	=> { return do_frame(vm, f) },
+		Opcode::IndexOp(i) 	=> { return do_index(vm, i) },
+		Opcode::IoOp(i) 	=> { return do_io(vm, i) },
+		Opcode::LinOp(l) 	=> { return do_linalg(vm, l) },
+		Opcode::MatOp(m) 	=> { return do_matrix(vm, m) },
+		Opcode::NaOp(n) 	=> { return do_na(vm, n) },
+		Opcode::RandOp(r) 	=> { return do_random(vm, r) },
+		Opcode::SortOp(s) 	=> { return do_sort(vm, s) },
+		Opcode::StatOp(s) 	=> { return do_stats(vm, s) },
 		Opcode::MathOp(m) 	=> {
 			match vm.stack.pop() {
 				Some(w) => { 
@@ -85,7 +110,7 @@ pub fn eval_inst(op: Opcode, vm: &mut Vm) -> Result<bool, String> {
 						Err(e) 		=> { return Err(e) }
 					} 
 				},
-				None => { return Err(format!("Operation requires at least 1 object on top of the stack. None found.")) }
+				None => { return error(ErrorType::Underflow, format!("Operation requires at least 1 object on top of the stack. None found.")) }
 			}
 
 		},
@@ -97,7 +122,7 @@ pub fn eval_inst(op: Opcode, vm: &mut Vm) -> Result<bool, String> {
 					vm.curr_mut().dict.insert(*s, word);
 					return Ok(false)
 				},
-				None => { return Err(format!("No objects found with which to set to '{}' with '='.", *s)) }
+				None => { return error(ErrorType::Underflow, format!("No objects found with which to set to '{}' with '='.", *s)) }
 			}
 		},
 		Opcode::SetProg(s)	=> {
@@ -112,7 +137,7 @@ pub fn eval_inst(op: Opcode, vm: &mut Vm) -> Result<bool, String> {
 						return Ok(false)
 					}
 				},
-				None => { return Err(format!("No objects found with which to set to '{}' with '='.", *s)) }
+				None => { return error(ErrorType::Underflow, format!("No objects found with which to set to '{}' with '='.", *s)) }
 			}
 		},
 
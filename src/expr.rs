@@ -1,6 +1,6 @@
 /* expr.rs
  * ------- */
-use crate::code::Opcode;
+use crate::code::{GenOp, Opcode};
 use crate::lexer::Token;
 
 use std::mem;
@@ -79,6 +79,11 @@ pub fn walk_expr_assign(expr : Expr, tok: Token) -> Vec<Opcode> {
             },
             _   => { return vec![code] }  
         },
+        Expr::LeftCode(code @ Opcode::GenOp(GenOp::Loc(_)), rhs) => {
+            let mut result = vec![code];
+            result.append( &mut walk_expr_assign(*rhs, tok) );
+            return result
+        },
         _ => { return walk_expr(expr) }
     }
 }
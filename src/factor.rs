@@ -0,0 +1,201 @@
+/* factor.rs
+ * --------- */
+use crate::code::{FactorOp, sprint_factorop};
+use crate::code::FactorOp::*;
+use crate::error::{ErrorType, StaxError, error};
+use crate::na::is_na;
+use crate::vm::Vm;
+use crate::words::{BoolResult, Word, num_word, str_vect, tighten};
+
+use ndarray::Array1;
+use std::collections::HashMap;
+
+/* Categorical data: one code per element into a table of levels, with 'None' for NA. The
+ * level order is the order used for printing, grouping and model contrasts. */
+#[derive(Debug, Clone)]
+pub struct Factor {
+	pub codes: Vec<Option<usize>>,
+	pub levels: Vec<String>
+}
+
+impl Factor {
+	/* Codes each label by its position in 'levels'. Labels that are not levels become NA. */
+	pub fn new(labels: &[Option<String>], levels: Vec<String>) -> Factor {
+		let index: HashMap<&str, usize> = levels.iter().enumerate().map(|(i, l)| (l.as_str(), i)).collect();
+		let codes = labels.iter().map(|l| l.as_ref().and_then(|l| index.get(l.as_str()).cloned())).collect();
+		return Factor { codes: codes, levels: levels }
+	}
+
+	pub fn len(&self) -> usize {
+		return self.codes.len()
+	}
+
+	pub fn labels(&self) -> Vec<Option<String>> {
+		return self.codes.iter().map(|c| c.map(|c| self.levels[c].clone())).collect()
+	}
+
+	/* The elements at the given positions, keeping every level. */
+	pub fn take(&self, idx: &[usize]) -> Factor {
+		return Factor { codes: idx.iter().map(|i| self.codes[*i]).collect(), levels: self.levels.clone() }
+	}
+
+	/* Elements as in a vector, then the levels in order, as R prints them. */
+	pub fn sprint(&self) -> String {
+		let items = self.labels().into_iter().map(|l| l.unwrap_or(format!("NA"))).collect::<Vec<String>>();
+		return format!("[{}]\nLevels: {}", items.join(", "), self.levels.join(" "))
+	}
+}
+
+pub fn do_factor(vm: &mut Vm, op: FactorOp) -> BoolResult {
+	let nargs = match op { Relevel => 2, _ => 1 };
+	if vm.stack.len() < nargs {
+		return error(ErrorType::Underflow, format!("'{}' requires {} objects on top of the stack. Only {} found.", sprint_factorop(&op), nargs, vm.stack.len()))
+	}
+	let args = vm.stack.split_off(vm.stack.len() - nargs);
+	let args: Vec<Word> = args.into_iter().map(|w| match w {
+		Word::Quote(q) 	=> { Ok(tighten(vm.run_newstack(q.to_vec())?)) },
+		_ => { Ok(w) }
+	}).collect::<Result<Vec<Word>, StaxError>>()?;
+	let result: Word;
+	match op {
+		AsStr 	=> match &args[0] {
+			Word::Factor(f) 	=> { result = Word::StrVect(Box::new(Array1::from_vec(f.labels()))) },
+			Word::StrVect(_) 	=> { result = args[0].clone() },
+			w => match labels(w) {
+				Some(l) => { result = Word::StrVect(Box::new(Array1::from_vec(l))) },
+				None 	=> { return type_error(w, &op) }
+			}
+		},
+		Droplevels 	=> {
+			let f = factor_arg(&args[0], &op)?;
+			let used: Vec<String> = f.levels.iter().enumerate().filter(|(i, _)| f.codes.contains(&Some(*i))).map(|(_, l)| l.clone()).collect();
+			result = Word::Factor(Box::new(Factor::new(&f.labels(), used)))
+		},
+		FactorOp::Factor 	=> { result = Word::Factor(Box::new(to_factor(&args[0], "factor")?)) },
+		Levels 	=> { result = str_vect(factor_arg(&args[0], &op)?.levels) },
+		Relevel => {
+			let f = factor_arg(&args[0], &op)?;
+			let front: Vec<String> = match &args[1] {
+				Word::Str(s) 	=> { vec![*s.clone()] },
+				Word::StrVect(v) 	=> { crate::frame::names(v)? },
+				w => { return error(ErrorType::Type, format!("'relevel' requires levels of type 'str' or 'vec<str>'. Given: '{}'.", w.sprint_type())) }
+			};
+			if let Some(l) = front.iter().find(|l| !f.levels.contains(l)) {
+				return error(ErrorType::Index, format!("\"{}\" is not a level of the factor.", l))
+			}
+			let mut levels = front.clone();
+			levels.extend(f.levels.iter().filter(|l| !front.contains(l)).cloned());
+			result = Word::Factor(Box::new(Factor::new(&f.labels(), levels)))
+		}
+	}
+	return vm.push_const(result)
+}
+
+/* Levels are the distinct values in increasing order, numerically for numbers as in R. A
+ * factor given again drops its unused levels. */
+pub fn to_factor(w: &Word, name: &str) -> Result<Factor, StaxError> {
+	match w {
+		Word::Factor(f) 	=> {
+			let used: Vec<String> = f.levels.iter().enumerate().filter(|(i, _)| f.codes.contains(&Some(*i))).map(|(_, l)| l.clone()).collect();
+			return Ok(Factor::new(&f.labels(), used))
+		},
+		Word::Vect(v) 	=> {
+			let mut values: Vec<f64> = v.iter().cloned().filter(|f| !is_na(*f)).collect();
+			values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+			values.dedup_by(|a, b| a == b || (a.is_nan() && b.is_nan()));
+			let levels = values.iter().map(|f| num_word(*f).sprint()).collect();
+			return Ok(Factor::new(&labels(w).unwrap_or(vec![]), levels))
+		},
+		Word::BoolVect(_) | Word::StrVect(_) 	=> {
+			let labels = labels(w).unwrap_or(vec![]);
+			let mut levels: Vec<String> = labels.iter().filter_map(|l| l.clone()).collect();
+			levels.sort();
+			levels.dedup();
+			return Ok(Factor::new(&labels, levels))
+		},
+		_ => { return error(ErrorType::Type, format!("'{}' cannot be completed on objects of type '{}'.", name, w.sprint_type())) }
+	}
+}
+
+
+/* 	== HELPERS == */
+
+/* The elements of a vector as text, with NA kept missing. */
+fn labels(w: &Word) -> Option<Vec<Option<String>>> {
+	match w {
+		Word::BoolVect(v) 	=> { return Some(v.iter().map(|b| b.map(|b| b.to_string())).collect()) },
+		Word::Factor(f) 	=> { return Some(f.labels()) },
+		Word::StrVect(v) 	=> { return Some(v.to_vec()) },
+		Word::Vect(v) 		=> { return Some(v.iter().map(|f| if is_na(*f) { None } else { Some(num_word(*f).sprint()) }).collect()) },
+		_ => { return None }
+	}
+}
+
+fn factor_arg(w: &Word, op: &FactorOp) -> Result<Factor, StaxError> {
+	match w {
+		Word::Factor(f) 	=> { return Ok(*f.clone()) },
+		_ => { return error(ErrorType::Type, format!("'{}' requires an object of type 'factor'. Given: '{}'.", sprint_factorop(op), w.sprint_type())) }
+	}
+}
+
+fn type_error(w: &Word, op: &FactorOp) -> BoolResult {
+	return error(ErrorType::Type, format!("'{}' cannot be completed on objects of type '{}'.", sprint_factorop(op), w.sprint_type()))
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vm::tests::run;
+
+	const F: &str = "f = [\"b\" \"a\" NA \"b\"] factor;";
+
+	fn top(src: &str) -> Word {
+		return run(&format!("{} {}", F, src)).unwrap().pop().unwrap()
+	}
+
+	fn strs(w: Word) -> Vec<Option<String>> {
+		match w {
+			Word::StrVect(v) 	=> { return v.to_vec() },
+			w => { panic!("not a vec<str>: {:?}", w) }
+		}
+	}
+
+	fn some(v: &[&str]) -> Vec<Option<String>> {
+		return v.iter().map(|s| Some(s.to_string())).collect()
+	}
+
+	#[test]
+	fn levels_and_codes() {
+		match top("f") {
+			Word::Factor(f) 	=> {
+				assert_eq!(f.levels, vec!["a", "b"]);
+				assert_eq!(f.codes, vec![Some(1), Some(0), None, Some(1)]);
+			},
+			w => { panic!("{:?}", w) }
+		}
+		/* Numbers are ordered as numbers, not as text. */
+		assert_eq!(strs(top("[10 2 2] factor levels")), some(&["2", "10"]));
+		assert_eq!(strs(top("f as_str")), vec![Some(format!("b")), Some(format!("a")), None, Some(format!("b"))]);
+	}
+
+	#[test]
+	fn relevel_and_drop() {
+		assert_eq!(strs(top("f \"b\" relevel levels")), some(&["b", "a"]));
+		assert_eq!(strs(top("f \"b\" relevel as_str")), strs(top("f as_str")));
+		assert_eq!(strs(top("f [0 3] subset levels")), some(&["a", "b"]));
+		assert_eq!(strs(top("f [0 3] subset droplevels levels")), some(&["b"]));
+	}
+
+	#[test]
+	fn compare_labels() {
+		match top("f \"b\" eq") {
+			Word::BoolVect(v) 	=> { assert_eq!(v.to_vec(), vec![Some(true), Some(false), None, Some(true)]) },
+			w => { panic!("{:?}", w) }
+		}
+		let kind = |src: &str| run(&format!("{} {}", F, src)).unwrap_err().kind;
+		assert_eq!(kind("f \"b\" lst"), ErrorType::Type);
+		assert_eq!(kind("f \"z\" relevel"), ErrorType::Index);
+		assert_eq!(kind("1 levels"), ErrorType::Type);
+	}
+}
@@ -0,0 +1,366 @@
+/* frame.rs
+ * -------- */
+use crate::code::{FrameOp, sprint_frameop};
+use crate::code::FrameOp::*;
+use crate::error::{ErrorType, StaxError, error};
+use crate::vm::Vm;
+use crate::words::{BoolResult, Word, WordResult, num_word, tighten};
+
+use ndarray::Array1;
+
+/* Rows printed before a frame is cut short. */
+const PRINT_ROWS: usize = 20;
+
+/* A table of named columns of equal length. Columns keep the order they were added in and
+ * can be of any vector type, so one frame can mix numbers, strings and booleans. */
+#[derive(Debug, Clone)]
+pub struct Frame {
+	pub names: Vec<String>,
+	pub columns: Vec<Word>
+}
+
+impl Frame {
+	pub fn new(names: Vec<String>, columns: Vec<Word>) -> Result<Frame, StaxError> {
+		let mut n: Option<usize> = None;
+		for (i, (name, col)) in names.iter().zip(columns.iter()).enumerate() {
+			if names[.. i].contains(name) {
+				return error(ErrorType::Domain, format!("Column \"{}\" appears more than once.", name))
+			}
+			let len = match column_len(col) {
+				Some(len) 	=> { len },
+				None 		=> { return error(ErrorType::Type, format!("Column \"{}\" must be a vector or a list. Given: '{}'.", name, col.sprint_type())) }
+			};
+			match n {
+				Some(m) if m != len => { return error(ErrorType::Index, format!("Column \"{}\" has {} rows but the frame has {}.", name, len, m)) },
+				_ => { n = Some(len) }
+			}
+		}
+		return Ok(Frame { names: names, columns: columns })
+	}
+
+	pub fn nrow(&self) -> usize {
+		return self.columns.first().and_then(column_len).unwrap_or(0)
+	}
+
+	pub fn ncol(&self) -> usize {
+		return self.columns.len()
+	}
+
+	pub fn column(&self, name: &str) -> Option<&Word> {
+		return self.names.iter().position(|n| n == name).map(|i| &self.columns[i])
+	}
+
+	/* A new frame holding the given rows, in the given order. */
+	pub fn rows(&self, idx: &[usize]) -> Frame {
+		return Frame { names: self.names.clone(), columns: self.columns.iter().map(|c| take_rows(c, idx)).collect() }
+	}
+
+	/* Right-aligned columns under their names, with the row index on the left. Long frames
+	 * show their first rows and a count of the rest. */
+	/* A long frame shows its first and last PRINT_ROWS/2 rows around a count of the rest. */
+	pub fn sprint(&self) -> String {
+		let n = self.nrow();
+		let half = PRINT_ROWS / 2;
+		let rows: Vec<usize> = if n > PRINT_ROWS { (0 .. half).chain(n - half .. n).collect() } else { (0 .. n).collect() };
+		let mut table: Vec<Vec<String>> = Vec::with_capacity(self.ncol() + 1);
+		let mut index = vec![String::new()];
+		index.extend(rows.iter().map(|i| i.to_string()));
+		table.push(index);
+		for (name, col) in self.names.iter().zip(self.columns.iter()) {
+			let mut cells = vec![name.clone()];
+			cells.extend(rows.iter().map(|i| cell(col, *i)));
+			table.push(cells);
+		}
+		let widths: Vec<usize> = table.iter().map(|c| c.iter().map(|s| s.chars().count()).max().unwrap_or(0)).collect();
+		let mut lines: Vec<String> = Vec::with_capacity(rows.len() + 2);
+		for r in 0 .. rows.len() + 1 {
+			lines.push(table.iter().zip(widths.iter()).map(|(c, w)| format!("{:>w$}", c[r], w = w)).collect::<Vec<String>>().join("  "));
+		}
+		if rows.len() < n {
+			lines.insert(half + 1, format!("... {} more rows", n - rows.len()));
+		}
+		return lines.join("\n")
+	}
+}
+
+pub fn do_frame(vm: &mut Vm, op: FrameOp) -> BoolResult {
+	let nargs = match op {
+		AddCol => 3,
+		DropCol | Head | Subset | Tail => 2,
+		FrameOp::Frame => if let Some(Word::Dict(_)) = vm.stack.last() { 1 } else { 2 },
+		_ => 1
+	};
+	if vm.stack.len() < nargs {
+		return error(ErrorType::Underflow, format!("'{}' requires {} objects on top of the stack. Only {} found.", sprint_frameop(&op), nargs, vm.stack.len()))
+	}
+	let args = vm.stack.split_off(vm.stack.len() - nargs);
+	let mut args: Vec<Word> = args.into_iter().map(|w| match w {
+		Word::Quote(q) 	=> { Ok(tighten(vm.run_newstack(q.to_vec())?)) },
+		_ => { Ok(w) }
+	}).collect::<Result<Vec<Word>, StaxError>>()?;
+	let result: Word;
+	match op {
+		AddCol 	=> {
+			let col = args.pop().unwrap();
+			let name = name_arg(&args[1], &op)?;
+			let mut f = frame_arg(&args[0], &op)?;
+			match f.names.iter().position(|n| *n == name) {
+				Some(i) => { f.columns[i] = col },
+				None 	=> { f.names.push(name); f.columns.push(col) }
+			}
+			result = Word::Frame(Box::new(Frame::new(f.names, f.columns)?))
+		},
+		DropCol => {
+			let drop: Vec<String> = match &args[1] {
+				Word::Str(s) 		=> { vec![*s.clone()] },
+				Word::StrVect(v) 	=> { names(v)? },
+				w => { return error(ErrorType::Type, format!("'drop_col' requires column names of type 'str' or 'vec<str>'. Given: '{}'.", w.sprint_type())) }
+			};
+			let f = frame_arg(&args[0], &op)?;
+			if let Some(name) = drop.iter().find(|d| !f.names.contains(d)) {
+				return error(ErrorType::Index, format!("Column \"{}\" not found.", name))
+			}
+			let (names, columns) = f.names.into_iter().zip(f.columns.into_iter()).filter(|(n, _)| !drop.contains(n)).unzip();
+			result = Word::Frame(Box::new(Frame { names: names, columns: columns }))
+		},
+		FrameOp::Frame 	=> {
+			let (names, columns): (Vec<String>, Vec<Word>) = match (&args[0], args.get(1)) {
+				/* A dict has no order, so its keys are sorted by name. Names and a list of columns
+				 * give the columns in the order given. */
+				(Word::Dict(d), None) 	=> {
+					let mut keys: Vec<&String> = d.keys().collect();
+					keys.sort();
+					(keys.iter().map(|k| k.to_string()).collect(), keys.iter().map(|k| d[*k].clone()).collect())
+				},
+				(Word::StrVect(n), Some(Word::List(c))) 	=> {
+					if n.len() != c.len() {
+						return error(ErrorType::Index, format!("'frame' requires one name per column. Given: {} and {}.", n.len(), c.len()))
+					}
+					(names(n)?, c.to_vec())
+				},
+				(Word::StrVect(n), Some(c)) if n.len() == 1 && column_len(c).is_some() 	=> { (names(n)?, vec![c.clone()]) },
+				(n, c) => { return error(ErrorType::Type, format!("'frame' requires a dict, or names of type 'vec<str>' and a list of columns. Given: '{}' and '{}'.", n.sprint_type(), c.map(|w| w.sprint_type()).unwrap_or(format!("none")))) }
+			};
+			let columns = columns.into_iter().map(|c| match c {
+				Word::Quote(q) 	=> { Ok(tighten(vm.run_newstack(q.to_vec())?)) },
+				_ => { Ok(c) }
+			}).collect::<Result<Vec<Word>, StaxError>>()?;
+			result = Word::Frame(Box::new(Frame::new(names, columns)?))
+		},
+		Head | Tail 	=> {
+			let len = match column_len(&args[0]) {
+				Some(len) 	=> { len },
+				None 		=> match &args[0] {
+					Word::Frame(f) 	=> { f.nrow() },
+					w => { return type_error(w, &op) }
+				}
+			};
+			let k = match args[1] {
+				Word::Int(i) if i >= 0 	=> { (i as usize).min(len) },
+				_ => { return error(ErrorType::Type, format!("'{}' requires a non-negative row count of type 'int'. Given: '{}'.", sprint_frameop(&op), args[1].sprint())) }
+			};
+			let idx: Vec<usize> = match op { Head => (0 .. k).collect(), _ => (len - k .. len).collect() };
+			result = select(&args[0], &idx)
+		},
+		Ncol 	=> match &args[0] {
+			Word::Frame(f) 	=> { result = Word::Int(f.ncol() as i32) },
+			Word::Mat(m) 	=> { result = Word::Int(m.ncols() as i32) },
+			w => { return type_error(w, &op) }
+		},
+		Nrow 	=> match &args[0] {
+			Word::Frame(f) 	=> { result = Word::Int(f.nrow() as i32) },
+			Word::Mat(m) 	=> { result = Word::Int(m.nrows() as i32) },
+			w => match column_len(w) {
+				Some(len) 	=> { result = Word::Int(len as i32) },
+				None 		=> { return type_error(w, &op) }
+			}
+		},
+		Subset 	=> {
+			let len = match &args[0] {
+				Word::Frame(f) 	=> { f.nrow() },
+				w => match column_len(w) {
+					Some(len) 	=> { len },
+					None 		=> { return type_error(w, &op) }
+				}
+			};
+			let idx: Vec<usize> = match &args[1] {
+				Word::BoolVect(mask) 	=> {
+					if mask.len() != len {
+						return error(ErrorType::Index, format!("Mask has {} elements but the object has {} rows.", mask.len(), len))
+					}
+					mask.iter().enumerate().filter(|(_, b)| **b == Some(true)).map(|(i, _)| i).collect()
+				},
+				/* Row positions, such as those from 'order', in the order given. */
+				Word::Vect(v) 	=> {
+					if let Some(i) = v.iter().find(|i| !(**i >= 0.0 && i.fract() == 0.0 && (**i as usize) < len)) {
+						return error(ErrorType::Index, format!("Row {} is out of bounds for an object with {} rows.", num_word(*i).sprint(), len))
+					}
+					v.iter().map(|i| *i as usize).collect()
+				},
+				w => { return error(ErrorType::Type, format!("'subset' requires a mask of type 'vec<bool>' or positions of type 'vec<num>'. Given: '{}'.", w.sprint_type())) }
+			};
+			result = select(&args[0], &idx)
+		}
+	}
+	return vm.push_const(result)
+}
+
+
+/* 	== HELPERS == */
+
+/* The number of rows of a word that can be used as a column. */
+pub fn column_len(w: &Word) -> Option<usize> {
+	match w {
+		Word::BoolVect(v) 	=> { return Some(v.len()) },
+		Word::Factor(f) 	=> { return Some(f.len()) },
+		Word::List(l) 		=> { return Some(l.len()) },
+		Word::StrVect(v) 	=> { return Some(v.len()) },
+		Word::Vect(v) 		=> { return Some(v.len()) },
+		_ => { return None }
+	}
+}
+
+/* The elements of a column at the given rows. */
+pub fn take_rows(w: &Word, idx: &[usize]) -> Word {
+	match w {
+		Word::BoolVect(v) 	=> { return Word::BoolVect(Box::new(idx.iter().map(|i| v[*i]).collect::<Array1<Option<bool>>>())) },
+		Word::Factor(f) 	=> { return Word::Factor(Box::new(f.take(idx))) },
+		Word::List(l) 		=> { return Word::List(Box::new(idx.iter().map(|i| l[*i].clone()).collect())) },
+		Word::StrVect(v) 	=> { return Word::StrVect(Box::new(idx.iter().map(|i| v[*i].clone()).collect::<Array1<Option<String>>>())) },
+		Word::Vect(v) 		=> { return Word::Vect(Box::new(idx.iter().map(|i| v[*i]).collect::<Array1<f64>>())) },
+		_ => { return w.clone() }
+	}
+}
+
+fn select(w: &Word, idx: &[usize]) -> Word {
+	match w {
+		Word::Frame(f) 	=> { return Word::Frame(Box::new(f.rows(idx))) },
+		_ => { return take_rows(w, idx) }
+	}
+}
+
+/* How a single element of a column is shown in a table or written to a file. Strings are
+ * printed bare. */
+pub(crate) fn cell(w: &Word, i: usize) -> String {
+	match w {
+		Word::BoolVect(v) 	=> { return v[i].map(|b| b.to_string()).unwrap_or(format!("NA")) },
+		Word::Factor(f) 	=> { return f.codes[i].map(|c| f.levels[c].clone()).unwrap_or(format!("NA")) },
+		Word::List(l) 		=> match &l[i] {
+			Word::Str(s) 	=> { return s.to_string() },
+			x => { return x.sprint_short(0) }
+		},
+		Word::StrVect(v) 	=> { return v[i].clone().unwrap_or(format!("NA")) },
+		Word::Vect(v) 		=> { return num_word(v[i]).sprint() },
+		_ => { return String::new() }
+	}
+}
+
+pub fn names(v: &Array1<Option<String>>) -> Result<Vec<String>, StaxError> {
+	match v.iter().cloned().collect::<Option<Vec<String>>>() {
+		Some(n) => { return Ok(n) },
+		None 	=> { return error(ErrorType::Domain, format!("Column names cannot be NA.")) }
+	}
+}
+
+fn frame_arg(w: &Word, op: &FrameOp) -> Result<Frame, StaxError> {
+	match w {
+		Word::Frame(f) 	=> { return Ok(*f.clone()) },
+		_ => { return error(ErrorType::Type, format!("'{}' requires an object of type 'frame'. Given: '{}'.", sprint_frameop(op), w.sprint_type())) }
+	}
+}
+
+fn name_arg(w: &Word, op: &FrameOp) -> Result<String, StaxError> {
+	match w {
+		Word::Str(s) 	=> { return Ok(*s.clone()) },
+		_ => { return error(ErrorType::Type, format!("'{}' requires a column name of type 'str'. Given: '{}'.", sprint_frameop(op), w.sprint_type())) }
+	}
+}
+
+fn type_error(w: &Word, op: &FrameOp) -> BoolResult {
+	return error(ErrorType::Type, format!("'{}' cannot be completed on objects of type '{}'.", sprint_frameop(op), w.sprint_type()))
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compare::equal_words;
+	use crate::vm::tests::run;
+	use std::collections::HashMap;
+
+	const F: &str = "f = [\"a\" \"b\"] [[1 2 3] [\"x\" \"y\" \"z\"]] frame;";
+
+	fn frame(src: &str) -> Frame {
+		match run(&format!("{} {}", F, src)).unwrap().pop() {
+			Some(Word::Frame(f)) 	=> { return *f },
+			w => { panic!("{}: {:?}", src, w) }
+		}
+	}
+
+	fn nums(v: &[f64]) -> Word {
+		return Word::Vect(Box::new(Array1::from_vec(v.to_vec())))
+	}
+
+	fn kind(src: &str) -> ErrorType {
+		return run(&format!("{} {}", F, src)).unwrap_err().kind
+	}
+
+	#[test]
+	fn construct() {
+		let f = frame("f");
+		assert_eq!(f.names, vec!["a", "b"]);
+		assert!(matches!(run(&format!("{} f nrow f ncol", F)).unwrap()[..], [Word::Int(3), Word::Int(2)]));
+		assert_eq!(kind("[\"a\" \"b\"] [[1 2] [1 2 3]] frame"), ErrorType::Index);
+		assert_eq!(kind("[\"a\" \"a\"] [[1 2] [1 2]] frame"), ErrorType::Domain);
+		assert_eq!(kind("[\"a\"] [[1 2] [1 2]] frame"), ErrorType::Index);
+	}
+
+	#[test]
+	fn from_dict() {
+		let mut d = HashMap::new();
+		d.insert(format!("z"), nums(&[1.0]));
+		d.insert(format!("m"), nums(&[2.0]));
+		let mut vm = crate::vm::tests::new_vm();
+		vm.stack.push(Word::Dict(Box::new(d)));
+		do_frame(&mut vm, FrameOp::Frame).unwrap();
+		match vm.stack.pop() {
+			Some(Word::Frame(f)) 	=> { assert_eq!(f.names, vec!["m", "z"]) },
+			_ => { panic!("not a frame") }
+		}
+	}
+
+	#[test]
+	fn rows() {
+		assert!(equal_words(&frame("f 2 head").columns[0], &nums(&[1.0, 2.0])));
+		assert!(equal_words(&frame("f 2 tail").columns[0], &nums(&[2.0, 3.0])));
+		assert!(equal_words(&frame("f 9 head").columns[0], &nums(&[1.0, 2.0, 3.0])));
+		assert!(equal_words(&frame("f [true false true] subset").columns[0], &nums(&[1.0, 3.0])));
+		assert!(equal_words(&frame("f [2 0] subset").columns[0], &nums(&[3.0, 1.0])));
+		assert_eq!(kind("f [3] subset"), ErrorType::Index);
+		assert_eq!(kind("f [true] subset"), ErrorType::Index);
+		assert_eq!(kind("f -1 head"), ErrorType::Type);
+	}
+
+	#[test]
+	fn columns() {
+		let f = frame("f \"c\" [4 5 6] add_col");
+		assert_eq!(f.names, vec!["a", "b", "c"]);
+		assert!(equal_words(&frame("f \"a\" [0 0 0] add_col").columns[0], &nums(&[0.0, 0.0, 0.0])));
+		assert_eq!(frame("f \"a\" drop_col").names, vec!["b"]);
+		assert_eq!(kind("f \"c\" [4 5] add_col"), ErrorType::Index);
+		assert_eq!(kind("f \"c\" drop_col"), ErrorType::Index);
+	}
+
+	#[test]
+	fn print_long() {
+		let n = PRINT_ROWS + 5;
+		let f = Frame::new(vec![format!("x")], vec![nums(&(0 .. n).map(|i| i as f64).collect::<Vec<f64>>())]).unwrap();
+		let lines: Vec<String> = f.sprint().lines().map(|l| l.split_whitespace().collect::<Vec<&str>>().join(" ")).collect();
+		let half = PRINT_ROWS / 2;
+		assert_eq!(lines.len(), PRINT_ROWS + 2);
+		assert_eq!(lines[half], format!("{} {}", half - 1, half - 1));
+		assert_eq!(lines[half + 1], format!("... {} more rows", n - PRINT_ROWS));
+		assert_eq!(lines[half + 2], format!("{} {}", n - half, n - half));
+		assert_eq!(lines[PRINT_ROWS + 1], format!("{} {}", n - 1, n - 1));
+	}
+}
@@ -2,6 +2,7 @@
  * -------- */
 use crate::code::{GenOp};
 use crate::code::GenOp::*;
+use crate::error::{ErrorType, error};
 use crate::vm::Vm;
 use crate::words::{BoolResult};
 
@@ -12,6 +13,11 @@ pub fn do_general(vm: &mut Vm, op: GenOp) -> BoolResult {
 			vm.line += u as u16;
 			return Ok(false)
 		},
+		Break 		=> {
+			if vm.loops == 0 { return error(ErrorType::Syntax, format!("'break' can only be used inside 'loop', 'times', 'while' or 'each'.")) }
+			vm.brk = true;
+			return Ok(true)
+		},
 		_ => { return Ok(false) }
 	}
 }
@@ -0,0 +1,675 @@
+/* htest.rs
+ * -------- */
+use crate::code::{Dist, Opcode, StatOp, sprint_statop};
+use crate::code::StatOp::*;
+use crate::dist::{gamma_upper, norm_cdf, norm_quantile, quantile, t_cdf};
+use crate::error::{ErrorType, StaxError, error};
+use crate::math::{mean_vec, var_vec};
+use crate::vm::Vm;
+use crate::words::{Word, WordResult, list2array1, toF64};
+
+use ndarray::{Array1, Array2};
+
+use std::collections::HashMap;
+
+const CONF_LEVEL: f64 = 0.95;
+
+/* Classical tests, two-sided at the 95% level. Each one takes its data from the stack and
+ * returns a dict with 'statistic', 'df', 'p', 'conf_int', 'estimate' and 'method'; fields that
+ * do not apply to a test are 'none'. */
+pub fn do_htest(vm: &mut Vm, op: &StatOp) -> WordResult {
+	let top = pop(vm, op)?;
+	match op {
+		ChisqTest 	=> match top {
+			Word::Mat(m) 	=> { return chisq_table(&m) },
+			_ => { return chisq_gof(&sample(vm, &top, op)?) }
+		},
+		CorTest 	=> {
+			let y = sample_raw(vm, &top, op)?;
+			let x = take_raw(vm, op)?;
+			return cor_test(&x, &y)
+		},
+		KsTest 		=> {
+			let x_word = pop(vm, op)?;
+			let x = sample(vm, &x_word, op)?;
+			match top {
+				Word::Quote(ref q) if !is_data(vm, q) 	=> {
+					let mut cdf = Vec::with_capacity(x.len());
+					for v in sorted(&x).iter() {
+						let out = vm.run_withstack(vec![Word::Num(*v)], Word::Quote(q.clone()))?;
+						cdf.push(out.last().map(toF64).unwrap_or(f64::NAN));
+					}
+					return ks_one(&sorted(&x), &cdf)
+				},
+				_ => { return ks_two(&x, &sample(vm, &top, op)?) }
+			}
+		},
+		PropTest 	=> {
+			let n = sample_raw(vm, &top, op)?;
+			let x = take_raw(vm, op)?;
+			return prop_test(&x, &n)
+		},
+		Ttest 		=> match top {
+			Word::Int(_) | Word::Num(_) 	=> {
+				let x = take(vm, op)?;
+				return t_one(&x, toF64(&top), "One Sample t-test")
+			},
+			Word::Bool(paired) 	=> {
+				let y = take_raw(vm, op)?;
+				let x = take_raw(vm, op)?;
+				if !paired { return t_welch(&drop_nan(&x), &drop_nan(&y)) }
+				return t_one(&differences(&x, &y, op)?, 0.0, "Paired t-test")
+			},
+			_ => {
+				let y = sample(vm, &top, op)?;
+				let x = take(vm, op)?;
+				return t_welch(&x, &y)
+			}
+		},
+		WilcoxTest 	=> match top {
+			Word::Int(_) | Word::Num(_) 	=> {
+				let x = take(vm, op)?;
+				return signed_rank(&x.mapv(|v| v - toF64(&top)))
+			},
+			Word::Bool(paired) 	=> {
+				let y = take_raw(vm, op)?;
+				let x = take_raw(vm, op)?;
+				if !paired { return rank_sum(&drop_nan(&x), &drop_nan(&y)) }
+				return signed_rank(&differences(&x, &y, op)?)
+			},
+			_ => {
+				let y = sample(vm, &top, op)?;
+				let x = take(vm, op)?;
+				return rank_sum(&x, &y)
+			}
+		},
+		_ => { return error(ErrorType::Type, format!("Statistical test '{}' not recognized!", sprint_statop(op))) }
+	}
+}
+
+
+/* 	== T TESTS == */
+
+fn t_one(x: &Array1<f64>, mu: f64, method: &str) -> WordResult {
+	let n = x.len() as f64;
+	if n < 2.0 { return too_few(&Ttest, 2) }
+	let mean = mean_vec(x);
+	let se = (var_vec(x) / n).sqrt();
+	let df = n - 1.0;
+	let t = (mean - mu) / se;
+	let q = quantile(&Dist::T, 0.5 + CONF_LEVEL / 2.0, &[df]);
+	return Ok(result(t, Some(df), 2.0 * t_cdf(-t.abs(), df), Some((mean - q * se, mean + q * se)), Word::Num(mean), method))
+}
+
+fn t_welch(x: &Array1<f64>, y: &Array1<f64>) -> WordResult {
+	let (nx, ny) = (x.len() as f64, y.len() as f64);
+	if nx < 2.0 || ny < 2.0 { return too_few(&Ttest, 2) }
+	let (mx, my) = (mean_vec(x), mean_vec(y));
+	let (vx, vy) = (var_vec(x) / nx, var_vec(y) / ny);
+	let se = (vx + vy).sqrt();
+	let df = (vx + vy).powi(2) / (vx.powi(2) / (nx - 1.0) + vy.powi(2) / (ny - 1.0));
+	let t = (mx - my) / se;
+	let q = quantile(&Dist::T, 0.5 + CONF_LEVEL / 2.0, &[df]);
+	let diff = mx - my;
+	return Ok(result(t, Some(df), 2.0 * t_cdf(-t.abs(), df), Some((diff - q * se, diff + q * se)), vect(vec![mx, my]), "Welch Two Sample t-test"))
+}
+
+
+/* 	== CHI-SQUARED TESTS == */
+
+/* Goodness of fit of counts to equal probabilities. */
+fn chisq_gof(x: &Array1<f64>) -> WordResult {
+	let k = x.len() as f64;
+	if k < 2.0 { return too_few(&ChisqTest, 2) }
+	let e = x.sum() / k;
+	let stat = x.iter().map(|o| (o - e).powi(2) / e).sum::<f64>();
+	let df = k - 1.0;
+	return Ok(result(stat, Some(df), gamma_upper(df / 2.0, stat / 2.0), None, Word::Null, "Chi-squared test for given probabilities"))
+}
+
+/* Independence in a contingency table, with Yates' correction for 2x2 tables as in R. */
+fn chisq_table(m: &Array2<f64>) -> WordResult {
+	let (r, c) = (m.nrows(), m.ncols());
+	if r < 2 || c < 2 {
+		return error(ErrorType::Domain, format!("'chisq_test' requires a table with at least 2 rows and 2 columns. Given: {}x{}.", r, c))
+	}
+	let total = m.sum();
+	let rows = m.sum_axis(ndarray::Axis(1));
+	let cols = m.sum_axis(ndarray::Axis(0));
+	let yates = r == 2 && c == 2;
+	let mut stat = 0.0;
+	for i in 0 .. r {
+		for j in 0 .. c {
+			let e = rows[i] * cols[j] / total;
+			let d = (m[[i, j]] - e).abs();
+			let d = if yates { d - d.min(0.5) } else { d };
+			stat += d * d / e;
+		}
+	}
+	let df = ((r - 1) * (c - 1)) as f64;
+	let method = if yates { "Pearson's Chi-squared test with Yates' continuity correction" } else { "Pearson's Chi-squared test" };
+	return Ok(result(stat, Some(df), gamma_upper(df / 2.0, stat / 2.0), None, Word::Null, method))
+}
+
+
+/* 	== CORRELATION == */
+
+/* Pearson's correlation over pairwise-complete observations, with a Fisher z interval. */
+fn cor_test(x: &Array1<f64>, y: &Array1<f64>) -> WordResult {
+	if x.len() != y.len() { return length_error(x.len(), y.len(), &CorTest) }
+	let pairs: Vec<(f64, f64)> = x.iter().zip(y.iter()).filter(|(a, b)| !a.is_nan() && !b.is_nan()).map(|(a, b)| (*a, *b)).collect();
+	let n = pairs.len() as f64;
+	if n < 3.0 { return too_few(&CorTest, 3) }
+	let mx = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+	let my = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+	let sxy = pairs.iter().map(|p| (p.0 - mx) * (p.1 - my)).sum::<f64>();
+	let sxx = pairs.iter().map(|p| (p.0 - mx).powi(2)).sum::<f64>();
+	let syy = pairs.iter().map(|p| (p.1 - my).powi(2)).sum::<f64>();
+	let r = sxy / (sxx * syy).sqrt();
+	let df = n - 2.0;
+	let t = r * (df / (1.0 - r * r)).sqrt();
+	let ci = if n > 3.0 {
+		let z = r.atanh();
+		let w = norm_quantile(0.5 + CONF_LEVEL / 2.0) / (n - 3.0).sqrt();
+		Some(((z - w).tanh(), (z + w).tanh()))
+	} else { None };
+	return Ok(result(t, Some(df), 2.0 * t_cdf(-t.abs(), df), ci, Word::Num(r), "Pearson's product-moment correlation"))
+}
+
+
+/* 	== PROPORTIONS == */
+
+/* Successes 'x' out of 'n' trials: against 0.5 for one group, or equality across groups. */
+fn prop_test(x: &Array1<f64>, n: &Array1<f64>) -> WordResult {
+	if x.len() != n.len() { return length_error(x.len(), n.len(), &PropTest) }
+	let k = x.len();
+	if k == 0 { return too_few(&PropTest, 1) }
+	if x.iter().zip(n.iter()).any(|(s, t)| !(*s >= 0.0 && s <= t)) {
+		return error(ErrorType::Domain, format!("'prop_test' requires counts between 0 and the number of trials."))
+	}
+	let est = x / n;
+	let z = norm_quantile(0.5 + CONF_LEVEL / 2.0);
+	let mut yates: f64 = if k <= 2 { 0.5 } else { 0.0 };
+	let mut ci = None;
+	let p0: Array1<f64>;
+	if k == 1 {
+		let (x, n, p) = (x[0], n[0], est[0]);
+		yates = yates.min((x - n * 0.5).abs());
+		let z22n = z * z / (2.0 * n);
+		let pc = p + yates / n;
+		let hi = if pc >= 1.0 { 1.0 } else { (pc + z22n + z * (pc * (1.0 - pc) / n + z22n / (2.0 * n)).sqrt()) / (1.0 + 2.0 * z22n) };
+		let pc = p - yates / n;
+		let lo = if pc <= 0.0 { 0.0 } else { (pc + z22n - z * (pc * (1.0 - pc) / n + z22n / (2.0 * n)).sqrt()) / (1.0 + 2.0 * z22n) };
+		ci = Some((lo, hi));
+		p0 = Array1::from_elem(1, 0.5);
+	} else {
+		if k == 2 {
+			let delta = est[0] - est[1];
+			let inv = n.mapv(|v| 1.0 / v).sum();
+			yates = yates.min(delta.abs() / inv);
+			let width = z * (&est * &est.mapv(|p| 1.0 - p) / n).sum().sqrt() + yates * inv;
+			ci = Some(((delta - width).max(-1.0), (delta + width).min(1.0)));
+		}
+		p0 = Array1::from_elem(k, x.sum() / n.sum());
+	}
+	let mut stat = 0.0;
+	for i in 0 .. k {
+		for (o, e) in [(x[i], n[i] * p0[i]), (n[i] - x[i], n[i] * (1.0 - p0[i]))].iter() {
+			stat += ((o - e).abs() - yates).powi(2) / e;
+		}
+	}
+	let df = if k == 1 { 1.0 } else { (k - 1) as f64 };
+	let correction = if yates > 0.0 { "with" } else { "without" };
+	let method = match k {
+		1 => format!("1-sample proportions test {} continuity correction", correction),
+		_ => format!("{}-sample test for equality of proportions {} continuity correction", k, correction)
+	};
+	let estimate = if k == 1 { Word::Num(est[0]) } else { Word::Vect(Box::new(est)) };
+	return Ok(result(stat, Some(df), gamma_upper(df / 2.0, stat / 2.0), ci, estimate, &method))
+}
+
+
+/* 	== RANK TESTS == */
+
+/* Wilcoxon signed rank test on differences from the null location. Zeros are dropped; the null
+ * distribution is exact below 50 observations without ties, as in R. */
+fn signed_rank(d: &Array1<f64>) -> WordResult {
+	let d: Vec<f64> = d.iter().cloned().filter(|v| *v != 0.0).collect();
+	let n = d.len();
+	if n == 0 { return too_few(&WilcoxTest, 1) }
+	let (ranks, ties) = rank_avg(&d.iter().map(|v| v.abs()).collect::<Vec<f64>>());
+	let v = ranks.iter().zip(d.iter()).filter(|(_, x)| **x > 0.0).fold(0.0, |acc, (r, _)| acc + r);
+	let (p, method);
+	if n < 50 && ties.is_empty() {
+		/* Distribution of the sum of a random subset of 1..n. */
+		let max = n * (n + 1) / 2;
+		let mut dens = vec![0.0; max + 1];
+		dens[0] = 1.0;
+		for i in 1 ..= n {
+			for s in (i ..= max).rev() { dens[s] = (dens[s] + dens[s - i]) / 2.0 }
+			for s in 0 .. i { dens[s] /= 2.0 }
+		}
+		p = exact_p(&dens, v as usize);
+		method = "Wilcoxon signed rank exact test";
+	} else {
+		let n = n as f64;
+		let z = v - n * (n + 1.0) / 4.0;
+		let tie_adj = ties.iter().map(|t| (t.powi(3) - t) / 48.0).sum::<f64>();
+		let sigma = (n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - tie_adj).sqrt();
+		p = normal_p(z, sigma);
+		method = "Wilcoxon signed rank test with continuity correction";
+	}
+	return Ok(result(v, None, p, None, Word::Null, method))
+}
+
+/* Wilcoxon rank sum (Mann-Whitney) test, with the same exact and approximate cases. */
+fn rank_sum(x: &Array1<f64>, y: &Array1<f64>) -> WordResult {
+	let (nx, ny) = (x.len(), y.len());
+	if nx == 0 || ny == 0 { return too_few(&WilcoxTest, 1) }
+	let pooled: Vec<f64> = x.iter().chain(y.iter()).cloned().collect();
+	let (ranks, ties) = rank_avg(&pooled);
+	let w = ranks[.. nx].iter().sum::<f64>() - (nx * (nx + 1)) as f64 / 2.0;
+	let (p, method);
+	if nx < 50 && ny < 50 && ties.is_empty() {
+		/* Counts of subsets of size nx from 1..nx+ny by their Mann-Whitney statistic. */
+		let max = nx * ny;
+		let mut count = vec![vec![0.0; max + 1]; nx + 1];
+		count[0][0] = 1.0;
+		for item in 0 .. nx + ny {
+			for k in (1 ..= nx.min(item + 1)).rev() {
+				let shift = item + 1 - k;
+				if shift > ny { continue }
+				for u in (shift ..= max).rev() { count[k][u] += count[k - 1][u - shift] }
+			}
+		}
+		let total: f64 = count[nx].iter().sum();
+		let dens: Vec<f64> = count[nx].iter().map(|c| c / total).collect();
+		p = exact_p(&dens, w as usize);
+		method = "Wilcoxon rank sum exact test";
+	} else {
+		let (m, n) = (nx as f64, ny as f64);
+		let z = w - m * n / 2.0;
+		let tie_adj = ties.iter().map(|t| t.powi(3) - t).sum::<f64>() / ((m + n) * (m + n - 1.0));
+		let sigma = (m * n / 12.0 * ((m + n + 1.0) - tie_adj)).sqrt();
+		p = normal_p(z, sigma);
+		method = "Wilcoxon rank sum test with continuity correction";
+	}
+	return Ok(result(w, None, p, None, Word::Null, method))
+}
+
+/* Two-sided p-value from a discrete null density at 'k'. */
+fn exact_p(dens: &[f64], k: usize) -> f64 {
+	let lower: f64 = dens[..= k].iter().sum();
+	let upper: f64 = dens[k ..].iter().sum();
+	return (2.0 * lower.min(upper)).min(1.0)
+}
+
+/* Two-sided normal p-value for a centred statistic, with continuity correction. */
+fn normal_p(z: f64, sigma: f64) -> f64 {
+	let z = (z - 0.5 * z.signum()) / sigma;
+	return 2.0 * norm_cdf(-z.abs())
+}
+
+/* Average ranks starting at 1, and the sizes of tied groups. */
+pub fn rank_avg(v: &[f64]) -> (Vec<f64>, Vec<f64>) {
+	let mut order: Vec<usize> = (0 .. v.len()).collect();
+	order.sort_by(|a, b| v[*a].partial_cmp(&v[*b]).unwrap_or(std::cmp::Ordering::Equal));
+	let mut ranks = vec![0.0; v.len()];
+	let mut ties = Vec::new();
+	let mut i = 0;
+	while i < order.len() {
+		let mut j = i;
+		while j + 1 < order.len() && v[order[j + 1]] == v[order[i]] { j += 1 }
+		let r = (i + j) as f64 / 2.0 + 1.0;
+		for k in i ..= j { ranks[order[k]] = r }
+		if j > i { ties.push((j - i + 1) as f64) }
+		i = j + 1;
+	}
+	return (ranks, ties)
+}
+
+
+/* 	== KOLMOGOROV-SMIRNOV == */
+
+/* One-sample test of sorted data against its distribution function values. */
+fn ks_one(x: &Array1<f64>, cdf: &[f64]) -> WordResult {
+	let n = x.len();
+	if n == 0 { return too_few(&KsTest, 1) }
+	let nf = n as f64;
+	let d = cdf.iter().enumerate().map(|(i, f)| ((i + 1) as f64 / nf - f).max(f - i as f64 / nf)).fold(0.0, f64::max);
+	let ties = x.windows(2).into_iter().any(|w| w[0] == w[1]);
+	let (p, method);
+	if n < 100 && !ties {
+		p = 1.0 - kolmogorov_exact(d, n);
+		method = "Exact one-sample Kolmogorov-Smirnov test";
+	} else {
+		p = 1.0 - kolmogorov_limit(nf.sqrt() * d);
+		method = "Asymptotic one-sample Kolmogorov-Smirnov test";
+	}
+	return Ok(result(d, None, p.max(0.0).min(1.0), None, Word::Null, method))
+}
+
+fn ks_two(x: &Array1<f64>, y: &Array1<f64>) -> WordResult {
+	let (m, n) = (x.len(), y.len());
+	if m == 0 || n == 0 { return too_few(&KsTest, 1) }
+	let (xs, ys) = (sorted(x), sorted(y));
+	let mut pooled: Vec<f64> = xs.iter().chain(ys.iter()).cloned().collect();
+	pooled.sort_by(|a, b| a.partial_cmp(b).unwrap());
+	let ties = pooled.windows(2).any(|w| w[0] == w[1]);
+	let ecdf = |s: &Array1<f64>, v: f64| s.iter().filter(|e| **e <= v).count() as f64 / s.len() as f64;
+	let d = pooled.iter().map(|v| (ecdf(&xs, *v) - ecdf(&ys, *v)).abs()).fold(0.0, f64::max);
+	let (p, method);
+	if m * n < 10000 && !ties {
+		p = 1.0 - smirnov_exact(d, m, n);
+		method = "Exact two-sample Kolmogorov-Smirnov test";
+	} else {
+		let (mf, nf) = (m as f64, n as f64);
+		p = 1.0 - kolmogorov_limit((mf * nf / (mf + nf)).sqrt() * d);
+		method = "Asymptotic two-sample Kolmogorov-Smirnov test";
+	}
+	return Ok(result(d, None, p.max(0.0).min(1.0), None, Word::Null, method))
+}
+
+/* Limiting distribution P(K <= z) of the scaled statistic. */
+fn kolmogorov_limit(z: f64) -> f64 {
+	if z <= 0.0 { return 0.0 }
+	if z < 1.0 {
+		let w = -std::f64::consts::PI.powi(2) / (8.0 * z * z);
+		let s: f64 = (1 .. 20).map(|k| (w * ((2 * k - 1) as f64).powi(2)).exp()).sum();
+		return (2.0 * std::f64::consts::PI).sqrt() / z * s
+	}
+	let s: f64 = (1 .. 100).map(|k| (if k % 2 == 1 { 1.0 } else { -1.0 }) * (-2.0 * (k * k) as f64 * z * z).exp()).sum();
+	return 1.0 - 2.0 * s
+}
+
+/* Exact P(D < d) for the one-sample statistic (Marsaglia, Tsang and Wang). */
+fn kolmogorov_exact(d: f64, n: usize) -> f64 {
+	let nf = n as f64;
+	let k = (nf * d) as usize + 1;
+	let m = 2 * k - 1;
+	let h = k as f64 - nf * d;
+	let mut hm = Array2::<f64>::zeros((m, m));
+	for i in 0 .. m {
+		for j in 0 .. m {
+			if i + 1 >= j { hm[[i, j]] = 1.0 }
+		}
+	}
+	for i in 0 .. m {
+		hm[[i, 0]] -= h.powi(i as i32 + 1);
+		hm[[m - 1, i]] -= h.powi((m - i) as i32);
+	}
+	hm[[m - 1, 0]] += if 2.0 * h - 1.0 > 0.0 { (2.0 * h - 1.0).powi(m as i32) } else { 0.0 };
+	for i in 0 .. m {
+		for j in 0 .. m {
+			if i + 1 > j {
+				for g in 1 ..= i + 1 - j { hm[[i, j]] /= g as f64 }
+			}
+		}
+	}
+	let (q, mut e) = matrix_power(&hm, n, k - 1);
+	let mut s = q[[k - 1, k - 1]];
+	for i in 1 ..= n {
+		s = s * i as f64 / nf;
+		if s < 1e-140 {
+			s *= 1e140;
+			e -= 140;
+		}
+	}
+	return s * 10f64.powi(e)
+}
+
+/* 'a' to the power 'n', rescaled by powers of ten to stay in range. Returns the matrix and the
+ * exponent of the scale. */
+fn matrix_power(a: &Array2<f64>, n: usize, centre: usize) -> (Array2<f64>, i32) {
+	if n == 1 { return (a.clone(), 0) }
+	let (h, eh) = matrix_power(a, n / 2, centre);
+	let mut v = h.dot(&h);
+	let mut e = 2 * eh;
+	if n % 2 == 1 { v = a.dot(&v) }
+	if v[[centre, centre]] > 1e140 {
+		v.mapv_inplace(|x| x * 1e-140);
+		e += 140;
+	}
+	return (v, e)
+}
+
+/* Exact P(D < d) for the two-sample statistic, by counting lattice paths. */
+fn smirnov_exact(d: f64, m: usize, n: usize) -> f64 {
+	let (m, n) = if m > n { (n, m) } else { (m, n) };
+	let (md, nd) = (m as f64, n as f64);
+	let q = (0.5 + (d * md * nd - 1e-7).floor()) / (md * nd);
+	let mut u: Vec<f64> = (0 ..= n).map(|j| if j as f64 / nd > q { 0.0 } else { 1.0 }).collect();
+	for i in 1 ..= m {
+		let w = i as f64 / (i + n) as f64;
+		u[0] = if i as f64 / md > q { 0.0 } else { w * u[0] };
+		for j in 1 ..= n {
+			u[j] = if (i as f64 / md - j as f64 / nd).abs() > q { 0.0 } else { w * u[j] + u[j - 1] };
+		}
+	}
+	return u[n]
+}
+
+
+/* 	== HELPERS == */
+
+fn result(stat: f64, df: Option<f64>, p: f64, ci: Option<(f64, f64)>, estimate: Word, method: &str) -> Word {
+	let mut dict: HashMap<String, Word> = HashMap::new();
+	dict.insert("statistic".to_string(), Word::Num(stat));
+	dict.insert("df".to_string(), match df { Some(v) => Word::Num(v), None => Word::Null });
+	dict.insert("p".to_string(), Word::Num(p));
+	dict.insert("conf_int".to_string(), match ci { Some((lo, hi)) => vect(vec![lo, hi]), None => Word::Null });
+	dict.insert("estimate".to_string(), estimate);
+	dict.insert("method".to_string(), Word::Str(Box::new(method.to_string())));
+	return Word::Dict(Box::new(dict))
+}
+
+fn vect(v: Vec<f64>) -> Word {
+	return Word::Vect(Box::new(Array1::from_vec(v)))
+}
+
+fn pop(vm: &mut Vm, op: &StatOp) -> WordResult {
+	match vm.stack.pop() {
+		Some(w) => { return Ok(w) },
+		None 	=> { return error(ErrorType::Underflow, format!("'{}' requires more objects on top of the stack.", sprint_statop(op))) }
+	}
+}
+
+/* Numbers from a vector, list or quote, keeping NaN so that paired data stays aligned. */
+fn sample_raw(vm: &mut Vm, w: &Word, op: &StatOp) -> Result<Array1<f64>, StaxError> {
+	match w {
+		Word::Int(_) | Word::Num(_) 	=> { return Ok(Array1::from_elem(1, toF64(w))) },
+		Word::List(l) 	=> { return Ok(list2array1(l)) },
+		Word::Quote(q) 	=> { return Ok(list2array1(&vm.run_newstack(q.to_vec())?)) },
+		Word::Vect(v) 	=> { return Ok(*v.clone()) },
+		_ => { return error(ErrorType::Type, format!("'{}' cannot be completed on objects of type '{}'.", sprint_statop(op), w.sprint_type())) }
+	}
+}
+
+fn take_raw(vm: &mut Vm, op: &StatOp) -> Result<Array1<f64>, StaxError> {
+	let w = pop(vm, op)?;
+	return sample_raw(vm, &w, op)
+}
+
+fn take(vm: &mut Vm, op: &StatOp) -> Result<Array1<f64>, StaxError> {
+	return Ok(drop_nan(&take_raw(vm, op)?))
+}
+
+/* Numbers from a vector, list or quote, with NaN dropped as missing. */
+fn sample(vm: &mut Vm, w: &Word, op: &StatOp) -> Result<Array1<f64>, StaxError> {
+	return Ok(drop_nan(&sample_raw(vm, w, op)?))
+}
+
+/* Quotes are also vector literals, so a quote is taken as a distribution function only when it
+ * does not evaluate to numbers on its own. */
+fn is_data(vm: &mut Vm, q: &Vec<Opcode>) -> bool {
+	match vm.run_newstack(q.to_vec()) {
+		Ok(items) 	=> { return items.len() > 0 && items.iter().all(|w| match w { Word::Int(_) | Word::Num(_) => true, _ => false }) },
+		Err(_) 		=> { return false }
+	}
+}
+
+fn drop_nan(v: &Array1<f64>) -> Array1<f64> {
+	return v.iter().cloned().filter(|x| !x.is_nan()).collect()
+}
+
+fn sorted(v: &Array1<f64>) -> Array1<f64> {
+	let mut s = v.to_vec();
+	s.sort_by(|a, b| a.partial_cmp(b).unwrap());
+	return Array1::from_vec(s)
+}
+
+/* Differences of complete pairs. */
+fn differences(x: &Array1<f64>, y: &Array1<f64>, op: &StatOp) -> Result<Array1<f64>, StaxError> {
+	if x.len() != y.len() { return length_error(x.len(), y.len(), op).map(|_| Array1::zeros(0)) }
+	return Ok(drop_nan(&(x - y)))
+}
+
+fn length_error(l: usize, r: usize, op: &StatOp) -> WordResult {
+	return error(ErrorType::Index, format!("'{}' requires samples of the same length. Given: {} and {}.", sprint_statop(op), l, r))
+}
+
+fn too_few(op: &StatOp, n: usize) -> WordResult {
+	return error(ErrorType::Domain, format!("'{}' requires at least {} observations.", sprint_statop(op), n))
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn get(w: &Word, key: &str) -> f64 {
+		match w {
+			Word::Dict(d) => match d.get(key) {
+				Some(Word::Num(f)) 	=> { return *f },
+				_ => { panic!("no number at '{}'", key) }
+			},
+			_ => { panic!("not a dict") }
+		}
+	}
+
+	fn arr(v: &[f64]) -> Array1<f64> {
+		return Array1::from_vec(v.to_vec())
+	}
+
+	#[test]
+	fn welch_sleep() {
+		/* R: t.test(extra ~ group, data = sleep) gives t = -1.8608, df = 17.776, p = 0.07939. */
+		let x = arr(&[0.7, -1.6, -0.2, -1.2, -0.1, 3.4, 3.7, 0.8, 0.0, 2.0]);
+		let y = arr(&[1.9, 0.8, 1.1, 0.1, -0.1, 4.4, 5.5, 1.6, 4.6, 3.4]);
+		let r = t_welch(&x, &y).unwrap();
+		assert!((get(&r, "statistic") + 1.8608).abs() < 1e-4);
+		assert!((get(&r, "df") - 17.776).abs() < 1e-3);
+		assert!((get(&r, "p") - 0.07939).abs() < 1e-5);
+	}
+
+	#[test]
+	fn wilcoxon_exact() {
+		/* Every one of the 20 splits of 1..6 is equally likely, and only one has W = 0. */
+		let r = rank_sum(&arr(&[1.0, 2.0, 3.0]), &arr(&[4.0, 5.0, 6.0])).unwrap();
+		assert_eq!(get(&r, "statistic"), 0.0);
+		assert!((get(&r, "p") - 0.1).abs() < 1e-12);
+		/* Of the 32 sign patterns, V = 15 arises once and V >= 14 twice. */
+		let r = signed_rank(&arr(&[1.0, 2.0, 3.0, 4.0, 5.0])).unwrap();
+		assert_eq!(get(&r, "statistic"), 15.0);
+		assert!((get(&r, "p") - 0.0625).abs() < 1e-12);
+		let r = signed_rank(&arr(&[-1.0, 2.0, 3.0, 4.0, 5.0])).unwrap();
+		assert_eq!(get(&r, "statistic"), 14.0);
+		assert!((get(&r, "p") - 0.125).abs() < 1e-12);
+	}
+
+	fn test(src: &str) -> Word {
+		return crate::vm::tests::run(src).unwrap().pop().unwrap()
+	}
+
+	fn kind(src: &str) -> ErrorType {
+		return crate::vm::tests::run(src).unwrap_err().kind
+	}
+
+	#[test]
+	fn t_words() {
+		/* R: t.test(1:5) gives t = 4.2426, df = 4, p = 0.01324 and the interval 1.0368 to 4.9632. */
+		let r = test("[1 2 3 4 5] 0 ttest");
+		assert!((get(&r, "statistic") - 18f64.sqrt()).abs() < 1e-12);
+		assert_eq!(get(&r, "df"), 4.0);
+		assert!((get(&r, "p") - 0.01324).abs() < 1e-5);
+		match &r {
+			Word::Dict(d) => match d.get("conf_int") {
+				Some(Word::Vect(v)) 	=> {
+					assert!((v[0] - 1.036757).abs() < 1e-6);
+					assert!((v[1] - 4.963243).abs() < 1e-6);
+				},
+				_ => { panic!("no interval") }
+			},
+			_ => { panic!("not a dict") }
+		}
+		/* Pairing with zeros is the one-sample test on the first sample. */
+		let p = test("[1 2 3 4 5] [0 0 0 0 0] true ttest");
+		assert!((get(&p, "statistic") - get(&r, "statistic")).abs() < 1e-12);
+		let w = test("[1 2 3 4 5] [2 4 6 8 10] false ttest");
+		let d = test("[1 2 3 4 5] [2 4 6 8 10] ttest");
+		assert_eq!(get(&w, "statistic"), get(&d, "statistic"));
+		assert_eq!(get(&w, "df"), get(&d, "df"));
+		assert_eq!(kind("[1] 0 ttest"), ErrorType::Domain);
+		assert_eq!(kind("[1 2 3] [1 2] true ttest"), ErrorType::Index);
+		assert_eq!(kind("\"a\" 0 ttest"), ErrorType::Type);
+	}
+
+	#[test]
+	fn chisq_words() {
+		/* With two degrees of freedom the upper tail is exp(-x/2). */
+		let r = test("[10 20 30] chisq_test");
+		assert!((get(&r, "statistic") - 10.0).abs() < 1e-12);
+		assert_eq!(get(&r, "df"), 2.0);
+		assert!((get(&r, "p") - (-5f64).exp()).abs() < 1e-10);
+		let r = test("[[10 20 30] [20 20 20]] mat chisq_test");
+		assert!((get(&r, "statistic") - 16.0 / 3.0).abs() < 1e-12);
+		assert!((get(&r, "p") - (-8f64 / 3.0).exp()).abs() < 1e-10);
+		/* Every expected count is 15 and Yates takes 0.5 off each deviation of 5. */
+		let r = test("[[10 20] [20 10]] mat chisq_test");
+		assert!((get(&r, "statistic") - 5.4).abs() < 1e-12);
+		assert_eq!(get(&r, "df"), 1.0);
+		assert_eq!(kind("[[1 2 3]] mat chisq_test"), ErrorType::Domain);
+		assert_eq!(kind("[5] chisq_test"), ErrorType::Domain);
+	}
+
+	#[test]
+	fn prop_words() {
+		let r = test("[50] [100] prop_test");
+		assert_eq!(get(&r, "statistic"), 0.0);
+		assert!((get(&r, "p") - 1.0).abs() < 1e-12);
+		assert_eq!(get(&r, "estimate"), 0.5);
+		/* R: prop.test(c(15, 25), c(50, 50)) gives X-squared = 3.375, p = 0.06619. */
+		let r = test("[15 25] [50 50] prop_test");
+		assert!((get(&r, "statistic") - 3.375).abs() < 1e-12);
+		assert_eq!(get(&r, "df"), 1.0);
+		assert!((get(&r, "p") - 0.06619).abs() < 1e-5);
+		assert_eq!(kind("[5] [3] prop_test"), ErrorType::Domain);
+		assert_eq!(kind("[1 2] [3] prop_test"), ErrorType::Index);
+	}
+
+	#[test]
+	fn cor_words() {
+		/* r = 6 / sqrt(60), so t = r sqrt(3 / (1 - r^2)) = 3 / sqrt(2). */
+		let r = test("[1 2 3 4 5] [2 4 5 4 5] cor_test");
+		assert!((get(&r, "estimate") - 6.0 / 60f64.sqrt()).abs() < 1e-12);
+		assert!((get(&r, "statistic") - 3.0 / 2f64.sqrt()).abs() < 1e-12);
+		assert_eq!(get(&r, "df"), 3.0);
+		assert!((get(&r, "p") - 2.0 * t_cdf(-3.0 / 2f64.sqrt(), 3.0)).abs() < 1e-12);
+		assert_eq!(kind("[1 2 3] [1 2] cor_test"), ErrorType::Index);
+		assert_eq!(kind("[1 2] [3 4] cor_test"), ErrorType::Domain);
+	}
+
+	#[test]
+	fn ks_words() {
+		/* Only one of the 20 orderings of 1..6 separates the samples completely. */
+		let r = test("[1 2 3] [4 5 6] ks_test");
+		assert_eq!(get(&r, "statistic"), 1.0);
+		assert!((get(&r, "p") - 0.1).abs() < 1e-12);
+		/* Against the uniform distribution function the largest gap is 0.9 - 2/3. */
+		let r = test("[0.5 0.9 0.1] [punif] ks_test");
+		assert!((get(&r, "statistic") - (0.9 - 2.0 / 3.0)).abs() < 1e-12);
+		let p = get(&r, "p");
+		assert!(p > 0.0 && p <= 1.0);
+		assert_eq!(kind("[] [1 2] ks_test"), ErrorType::Domain);
+	}
+}
@@ -0,0 +1,143 @@
+/* index.rs
+ * -------- */
+use crate::code::{IndexOp, sprint_indexop};
+use crate::code::IndexOp::*;
+use crate::error::{ErrorType, StaxError, error};
+use crate::vm::Vm;
+use crate::frame::names;
+use crate::words::{BoolResult, Word, WordResult, bool_word, num_word, str_vect, str_word, tighten};
+
+use ndarray::Array1;
+use std::collections::HashMap;
+
+pub fn do_index(vm: &mut Vm, op: IndexOp) -> BoolResult {
+	let nargs = match op { Dict | Get => 2, Keys => 1 };
+	if vm.stack.len() < nargs {
+		return error(ErrorType::Underflow, format!("'{}' requires {} objects on top of the stack. Only {} found.", sprint_indexop(&op), nargs, vm.stack.len()))
+	}
+	let arg = vm.stack.pop().unwrap();
+	let result: Word;
+	match op {
+		Dict 	=> {
+			let keys = vm.stack.pop().unwrap();
+			result = do_dict(vm, keys, arg)?
+		},
+		Get 	=> {
+			let w = match vm.stack.pop().unwrap() {
+				Word::Quote(q) 	=> { tighten(vm.run_newstack(q.to_vec())?) },
+				w => { w }
+			};
+			result = do_get(&w, &arg)?
+		},
+		Keys 	=> match arg {
+			Word::Dict(d) 	=> {
+				let mut keys: Vec<String> = d.keys().cloned().collect();
+				keys.sort();
+				result = str_vect(keys)
+			},
+			Word::Frame(f) 	=> { result = str_vect(f.names.clone()) },
+			_ => { return error(ErrorType::Type, format!("'keys' cannot be completed on objects of type '{}'.", arg.sprint_type())) }
+		}
+	}
+	return vm.push_const(result)
+}
+
+/* 'keys values dict' pairs each name with the value at the same position. Quotes are
+ * evaluated, and a vector of values gives one scalar per key. */
+fn do_dict(vm: &mut Vm, keys: Word, values: Word) -> WordResult {
+	let keys: Vec<String> = match keys {
+		Word::Quote(q) 	=> match tighten(vm.run_newstack(q.to_vec())?) {
+			Word::StrVect(v) 	=> { names(&v)? },
+			Word::List(l) if l.len() == 0 	=> { vec![] },
+			w => { return error(ErrorType::Type, format!("'dict' requires keys of type 'vec<str>'. Given: '{}'.", w.sprint_type())) }
+		},
+		Word::StrVect(v) 	=> { names(&v)? },
+		w => { return error(ErrorType::Type, format!("'dict' requires keys of type 'vec<str>'. Given: '{}'.", w.sprint_type())) }
+	};
+	let values: Vec<Word> = match values {
+		Word::Quote(q) 		=> { vm.run_newstack(q.to_vec())? },
+		Word::List(l) 		=> { *l },
+		Word::Vect(v) 		=> { v.iter().map(|f| num_word(*f)).collect() },
+		Word::StrVect(v) 	=> { v.iter().map(str_word).collect() },
+		Word::BoolVect(v) 	=> { v.iter().map(bool_word).collect() },
+		w => { return error(ErrorType::Type, format!("'dict' cannot take values of type '{}'.", w.sprint_type())) }
+	};
+	if keys.len() != values.len() {
+		return error(ErrorType::Index, format!("'dict' requires one value per key. Given: {} and {}.", keys.len(), values.len()))
+	}
+	return Ok(Word::Dict(Box::new(keys.into_iter().zip(values.into_iter()).collect::<HashMap<String, Word>>())))
+}
+
+/* Looks up a key in a dict, a column of a frame by name or position, or an element of a list
+ * or vector. Indices start at 0 and negative ones count from the end. A quote is evaluated
+ * into the collection it builds first. */
+fn do_get(w: &Word, key: &Word) -> WordResult {
+	match (w, key) {
+		(Word::Dict(d), Word::Str(k)) 	=> match d.get(&**k) {
+			Some(v) => { return Ok(v.clone()) },
+			None 	=> { return error(ErrorType::Index, format!("Key \"{}\" not found.", k)) }
+		},
+		(Word::Frame(f), Word::Str(k)) 	=> match f.column(k) {
+			Some(c) => { return Ok(c.clone()) },
+			None 	=> { return error(ErrorType::Index, format!("Column \"{}\" not found.", k)) }
+		},
+		(Word::Frame(f), _) 	=> { return Ok(f.columns[position(key, f.ncol())?].clone()) },
+		(Word::List(l), _) 		=> { return Ok(l[position(key, l.len())?].clone()) },
+		(Word::Vect(v), _) 		=> { return Ok(num_word(v[position(key, v.len())?])) },
+		(Word::StrVect(v), _) 	=> { return Ok(str_word(&v[position(key, v.len())?])) },
+		(Word::BoolVect(v), _) 	=> { return Ok(bool_word(&v[position(key, v.len())?])) },
+		(Word::Factor(f), _) 	=> { return Ok(str_word(&f.labels()[position(key, f.len())?])) },
+		_ => { return error(ErrorType::Type, format!("'get' cannot be completed on objects of type '{}' and '{}'.", w.sprint_type(), key.sprint_type())) }
+	}
+}
+
+fn position(i: &Word, len: usize) -> Result<usize, StaxError> {
+	let idx = match i {
+		Word::Int(x) 	=> { if *x < 0 { len as i64 + *x as i64 } else { *x as i64 } },
+		_ => { return error(ErrorType::Type, format!("Index must be of type 'int'. Given: '{}'.", i.sprint_type())) }
+	};
+	if idx < 0 || idx >= len as i64 {
+		return error(ErrorType::Index, format!("Index {} is out of bounds for an object of length {}.", i.sprint(), len))
+	}
+	return Ok(idx as usize)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compare::equal_words;
+	use crate::vm::tests::run;
+
+	const D: &str = "d = [\"a\" \"b\"] [1 \"x\"] dict;";
+
+	fn check(src: &str, expected: Word) {
+		let top = run(&format!("{} {}", D, src)).unwrap().pop().unwrap();
+		assert!(equal_words(&top, &expected), "{}: {:?}", src, top);
+	}
+
+	fn kind(src: &str) -> ErrorType {
+		return run(&format!("{} {}", D, src)).unwrap_err().kind
+	}
+
+	#[test]
+	fn lookup() {
+		check("d \"a\" get", Word::Int(1));
+		check("d \"b\" get", Word::Str(Box::new(format!("x"))));
+		check("d keys", str_vect(vec![format!("a"), format!("b")]));
+		check("[\"p\" \"q\"] [1.5 2.5] dict \"q\" get", Word::Num(2.5));
+		check("[10 20 30] -1 get", Word::Int(30));
+		check("[\"u\" \"v\"] 0 get", Word::Str(Box::new(format!("u"))));
+		check("[\"n\"] [[4 5]] frame \"n\" get", Word::Vect(Box::new(Array1::from_vec(vec![4.0, 5.0]))));
+	}
+
+	#[test]
+	fn errors() {
+		assert_eq!(kind("d \"c\" get"), ErrorType::Index);
+		assert_eq!(kind("[10 20 30] 3 get"), ErrorType::Index);
+		assert_eq!(kind("[10 20 30] \"a\" get"), ErrorType::Type);
+		assert_eq!(kind("[\"a\" \"b\"] [1] dict"), ErrorType::Index);
+		assert_eq!(kind("[1 2] [1 2] dict"), ErrorType::Type);
+		assert_eq!(kind("3 keys"), ErrorType::Type);
+	}
+}
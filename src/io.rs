@@ -0,0 +1,288 @@
+/* io.rs
+ * ----- */
+use crate::code::{IoOp, sprint_ioop};
+use crate::code::IoOp::*;
+use crate::error::{ErrorType, StaxError, error};
+use crate::frame::{Frame, cell};
+use crate::na::na;
+use crate::vm::Vm;
+use crate::words::{BoolResult, Word, WordResult};
+
+use csv::{ReaderBuilder, WriterBuilder};
+use ndarray::Array1;
+use std::collections::HashMap;
+
+/* Cells read as NA, in columns of any type. */
+const MISSING: [&str; 2] = ["", "NA"];
+
+/* 'path read_csv' loads a file with a header row into a frame. Optional arguments go on top,
+ * in order: a delimiter for 'read_delim', 'false' for a file without a header, and a dict
+ * mapping column names to "num", "str" or "bool" to override the inferred types. Writing
+ * takes 'frame path write_csv', or 'frame path delim write_delim'. */
+pub fn do_io(vm: &mut Vm, op: IoOp) -> BoolResult {
+	match op {
+		ReadCsv | ReadDelim | ReadTsv 	=> {
+			let types = match vm.stack.last() {
+				Some(Word::Dict(_)) => match vm.stack.pop() { Some(Word::Dict(d)) => Some(*d), _ => None },
+				_ => None
+			};
+			let header = match vm.stack.last() {
+				Some(Word::Bool(_)) => match vm.stack.pop() { Some(Word::Bool(b)) => b, _ => true },
+				_ => true
+			};
+			let delim = match op {
+				ReadCsv 	=> { b',' },
+				ReadTsv 	=> { b'\t' },
+				_ 	=> { delimiter(&pop(vm, &op)?, &op)? }
+			};
+			let path = path(&pop(vm, &op)?, &op)?;
+			let result = read_delim(&path, delim, header, types)?;
+			return vm.push_const(result)
+		},
+		WriteCsv | WriteDelim | WriteTsv 	=> {
+			let delim = match op {
+				WriteCsv 	=> { b',' },
+				WriteTsv 	=> { b'\t' },
+				_ 	=> { delimiter(&pop(vm, &op)?, &op)? }
+			};
+			let path = path(&pop(vm, &op)?, &op)?;
+			let frame = match pop(vm, &op)? {
+				Word::Frame(f) 	=> { *f },
+				Word::Dict(d) 	=> {
+					let mut keys: Vec<String> = d.keys().cloned().collect();
+					keys.sort();
+					let columns = keys.iter().map(|k| d[k].clone()).collect();
+					Frame::new(keys, columns)?
+				},
+				w => { return error(ErrorType::Type, format!("'{}' requires an object of type 'frame' or 'dict'. Given: '{}'.", sprint_ioop(&op), w.sprint_type())) }
+			};
+			write_delim(&frame, &path, delim)?;
+			return Ok(false)
+		}
+	}
+}
+
+
+/* 	== READING == */
+
+fn read_delim(path: &str, delim: u8, header: bool, types: Option<HashMap<String, Word>>) -> WordResult {
+	let mut reader = match ReaderBuilder::new().delimiter(delim).has_headers(false).flexible(true).from_path(path) {
+		Ok(r) 	=> { r },
+		Err(e) 	=> { return error(ErrorType::Io, format!("File '{}' could not be read. {}", path, e)) }
+	};
+	/* Every row must have as many fields as the first, which is the header if there is one. */
+	let mut rows: Vec<Vec<String>> = Vec::new();
+	let mut ncol: Option<usize> = None;
+	for record in reader.records() {
+		let r = match record {
+			Ok(r) 	=> { r },
+			Err(e) 	=> { return error(ErrorType::Io, format!("File '{}' could not be parsed. {}", path, e)) }
+		};
+		let line = r.position().map(|p| p.line()).unwrap_or(rows.len() as u64 + 1);
+		match ncol {
+			Some(n) if n != r.len() => {
+				return error(ErrorType::Io, format!("Line {} of '{}' has {} fields, but {} were expected.", line, path, r.len(), n))
+			},
+			_ => { ncol = Some(r.len()) }
+		}
+		rows.push(r.iter().map(|s| s.to_string()).collect());
+	}
+	let ncol = ncol.unwrap_or(0);
+	let names: Vec<String> = if header && rows.len() > 0 {
+		rows.remove(0).into_iter().enumerate().map(|(j, s)| if s.len() > 0 { s } else { format!("V{}", j + 1) }).collect()
+	} else {
+		(0 .. ncol).map(|j| format!("V{}", j + 1)).collect()
+	};
+
+	let types = types.unwrap_or(HashMap::new());
+	if let Some(k) = types.keys().find(|k| !names.contains(k)) {
+		return error(ErrorType::Index, format!("Column \"{}\" not found in '{}'.", k, path))
+	}
+	let mut columns = Vec::with_capacity(ncol);
+	for (j, name) in names.iter().enumerate() {
+		let cells: Vec<&str> = rows.iter().map(|r| &r[j][..]).collect();
+		let column = match types.get(name) {
+			Some(Word::Str(t)) 	=> match &t[..] {
+				"num" 	=> { Word::Vect(Box::new(cells.iter().map(|s| parse_num(s).unwrap_or(na())).collect::<Array1<f64>>())) },
+				"str" 	=> { Word::StrVect(Box::new(cells.iter().map(|s| parse_str(s)).collect::<Array1<Option<String>>>())) },
+				"bool" 	=> match cells.iter().map(|s| parse_bool(s)).collect::<Option<Array1<Option<bool>>>>() {
+					Some(b) => { Word::BoolVect(Box::new(b)) },
+					None 	=> { return error(ErrorType::Domain, format!("Column \"{}\" cannot be read as 'bool'.", name)) }
+				},
+				_ => { return error(ErrorType::Domain, format!("Column type must be \"num\", \"str\" or \"bool\". Given: \"{}\".", t)) }
+			},
+			Some(w) => { return error(ErrorType::Type, format!("Column types must be of type 'str'. Given: '{}'.", w.sprint_type())) },
+			None 	=> { infer(&cells) }
+		};
+		columns.push(column);
+	}
+	return Ok(Word::Frame(Box::new(Frame::new(names, columns)?)))
+}
+
+/* Numbers if every present cell is one, booleans if every present cell is one, and strings
+ * otherwise. A column with nothing in it is read as numbers. */
+fn infer(cells: &[&str]) -> Word {
+	let present: Vec<&&str> = cells.iter().filter(|s| !is_missing(s)).collect();
+	if present.iter().all(|s| parse_num(s).is_some()) {
+		return Word::Vect(Box::new(cells.iter().map(|s| parse_num(s).unwrap_or(na())).collect::<Array1<f64>>()))
+	}
+	if let Some(b) = cells.iter().map(|s| parse_bool(s)).collect::<Option<Array1<Option<bool>>>>() {
+		return Word::BoolVect(Box::new(b))
+	}
+	return Word::StrVect(Box::new(cells.iter().map(|s| parse_str(s)).collect::<Array1<Option<String>>>()))
+}
+
+fn is_missing(s: &str) -> bool {
+	return MISSING.contains(&s.trim())
+}
+
+fn parse_num(s: &str) -> Option<f64> {
+	if is_missing(s) { return None }
+	return s.trim().parse::<f64>().ok()
+}
+
+/* 'None' when the cell is not a boolean, and 'Some(None)' when it is missing. */
+fn parse_bool(s: &str) -> Option<Option<bool>> {
+	match s.trim() {
+		"true" | "TRUE" | "True" 	=> { return Some(Some(true)) },
+		"false" | "FALSE" | "False" => { return Some(Some(false)) },
+		_ if is_missing(s) 	=> { return Some(None) },
+		_ => { return None }
+	}
+}
+
+fn parse_str(s: &str) -> Option<String> {
+	if is_missing(s) { return None }
+	return Some(s.to_string())
+}
+
+
+/* 	== WRITING == */
+
+fn write_delim(frame: &Frame, path: &str, delim: u8) -> Result<(), StaxError> {
+	let mut writer = match WriterBuilder::new().delimiter(delim).from_path(path) {
+		Ok(w) 	=> { w },
+		Err(e) 	=> { return error(ErrorType::Io, format!("File '{}' could not be written. {}", path, e)) }
+	};
+	let mut records: Vec<Vec<String>> = vec![frame.names.clone()];
+	for i in 0 .. frame.nrow() {
+		records.push(frame.columns.iter().map(|c| cell(c, i)).collect());
+	}
+	for r in records.iter() {
+		if let Err(e) = writer.write_record(r) {
+			return error(ErrorType::Io, format!("File '{}' could not be written. {}", path, e))
+		}
+	}
+	if let Err(e) = writer.flush() {
+		return error(ErrorType::Io, format!("File '{}' could not be written. {}", path, e))
+	}
+	return Ok(())
+}
+
+
+/* 	== HELPERS == */
+
+fn pop(vm: &mut Vm, op: &IoOp) -> WordResult {
+	match vm.stack.pop() {
+		Some(w) => { return Ok(w) },
+		None 	=> { return error(ErrorType::Underflow, format!("'{}' requires more objects on top of the stack.", sprint_ioop(op))) }
+	}
+}
+
+fn path(w: &Word, op: &IoOp) -> Result<String, StaxError> {
+	match w {
+		Word::Str(s) 	=> { return Ok(*s.clone()) },
+		_ => { return error(ErrorType::Type, format!("'{}' requires a path of type 'str'. Given: '{}'.", sprint_ioop(op), w.sprint_type())) }
+	}
+}
+
+fn delimiter(w: &Word, op: &IoOp) -> Result<u8, StaxError> {
+	match w {
+		Word::Str(s) if s.len() == 1 && s.is_ascii() 	=> { return Ok(s.as_bytes()[0]) },
+		_ => { return error(ErrorType::Type, format!("'{}' requires a delimiter of a single character. Given: {}.", sprint_ioop(op), w.sprint())) }
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::na::is_na;
+	use crate::vm::tests::run;
+	use std::fs;
+
+	/* Writes 'text' to a file of its own in the temporary directory. */
+	fn file(name: &str, text: &str) -> String {
+		let path = std::env::temp_dir().join(format!("stax_io_{}_{}", std::process::id(), name));
+		fs::write(&path, text).unwrap();
+		return path.to_string_lossy().to_string()
+	}
+
+	fn read(path: &str, header: bool, types: Option<HashMap<String, Word>>) -> Frame {
+		match read_delim(path, b',', header, types).unwrap() {
+			Word::Frame(f) 	=> { return *f },
+			_ => { panic!("not a frame") }
+		}
+	}
+
+	fn override_type(name: &str, t: &str) -> HashMap<String, Word> {
+		let mut types = HashMap::new();
+		types.insert(name.to_string(), Word::Str(Box::new(t.to_string())));
+		return types
+	}
+
+	#[test]
+	fn inference() {
+		let f = read(&file("infer.csv", "n,b,s,e\n1.5,true,x,\n2,FALSE,3,NA\nNA,NA,y,\n"), true, None);
+		assert_eq!(f.names, vec!["n", "b", "s", "e"]);
+		assert!(matches!(&f.columns[0], Word::Vect(v) if v[0] == 1.5 && v[1] == 2.0 && is_na(v[2])));
+		assert!(matches!(&f.columns[1], Word::BoolVect(v) if v.to_vec() == vec![Some(true), Some(false), None]));
+		assert!(matches!(&f.columns[2], Word::StrVect(v) if v.to_vec() == vec![Some(format!("x")), Some(format!("3")), Some(format!("y"))]));
+		assert!(matches!(&f.columns[3], Word::Vect(v) if v.iter().all(|x| is_na(*x))));
+	}
+
+	#[test]
+	fn quoting_and_header() {
+		let f = read(&file("quote.csv", "1,\"a, b\"\n2,\"say \"\"hi\"\"\"\n"), false, None);
+		assert_eq!(f.names, vec!["V1", "V2"]);
+		assert!(matches!(&f.columns[1], Word::StrVect(v) if v.to_vec() == vec![Some(format!("a, b")), Some(format!("say \"hi\""))]));
+	}
+
+	#[test]
+	fn type_override() {
+		let path = file("types.csv", "id,flag\n007,1\n010,0\n");
+		let types = override_type("id", "str");
+		let f = read(&path, true, Some(types));
+		assert!(matches!(&f.columns[0], Word::StrVect(v) if v[0] == Some(format!("007"))));
+		assert!(matches!(&f.columns[1], Word::Vect(_)));
+		let bad = override_type("flag", "bool");
+		assert_eq!(read_delim(&path, b',', true, Some(bad)).unwrap_err().kind, ErrorType::Domain);
+		let missing = override_type("x", "num");
+		assert_eq!(read_delim(&path, b',', true, Some(missing)).unwrap_err().kind, ErrorType::Index);
+	}
+
+	#[test]
+	fn ragged_rows() {
+		for text in ["a,b\n1,2\n3\n", "a,b\n1,2\n3,4,5\n"] {
+			let e = read_delim(&file("ragged.csv", text), b',', true, None).unwrap_err();
+			assert_eq!(e.kind, ErrorType::Io);
+			assert!(e.msg.contains("Line 3"), "{}", e.msg);
+		}
+	}
+
+	#[test]
+	fn round_trip() {
+		let path = file("round.tsv", "");
+		let src = format!("[\"x\" \"s\"] [[1 NA 2.5] [\"a\" \"b,c\" NA]] frame dup \"{}\" write_tsv \"{}\" read_tsv", path, path);
+		let stack = run(&src).unwrap();
+		match (&stack[0], &stack[1]) {
+			(Word::Frame(a), Word::Frame(b)) 	=> {
+				/* NA cells compare unequal, so the frames are compared as printed. */
+				assert_eq!(a.sprint(), b.sprint());
+				assert!(a.columns.iter().zip(b.columns.iter()).all(|(x, y)| x.sprint_type() == y.sprint_type()));
+			},
+			_ => { panic!("not frames") }
+		}
+		assert_eq!(run("1 \"/nonexistent/stax.csv\" read_csv").unwrap_err().kind, ErrorType::Io);
+	}
+}
@@ -1,12 +1,14 @@
 /* lexer.rs
  * -------- */
 use crate::code::{Opcode, ConstCode, BinOp, BoolOp};
+use crate::error::{ErrorType, StaxError, error};
 use crate::words::{BoolResult, Word};
 
 use unicode_segmentation::UnicodeSegmentation;
 use rustyline::Editor;
 use std::cmp::Ordering;
 use std::mem;
+use std::rc::Rc;
 
 
 /*  HELPER FUNCTIONS    */
@@ -17,7 +19,7 @@ fn stop_chars_num(ch: &String) -> bool { return " \t\r\n[](){}!@#$%^&*-+=:;,<>?/
 fn alpha_chars(ch: &String) -> bool { return "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ".contains(ch) }
 
 /*  HELPER TYPES  */
-type TokenResult = Result<Token, String>;
+type TokenResult = Result<Token, StaxError>;
 
 
 #[derive(Clone, Debug, PartialEq)]
@@ -114,16 +116,36 @@ impl PartialEq for Prec {
 
 
 
+/*  SOURCE SPANS  */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+	pub file 	: Rc<String>,
+	pub line 	: u16,
+	pub col 	: u16
+}
+
+impl Span {
+	pub fn sprint(&self) -> String {
+		return format!("{}:{}:{}", self.file, self.line, self.col)
+	}
+}
+
 /*  MAIN PARSER  */
 pub struct Lexer {
     pub breaks      : Vec<usize>,
     pub chars       : Vec<String>,
     pub current 	: Token,
-    file	 		: String,
+    file	 		: Rc<String>,
     interactive 	: bool,
     line 			: u16,
     pos             : usize,
     pub future		: Token,
+    pub spans       : Vec<Span>,        // spans recorded by the compiler, see 'record_span'
+    pub tables      : Vec<Vec<Span>>,   // one table per compiled block, parallel to its opcodes
+    pub table_base  : usize,            // id of the first table, set by the Vm
+    start           : (u16, u16),
+    cspan           : (u16, u16),
+    fspan           : (u16, u16),
 }
 
 
@@ -133,17 +155,24 @@ impl Lexer {
 			breaks      : Vec::new(),
             chars       : UnicodeSegmentation::graphemes(&input[..], true).into_iter().map(|x| x.to_string()).collect::<Vec<String>>(),
             current 	: Token::Blank,
-            file        : file.to_string(),
+            file        : Rc::new(file.to_string()),
             interactive : interactive,  
             line        : 0,
             pos         : 0,
            	future 		: Token::Blank,
+           	spans       : Vec::new(),
+           	tables      : Vec::new(),
+           	table_base  : 0,
+           	start       : (1, 1),
+           	cspan       : (1, 1),
+           	fspan       : (1, 1),
 		};
 		return lexer
 	}
 
 
 	pub fn next_token(&mut self) -> BoolResult {
+		self.start = self.position(self.pos);
 		if self.pos >= self.chars.len() {
 			return self.token(Token::Eof)
 		}
@@ -223,6 +252,23 @@ impl Lexer {
         return self.token(Token::Infix(op, prec))
 	}
 
+	/* Records the span of the current token and returns its index in 'spans'. */
+	pub fn record_span(&mut self) -> u32 {
+		self.spans.push(Span{ file: self.file.clone(), line: self.cspan.0, col: self.cspan.1 });
+		return (self.spans.len() - 1) as u32
+	}
+
+	pub fn file(&self) -> Rc<String> {
+		return self.file.clone()
+	}
+
+	fn position(&self, pos: usize) -> (u16, u16) {
+		match self.breaks.last() {
+			Some(b) => { return (self.line + 1, (pos - b) as u16) },
+			None 	=> { return (1, (pos + 1) as u16) }
+		}
+	}
+
 	pub fn is_match(&self, tok: &Vec<Token>) -> bool {
     	for i in tok {
     		if self.current == *i { return true }
@@ -302,9 +348,9 @@ impl Lexer {
             if ch == "" {
                 if self.interactive {
                     let hasErr = self.prompt();
-                    if hasErr { return Err( format!("Unable to read user input.") ) }
+                    if hasErr { return error(ErrorType::Io, format!("Unable to read user input.")) }
                 } else {
-                    return Err( format!("File ended before reaching '{}'. Command could not be determined.", stop))
+                    return error(ErrorType::Syntax, format!("File ended before reaching '{}'. Command could not be determined.", stop))
                 }
                 continue
             } else if ch == stop {
@@ -325,6 +371,7 @@ impl Lexer {
             "true"  => { return self.token(Token::Const(Opcode::Const(ConstCode::True))) },
             "false" => { return self.token(Token::Const(Opcode::Const(ConstCode::False))) },
             "none"  => { return self.token(Token::Const(Opcode::Const(ConstCode::Null))) },
+            "NA"    => { return self.token(Token::Const(Opcode::Const(ConstCode::Na))) },
             _ => { }
         }
         return self.token(Token::Sym(Box::new(s)))
@@ -332,11 +379,13 @@ impl Lexer {
 
     fn token(&mut self, tok: Token) -> BoolResult {
 		self.current = mem::replace(&mut self.future, tok);
+		self.cspan = mem::replace(&mut self.fspan, self.start);
 		return Ok(false)
 	}
 
 	fn token_adv(&mut self, tok: Token) -> BoolResult {
 		self.current = mem::replace(&mut self.future, tok);
+		self.cspan = mem::replace(&mut self.fspan, self.start);
 		self.pos += 1;
 		return Ok(false)
 	}
@@ -10,15 +10,27 @@ pub mod code;
 pub mod combinator;
 pub mod compare;
 pub mod compiler;
+pub mod dist;
 pub mod env;
 pub mod error;
 pub mod eval;
 pub mod expr;
+pub mod factor;
+pub mod frame;
 pub mod genop;
+pub mod htest;
+pub mod index;
+pub mod io;
 pub mod lexer;
+pub mod linalg;
 pub mod math;
+pub mod matrix;
+pub mod na;
 pub mod parser; // TODO: change/remove!
+pub mod random;
+pub mod sort;
 pub mod stackop;
+pub mod stats;
 pub mod text_format;
 pub mod unop;
 pub mod vm;
@@ -0,0 +1,298 @@
+/* linalg.rs
+ * --------- */
+use crate::code::{LinOp, sprint_linop};
+use crate::code::LinOp::*;
+use crate::error::{ErrorType, StaxError, error};
+use crate::vm::Vm;
+use crate::words::{BoolResult, Word, WordResult, toF64};
+
+use ndarray::{Array1, Array2, Axis};
+use ndarray_linalg::{Cholesky, Determinant, Eig, Eigh, Inverse, QR, SVD, Solve, UPLO};
+use ndarray_linalg::error::LinalgError;
+
+use std::collections::HashMap;
+
+pub fn do_linalg(vm: &mut Vm, op: LinOp) -> BoolResult {
+	let nargs = match op { Matmul | Solve => 2, _ => 1 };
+	if vm.stack.len() < nargs {
+		return error(ErrorType::Underflow, format!("'{}' requires {} objects on top of the stack. Only {} found.", sprint_linop(&op), nargs, vm.stack.len()))
+	}
+	let arg = vm.stack.pop().unwrap();
+	let result: Word;
+	match op {
+		Matmul 	=> { result = do_matmul(&vm.stack.pop().unwrap(), &arg)? },
+		Solve 	=> { result = do_solve(&vm.stack.pop().unwrap(), &arg)? },
+		Transpose 	=> match arg {
+			Word::Vect(v) 	=> { result = Word::Mat(Box::new(v.insert_axis(Axis(0)))) },
+			_ => { result = Word::Mat(Box::new(to_mat(&arg, &op)?.reversed_axes())) }
+		},
+		_ => { result = do_mat(to_mat(&arg, &op)?, &op)? }
+	}
+	return vm.push_const(result)
+}
+
+fn do_mat(m: Array2<f64>, op: &LinOp) -> WordResult {
+	match op {
+		Chol 	=> {
+			let l = m.cholesky(UPLO::Lower).map_err(linalg_error)?;
+			return Ok(factors(vec![("l", Word::Mat(Box::new(l)))]))
+		},
+		Det 	=> {
+			square(&m, op)?;
+			return Ok(Word::Num(m.det().map_err(linalg_error)?))
+		},
+		Eigen 	=> { return eigen(m) },
+		Inv 	=> {
+			square(&m, op)?;
+			return Ok(Word::Mat(Box::new(m.inv().map_err(linalg_error)?)))
+		},
+		Qr 		=> {
+			let (q, r) = m.qr().map_err(linalg_error)?;
+			return Ok(factors(vec![("q", Word::Mat(Box::new(q))), ("r", Word::Mat(Box::new(r)))]))
+		},
+		Rank 	=> {
+			if m.len() == 0 { return Ok(Word::Int(0)) }
+			let (_, s, _) = m.svd(false, false).map_err(linalg_error)?;
+			let tol = s.fold(0.0_f64, |a, b| a.max(*b)) * (m.nrows().max(m.ncols()) as f64) * f64::EPSILON;
+			return Ok(Word::Int(s.iter().filter(|x| **x > tol).count() as i32))
+		},
+		Svd 	=> {
+			let (u, s, vt) = m.svd(true, true).map_err(linalg_error)?;
+			return Ok(factors(vec![
+				("u", Word::Mat(Box::new(u.unwrap()))),
+				("d", Word::Vect(Box::new(s))),
+				("vt", Word::Mat(Box::new(vt.unwrap())))
+			]))
+		},
+		Trace 	=> {
+			square(&m, op)?;
+			return Ok(Word::Num(m.diag().sum()))
+		},
+		_ => { return error(ErrorType::Type, format!("Linear algebra operation '{}' not recognized!", sprint_linop(op))) }
+	}
+}
+
+/* Matrix-matrix and matrix-vector products. Two vectors give their dot product. As in R, a
+ * vector next to a matrix with a single row (or column) is taken as a column (or row). */
+fn do_matmul(lhs: &Word, rhs: &Word) -> WordResult {
+	let (l, r) = match (lhs, rhs) {
+		(Word::Vect(l), Word::Vect(r)) 	=> {
+			if l.len() != r.len() { return shape_error(&[l.len()], &[r.len()], &Matmul) }
+			return Ok(Word::Num(l.dot(&**r)))
+		},
+		(Word::Mat(l), Word::Vect(r)) if l.ncols() == r.len() 	=> {
+			return Ok(Word::Vect(Box::new(l.dot(&**r))))
+		},
+		(Word::Mat(l), Word::Vect(r)) 	=> { (*l.clone(), r.to_owned().insert_axis(Axis(0))) },
+		(Word::Vect(l), Word::Mat(r)) if l.len() == r.nrows() 	=> {
+			return Ok(Word::Vect(Box::new(l.dot(&**r))))
+		},
+		_ => { (to_mat(lhs, &Matmul)?, to_mat(rhs, &Matmul)?) }
+	};
+	if l.ncols() != r.nrows() { return shape_error(l.shape(), r.shape(), &Matmul) }
+	return Ok(Word::Mat(Box::new(l.dot(&r))))
+}
+
+/* Solves 'a x = b' for a vector or for every column of a matrix 'b'. */
+fn do_solve(a: &Word, b: &Word) -> WordResult {
+	let a = to_mat(a, &Solve)?;
+	square(&a, &Solve)?;
+	match b {
+		Word::Vect(v) 	=> {
+			if v.len() != a.nrows() { return shape_error(a.shape(), v.shape(), &Solve) }
+			return Ok(Word::Vect(Box::new(a.solve(&**v).map_err(linalg_error)?)))
+		},
+		_ => {
+			let b = to_mat(b, &Solve)?;
+			if b.nrows() != a.nrows() { return shape_error(a.shape(), b.shape(), &Solve) }
+			let mut x = Array2::<f64>::zeros(b.raw_dim());
+			for (j, col) in b.axis_iter(Axis(1)).enumerate() {
+				x.column_mut(j).assign(&a.solve(&col).map_err(linalg_error)?);
+			}
+			return Ok(Word::Mat(Box::new(x)))
+		}
+	}
+}
+
+/* Eigenvalues in decreasing order with their eigenvectors as columns. Symmetric matrices use
+ * the symmetric solver; complex eigenvalues of other matrices are not representable. */
+fn eigen(m: Array2<f64>) -> WordResult {
+	square(&m, &Eigen)?;
+	let (values, vectors): (Array1<f64>, Array2<f64>);
+	if m == m.t() {
+		let (e, v) = m.eigh(UPLO::Lower).map_err(linalg_error)?;
+		values = e;
+		vectors = v;
+	} else {
+		let (e, v) = m.eig().map_err(linalg_error)?;
+		let scale = e.iter().fold(1.0_f64, |a, c| a.max(c.norm()));
+		if e.iter().any(|c| c.im.abs() > 1e-10 * scale) {
+			return error(ErrorType::Domain, format!("Matrix has complex eigenvalues, which cannot be represented."))
+		}
+		values = e.mapv(|c| c.re);
+		vectors = v.mapv(|c| c.re);
+	}
+	let mut order: Vec<usize> = (0 .. values.len()).collect();
+	order.sort_by(|a, b| values[*b].partial_cmp(&values[*a]).unwrap_or(std::cmp::Ordering::Equal));
+	return Ok(factors(vec![
+		("values", Word::Vect(Box::new(values.select(Axis(0), &order)))),
+		("vectors", Word::Mat(Box::new(vectors.select(Axis(1), &order))))
+	]))
+}
+
+
+/* 	== HELPERS == */
+
+fn to_mat(w: &Word, op: &LinOp) -> Result<Array2<f64>, StaxError> {
+	match w {
+		Word::Mat(m) 	=> { return Ok(*m.clone()) },
+		Word::Vect(v) 	=> { return Ok(v.to_owned().insert_axis(Axis(1))) },
+		Word::Int(_) | Word::Num(_) 	=> { return Ok(Array2::from_elem((1, 1), toF64(w))) },
+		_ => { return error(ErrorType::Type, format!("'{}' cannot be completed on objects of type '{}'.", sprint_linop(op), w.sprint_type())) }
+	}
+}
+
+fn square(m: &Array2<f64>, op: &LinOp) -> Result<(), StaxError> {
+	if m.is_square() { return Ok(()) }
+	return error(ErrorType::Domain, format!("'{}' requires a square matrix. Given: {}x{}.", sprint_linop(op), m.nrows(), m.ncols()))
+}
+
+fn factors(items: Vec<(&str, Word)>) -> Word {
+	let dict: HashMap<String, Word> = items.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+	return Word::Dict(Box::new(dict))
+}
+
+fn shape_error(lhs: &[usize], rhs: &[usize], op: &LinOp) -> WordResult {
+	return error(ErrorType::Index, format!("'{}' cannot be completed between objects of shape {:?} and {:?}.", sprint_linop(op), lhs, rhs))
+}
+
+fn linalg_error(e: LinalgError) -> StaxError {
+	return StaxError::new(ErrorType::Domain, format!("Linear algebra routine failed. {}", e))
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vm::tests::run;
+	use ndarray::array;
+
+	fn get(w: &Word, key: &str) -> Array2<f64> {
+		match w {
+			Word::Dict(d) 	=> match d.get(key) {
+				Some(Word::Mat(m)) 	=> { return *m.clone() },
+				Some(Word::Vect(v)) 	=> { return v.to_owned().insert_axis(Axis(1)) },
+				_ => { panic!("no matrix '{}'", key) }
+			},
+			_ => { panic!("not a dict") }
+		}
+	}
+
+	fn close(a: &Array2<f64>, b: &Array2<f64>) -> bool {
+		return a.shape() == b.shape() && a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-9)
+	}
+
+	fn num(w: Word) -> f64 {
+		match w { Word::Num(f) => f, w => panic!("not a number: {}", w.sprint_type()) }
+	}
+
+	#[test]
+	fn products() {
+		let a = Word::Mat(Box::new(array![[1.0, 2.0], [3.0, 4.0]]));
+		let v = Word::Vect(Box::new(array![1.0, 1.0]));
+		match do_matmul(&a, &a).unwrap() {
+			Word::Mat(m) 	=> { assert!(close(&m, &array![[7.0, 10.0], [15.0, 22.0]])) },
+			_ => { panic!("matmul") }
+		}
+		match do_matmul(&a, &v).unwrap() {
+			Word::Vect(r) 	=> { assert_eq!(*r, array![3.0, 7.0]) },
+			_ => { panic!("matmul") }
+		}
+		assert_eq!(num(do_matmul(&v, &v).unwrap()), 2.0);
+		let b = Word::Vect(Box::new(array![1.0, 2.0, 3.0]));
+		assert_eq!(do_matmul(&a, &b).unwrap_err().kind, ErrorType::Index);
+	}
+
+	#[test]
+	fn square_words() {
+		let m = array![[2.0, 1.0], [1.0, 3.0]];
+		assert_eq!(num(do_mat(m.clone(), &Trace).unwrap()), 5.0);
+		assert!((num(do_mat(m.clone(), &Det).unwrap()) - 5.0).abs() < 1e-12);
+		match do_mat(m.clone(), &Inv).unwrap() {
+			Word::Mat(i) 	=> { assert!(close(&m.dot(&*i), &Array2::eye(2))) },
+			_ => { panic!("inv") }
+		}
+		let r = array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+		for op in [Trace, Det, Inv] {
+			assert_eq!(do_mat(r.clone(), &op).unwrap_err().kind, ErrorType::Domain);
+		}
+	}
+
+	#[test]
+	fn singular() {
+		let s = array![[1.0, 2.0], [2.0, 4.0]];
+		assert_eq!(do_mat(s.clone(), &Inv).unwrap_err().kind, ErrorType::Domain);
+		let b = Word::Vect(Box::new(array![1.0, 1.0]));
+		assert_eq!(do_solve(&Word::Mat(Box::new(s)), &b).unwrap_err().kind, ErrorType::Domain);
+	}
+
+	#[test]
+	fn solve() {
+		let a = Word::Mat(Box::new(array![[2.0, 1.0], [1.0, 3.0]]));
+		match do_solve(&a, &Word::Vect(Box::new(array![3.0, 5.0]))).unwrap() {
+			Word::Vect(x) 	=> { assert!(close(&x.insert_axis(Axis(1)), &array![[0.8], [1.4]])) },
+			_ => { panic!("solve") }
+		}
+		match do_solve(&a, &Word::Mat(Box::new(Array2::eye(2)))).unwrap() {
+			Word::Mat(x) 	=> { assert!(close(&x, &(array![[3.0, -1.0], [-1.0, 2.0]] / 5.0))) },
+			_ => { panic!("solve") }
+		}
+	}
+
+	#[test]
+	fn cholesky() {
+		let m = array![[4.0, 2.0], [2.0, 3.0]];
+		let l = get(&do_mat(m.clone(), &Chol).unwrap(), "l");
+		assert_eq!(l[[0, 1]], 0.0);
+		assert!(close(&l.dot(&l.t()), &m));
+		let indefinite = array![[1.0, 2.0], [2.0, 1.0]];
+		assert_eq!(do_mat(indefinite, &Chol).unwrap_err().kind, ErrorType::Domain);
+	}
+
+	#[test]
+	fn decompositions() {
+		let m = array![[1.0, 2.0], [3.0, 4.0], [5.0, 7.0]];
+		let qr = do_mat(m.clone(), &Qr).unwrap();
+		let (q, r) = (get(&qr, "q"), get(&qr, "r"));
+		assert!(close(&q.t().dot(&q), &Array2::eye(2)));
+		assert_eq!(r[[1, 0]], 0.0);
+		assert!(close(&q.dot(&r), &m));
+		let svd = do_mat(m.clone(), &Svd).unwrap();
+		let (u, d, vt) = (get(&svd, "u"), get(&svd, "d"), get(&svd, "vt"));
+		assert!(d[[0, 0]] >= d[[1, 0]]);
+		let mut s = Array2::<f64>::zeros((3, 2));
+		s.diag_mut().assign(&d.column(0));
+		assert!(close(&u.dot(&s).dot(&vt), &m));
+	}
+
+	#[test]
+	fn symmetric_eigen() {
+		let m = array![[2.0, 1.0], [1.0, 2.0]];
+		let e = do_mat(m.clone(), &Eigen).unwrap();
+		let (values, vectors) = (get(&e, "values"), get(&e, "vectors"));
+		assert!(close(&values, &array![[3.0], [1.0]]));
+		for j in 0 .. 2 {
+			let v = vectors.column(j);
+			assert!(close(&m.dot(&v).insert_axis(Axis(1)), &(&v * values[[j, 0]]).insert_axis(Axis(1))));
+		}
+	}
+
+	#[test]
+	fn matrix_rank() {
+		assert!(matches!(do_mat(array![[1.0, 2.0], [2.0, 4.0], [3.0, 6.0]], &Rank).unwrap(), Word::Int(1)));
+		assert!(matches!(do_mat(Array2::eye(3), &Rank).unwrap(), Word::Int(3)));
+		/* 'rank' of a matrix is not the matrix rank. */
+		assert!(matches!(run("[[1 2] [3 4]] mat mrank").unwrap().pop(), Some(Word::Int(2))));
+		assert!(run("[[1 2] [3 4]] mat rank").is_err());
+	}
+}
@@ -1,72 +1,286 @@
 use stax::base::create_base;
-use stax::parser::Parser;
 use stax::code::sprint_opcode;
-use stax::vm::Vm;
+use stax::vm::{Vm, MAX_DEPTH};
 
 use stax::code::Opcode;
-use stax::words::Word;
+use stax::words::{Word, str_vect};
 
 use stax::lexer::Lexer;
-use stax::compiler::compile;
 
 use rustyline::Editor;
+use rustyline::error::ReadlineError;
 
+use std::env;
+use std::fs;
+use std::io::{stdin, Read};
 use std::mem::size_of;
+use std::process;
+use std::thread;
+
+// Nested calls recurse in the evaluator. The VM stops them before the thread's stack runs
+// out, so a large stack only lets programs nest deeper before reaching 'max_depth'.
+const THREAD_STACK: usize = 1 << 30;
+
+const USAGE: &str = "usage: stax [options] [script.stax | -] [args...]
+
+options:
+    -d, --disasm         print the compiled opcodes of every statement, and opcode sizes in the REPL
+    -q, --quiet          do not print the stack after each statement
+    -e, --eval <expr>    evaluate <expr>, print the stack and exit
+    -p, --prelude <file> run <file> before the program or REPL starts
+    -m, --max-depth <n>  limit nested calls to <n> frames (default 10000)
+    -h, --help           print this message
+    -                    read the program from stdin";
+
+struct Options {
+    disasm  : bool,
+    quiet   : bool,
+    eval    : Option<String>,
+    prelude : Option<String>,
+    depth   : usize,
+    script  : Option<String>,
+    args    : Vec<String>
+}
 
 fn main() {
-    let flag_byteprint = true;
+    let opts: Options;
+    match parse_args(env::args().skip(1).collect()) {
+        Ok(o)   => { opts = o },
+        Err(e)  => {
+            eprintln!("ERROR: {}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    }
 
-    println!("Size of opcode: {}", size_of::<Opcode>());
-    println!("Size of word: {}", size_of::<Word>());
+    let child = thread::Builder::new().stack_size(THREAD_STACK).spawn(move || { run(opts) });
+    match child.map(|c| c.join()) {
+        Ok(Ok(code))    => { process::exit(code) },
+        _               => { process::exit(1) }
+    }
+}
 
-    let mut cli = Editor::<()>::new();
+fn run(opts: Options) -> i32 {
+    let mut vm = new_vm(opts.args.clone());
+    vm.max_depth = opts.depth;
+    match &opts.prelude {
+        Some(file) => {
+            if !run_file(&mut vm, file, &opts) { return 1 }
+        },
+        None => { }
+    }
+
+    match (&opts.eval, &opts.script) {
+        (Some(expr), _) => {
+            let ok = run_source(&mut vm, expr.to_string(), "eval", false, &opts, true);
+            return if ok { 0 } else { 1 }
+        },
+        (None, Some(file)) => {
+            let ok: bool;
+            if file == "-" {
+                let mut input = String::new();
+                match stdin().read_to_string(&mut input) {
+                    Ok(_)   => { ok = run_source(&mut vm, input, "stdin", false, &opts, false) },
+                    Err(e)  => {
+                        eprintln!("ERROR: Program could not be read from stdin. {}", e);
+                        ok = false;
+                    }
+                }
+            } else { ok = run_file(&mut vm, file, &opts) }
+            return if ok { 0 } else { 1 }
+        },
+        (None, None) => {
+            repl(&mut vm, &opts);
+            return 0
+        }
+    }
+}
+
+fn parse_args(argv: Vec<String>) -> Result<Options, String> {
+    let mut opts = Options { disasm: false, quiet: false, eval: None, prelude: None, depth: MAX_DEPTH, script: None, args: vec![] };
+    let mut i = 0;
+    while i < argv.len() {
+        match &argv[i][..] {
+            "-d" | "--disasm"   => { opts.disasm = true },
+            "-q" | "--quiet"    => { opts.quiet = true },
+            "-h" | "--help"     => {
+                println!("{}", USAGE);
+                process::exit(0);
+            },
+            "-m" | "--max-depth" => {
+                if i + 1 >= argv.len() { return Err(format!("Option '{}' requires a value.", argv[i])) }
+                match argv[i+1].parse::<usize>() {
+                    Ok(n) if n > 2  => { opts.depth = n },
+                    _               => { return Err(format!("Option '{}' requires a number greater than 2.", argv[i])) }
+                }
+                i += 1;
+            },
+            "-e" | "--eval" | "-p" | "--prelude" => {
+                if i + 1 >= argv.len() { return Err(format!("Option '{}' requires a value.", argv[i])) }
+                if argv[i] == "-e" || argv[i] == "--eval" {
+                    opts.eval = Some(argv[i+1].clone());
+                } else { opts.prelude = Some(argv[i+1].clone()) }
+                i += 1;
+            },
+            "--"    => {
+                if i + 1 < argv.len() { opts.script = Some(argv[i+1].clone()) }
+                if opts.eval.is_some() && opts.script.is_some() { return Err(format!("Option '-e' cannot be combined with a script.")) }
+                opts.args = argv[(i+2).min(argv.len())..].to_vec();
+                return Ok(opts)
+            },
+            arg     => {
+                if arg.starts_with("-") && arg != "-" { return Err(format!("Option '{}' not recognized.", arg)) }
+                if opts.eval.is_some() { return Err(format!("Option '-e' cannot be combined with a script.")) }
+                opts.script = Some(arg.to_string());
+                opts.args = argv[i+1..].to_vec();
+                return Ok(opts)
+            }
+        }
+        i += 1;
+    }
+    return Ok(opts)
+}
+
+fn new_vm(args: Vec<String>) -> Vm {
     let mut vm  = Vm::new();
     vm.envs.push(create_base());
     vm.ep += 1;
     vm.env_push(vec![]); // GLOBAL!
+    vm.global().dict.insert("args".to_string(), str_vect(args));
+    return vm
+}
 
-    loop {
-        let s = cli.readline("> ");
-        if s.is_err() {
-            println!("There has been an error!");
-            continue;
+fn print_opcodes(statements: &Vec<Vec<Opcode>>) {
+    for statement in statements.iter() {
+        if statement.len() > 0 {
+            for op in 0.. statement.len() {
+                println!("{:0>6} {}", op, sprint_opcode(&statement[op]));
+            }
+            println!("");
         }
-        let input = s.unwrap();
-        if input == "".to_string() { continue }
-        if input == "quit".to_string() { break }
-        
-        let mut lexer = Lexer::new(input, true, "interactive");
-        match compile(&mut lexer) {
-            Ok(v)   => {
-                if flag_byteprint {
-                    for statement in v.iter() {
-                        if statement.len() > 0 {
-                            for op in 0.. statement.len() {
-                                println!("{:0>6} {}", op, sprint_opcode(&statement[op]));
+    }
+}
+
+/* Compiles and runs 'input' statement by statement. Returns false on the first error.
+ * With 'toplevel', the stack is printed (unless quiet) and cleared after each statement. */
+fn run_source(vm: &mut Vm, input: String, file: &str, interactive: bool, opts: &Options, toplevel: bool) -> bool {
+    let mut lexer = Lexer::new(input, interactive, file);
+    match vm.compile(&mut lexer) {
+        Ok(v)   => {
+            if opts.disasm { print_opcodes(&v) }
+            for statement in v {
+                if statement.len() > 0 {
+                    vm.global().reset(statement);
+                    match vm.eval() {
+                        Ok(_)   => {
+                            if toplevel && vm.stack.len() > 0 {
+                                // TODO: clear except for last, then print last!
+                                if !opts.quiet { vm.print_stack() }
+                                vm.clear();
                             }
-                            println!("");
-                        }
-                    }
-                }
-                for statement in v {
-                    if statement.len() > 0 {
-                        vm.global().reset(statement);
-                        let result = vm.eval();
-                        match result {
-                            Ok(_)   => {
-                                if vm.stack.len() > 0 { 
-                                    // TODO: clear except for last, then print last!
-                                    vm.print_stack();
-                                    vm.clear(); 
-                                }
-                            },
-                            Err(e)  => { println!("ERROR: {}", e) }
+                        },
+                        Err(e)  => {
+                            eprintln!("{}", e.sprint());
+                            return false
                         }
                     }
                 }
-            },
-            Err(e)  => { println!("ERROR: {}\n", e) }
+            }
+            return true
+        },
+        Err(e)  => {
+            eprintln!("{}", e.sprint());
+            return false
+        }
+    }
+}
+
+fn run_file(vm: &mut Vm, file: &String, opts: &Options) -> bool {
+    match fs::read_to_string(file) {
+        Ok(input)   => { return run_source(vm, input, file, false, opts, false) },
+        Err(e)      => {
+            eprintln!("ERROR: File '{}' could not be read. {}", file, e);
+            return false
         }
     }
+}
+
+fn repl(vm: &mut Vm, opts: &Options) {
+    if opts.disasm {
+        println!("Size of opcode: {}", size_of::<Opcode>());
+        println!("Size of word: {}", size_of::<Word>());
+    }
 
-}
\ No newline at end of file
+    let mut cli = Editor::<()>::new();
+    loop {
+        let input: String;
+        match cli.readline("> ") {
+            Ok(s)   => { input = s },
+            Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => { break },
+            Err(_)  => {
+                println!("There has been an error!");
+                continue;
+            }
+        }
+        if input == "".to_string() { continue }
+        if input == "quit".to_string() { break }
+        cli.add_history_entry(input.as_str());
+
+        let base = vm.next_span;
+        run_source(vm, input, "interactive", true, opts, true);
+        vm.free_spans(base);
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(argv: &[&str]) -> Result<Options, String> {
+        return parse_args(argv.iter().map(|s| s.to_string()).collect())
+    }
+
+    fn fails(argv: &[&str]) -> String {
+        match parse(argv) {
+            Ok(_)   => { panic!("{:?} parsed", argv) },
+            Err(e)  => { return e }
+        }
+    }
+
+    #[test]
+    fn options() {
+        let opts = parse(&[]).unwrap();
+        assert!(!opts.disasm && !opts.quiet && opts.eval.is_none() && opts.script.is_none());
+        assert_eq!(opts.depth, MAX_DEPTH);
+        let opts = parse(&["-d", "--quiet", "-m", "50", "-p", "lib.stax", "-e", "1 2 add"]).unwrap();
+        assert!(opts.disasm && opts.quiet);
+        assert_eq!(opts.depth, 50);
+        assert_eq!(opts.prelude, Some("lib.stax".to_string()));
+        assert_eq!(opts.eval, Some("1 2 add".to_string()));
+    }
+
+    #[test]
+    fn script_args() {
+        /* Everything after the script belongs to the program, options included. */
+        let opts = parse(&["-q", "run.stax", "-d", "x"]).unwrap();
+        assert_eq!(opts.script, Some("run.stax".to_string()));
+        assert_eq!(opts.args, vec!["-d".to_string(), "x".to_string()]);
+        assert!(!opts.disasm);
+        let opts = parse(&["-"]).unwrap();
+        assert_eq!(opts.script, Some("-".to_string()));
+        let opts = parse(&["--", "-odd.stax", "a"]).unwrap();
+        assert_eq!(opts.script, Some("-odd.stax".to_string()));
+        assert_eq!(opts.args, vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(fails(&["-x"]).contains("not recognized"));
+        assert!(fails(&["-e"]).contains("requires a value"));
+        assert!(fails(&["-m", "2"]).contains("greater than 2"));
+        assert!(fails(&["-m", "many"]).contains("greater than 2"));
+        assert!(fails(&["-e", "1", "run.stax"]).contains("cannot be combined"));
+        assert!(fails(&["-e", "1", "--", "run.stax"]).contains("cannot be combined"));
+    }
+}
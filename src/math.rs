@@ -2,30 +2,60 @@
  * ------- */
 use crate::code::{MathOp, sprint_mathop};
 use crate::code::MathOp::*;
+use crate::error::{ErrorType, StaxError, error};
+use crate::htest::rank_avg;
+use crate::na::{is_na, na};
 use crate::vm::Vm;
-use crate::words::{Word, WordResult, list2array1};
+use crate::words::{Word, WordResult, list2array1, num_word, toF64};
 
 use core::f64::NAN;
-use ndarray::{Array1, Array2};
+use ndarray::{Array1, Array2, Axis};
 use ndarray_stats::{QuantileExt, SummaryStatisticsExt};
 
+use std::collections::HashMap;
+
 pub fn do_math(vm: &mut Vm, op: MathOp, word: &Word) -> WordResult {
-	match word {
-		Word::Int(i) 	=> { return do_num(op, *i as f64) },
-		Word::Num(f)	=> { return do_num(op, *f) },
-		Word::List(l) 	=> { return do_vect(op, &list2array1(l)) },
-		Word::Quote(q)	=> {
-			match vm.run_newstack(q.to_vec()) {
-				Ok(w) => { return do_vect(op, &list2array1(&w)) },
-				Err(e) 	=> { return Err(e) }
-			}
-		},
-		Word::Vect(v)	=> { return do_vect(op, v)},
-		_ => { return Err(format!("Operation '{}' cannot be completed on objects of type '{}'.", sprint_mathop(&op), word.sprint_type())) }
+	match op {
+		Cor | Cov 	=> { return do_cor(vm, op, word) },
+		_ => { }
+	}
+	let na_rm = vm.na_rm;
+	let result = match op {
+		Quantile | Wmean 	=> { do_binary(vm, op, word, na_rm)? },
+		_ => match word {
+			Word::Int(i) 	=> { do_num(op, *i as f64, na_rm)? },
+			Word::Na 		=> { do_num(op, na(), na_rm)? },
+			Word::Num(f)	=> { do_num(op, *f, na_rm)? },
+			Word::List(l) 	=> { do_vect(op, &list2array1(l), na_rm)? },
+			Word::Quote(q)	=> { do_vect(op, &list2array1(&vm.run_newstack(q.to_vec())?), na_rm)? },
+			Word::Vect(v)	=> { do_vect(op, v, na_rm)? },
+			Word::BoolVect(b) 	=> { do_vect(op, &bool2array1(b), na_rm)? },
+			_ => { return error(ErrorType::Type, format!("Operation '{}' cannot be completed on objects of type '{}'.", sprint_mathop(&op), word.sprint_type())) }
+		}
+	};
+	match result {
+		Word::Num(f) 	=> { return Ok(num_word(f)) },
+		_ => { return Ok(result) }
+	}
+}
+
+/* Reductions skip NaN, as they always have, but an NA anywhere makes the result NA as in R.
+ * Inside '[...] na_rm' they skip NA as well. */
+fn is_reduction(op: &MathOp) -> bool {
+	match op {
+		Iqr | Kurtosis | Mad | Max | Mean | Median | Min | Mode | Prod | Quantile | Range | Sd | Skewness | Sum | Var | Wmean => { return true },
+		_ => { return false }
 	}
 }
 
-fn do_num(op: MathOp, f: f64) -> WordResult {
+/* The value a reduction gives when it cannot skip missing data. */
+fn missing(v: &Array1<f64>) -> Option<f64> {
+	if v.iter().any(|f| is_na(*f)) { return Some(na()) }
+	return None
+}
+
+fn do_num(op: MathOp, f: f64, na_rm: bool) -> WordResult {
+	if f.is_nan() && is_reduction(&op) { return do_vect(op, &Array1::from_elem(1, f), na_rm) }
 	match op {
 		Abs 	=> { return Ok(Word::Num(f.abs())) },
 	    Acos 	=> { return Ok(Word::Num(f.acos())) },
@@ -38,6 +68,7 @@ fn do_num(op: MathOp, f: f64) -> WordResult {
 	    Ceil 	=> { return Ok(Word::Num(f.ceil())) },
 	    Cos 	=> { return Ok(Word::Num(f.cos())) },
 	    Cosh 	=> { return Ok(Word::Num(f.cosh())) },
+	    Cummax | Cummin | Cumprod | Cumsum 	=> { return Ok(Word::Num(f)) },
 	    Exp 	=> { return Ok(Word::Num(f.exp())) },
 	    Floor 	=> { return Ok(Word::Num(f.floor())) },
 	    Fract 	=> { return Ok(Word::Num(f.fract())) },
@@ -57,12 +88,20 @@ fn do_num(op: MathOp, f: f64) -> WordResult {
 	    Sqrt 	=> { return Ok(Word::Num(f.sqrt())) },
 	    Tan 	=> { return Ok(Word::Num(f.tan())) },
 	    Tanh 	=> { return Ok(Word::Num(f.tanh())) },
-	    Trunc 	=> { return Ok(Word::Int(f.trunc() as i32)) },
-	    Var 	=> { return Ok(Word::Num(0.0)) }
+	    Trunc 	=> { if f.is_nan() { return Ok(num_word(f)) } else { return Ok(Word::Int(f.trunc() as i32)) } },
+	    Var 	=> { return Ok(Word::Num(0.0)) },
+	    _ 		=> { return do_vect(op, &Array1::from_elem(1, f), na_rm) }
 	}
 }
 
-fn do_vect(op: MathOp, v: &Array1<f64>) -> WordResult {
+fn do_vect(op: MathOp, v: &Array1<f64>, na_rm: bool) -> WordResult {
+	if is_reduction(&op) && !na_rm {
+		match (missing(v), &op) {
+			(Some(m), Range) 	=> { return Ok(Word::Vect(Box::new(Array1::from_elem(2, m)))) },
+			(Some(m), _) 		=> { return Ok(Word::Num(m)) },
+			_ => { }
+		}
+	}
 	match op {
 		Abs 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.abs())))) },
 	    Acos 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.acos())))) },
@@ -75,30 +114,210 @@ fn do_vect(op: MathOp, v: &Array1<f64>) -> WordResult {
 	    Ceil 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.ceil())))) },
 	    Cos 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.cos())))) },
 	    Cosh 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.cosh())))) },
+	    Cummax 	=> { return Ok(Word::Vect(Box::new(cumulate(v, f64::max)))) },
+	    Cummin 	=> { return Ok(Word::Vect(Box::new(cumulate(v, f64::min)))) },
+	    Cumprod => { return Ok(Word::Vect(Box::new(cumulate(v, |x, y| x * y)))) },
+	    Cumsum 	=> { return Ok(Word::Vect(Box::new(cumulate(v, |x, y| x + y)))) },
 	    Exp 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.exp())))) },
 	    Floor 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.floor())))) },
 	    Fract 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.fract())))) },
+	    Iqr 	=> {
+	    	let s = sorted_vec(v);
+	    	return Ok(Word::Num(quantile_sorted(&s, 0.75) - quantile_sorted(&s, 0.25)))
+	    },
+	    Kurtosis => { return Ok(Word::Num(moment_ratio(v, 4) - 3.0)) },
 	    Ln 		=> { return Ok(Word::Vect(Box::new(v.map(|f| f.ln())))) },
 	    Log10 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.log10())))) },
 	    Log2 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.log2())))) },
+	    Mad 	=> {
+	    	let s = sorted_vec(v);
+	    	let m = quantile_sorted(&s, 0.5);
+	    	return Ok(Word::Num(1.4826 * quantile_sorted(&sorted_vec(&s.mapv(|x| (x - m).abs())), 0.5)))
+	    },
 	    Max 	=> { return Ok(Word::Num(*v.max_skipnan())) },
 	    Mean 	=> { return Ok(Word::Num( mean_vec(v) )) },
+	    Median 	=> { return Ok(Word::Num(quantile_sorted(&sorted_vec(v), 0.5))) },
 	    Min 	=> { return Ok(Word::Num(*v.min_skipnan())) },
+	    Mode 	=> { return Ok(Word::Num(mode_vec(v))) },
 	    Neg 	=> { return Ok(Word::Vect(Box::new(v.map(|f| -f)))) },
+	    Prod 	=> { return Ok(Word::Num(v.iter().filter(|f| !f.is_nan()).product())) },
+	    Range 	=> {
+	    	let s = sorted_vec(v);
+	    	let (lo, hi) = if s.len() == 0 { (NAN, NAN) } else { (s[0], s[s.len() - 1]) };
+	    	return Ok(Word::Vect(Box::new(Array1::from_vec(vec![lo, hi]))))
+	    },
 	    Recip 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.recip())))) },
 	    Round0 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.round())))) },
 	    Sd 		=> { return Ok(Word::Num( var_vec(v).sqrt() )) },
 	    Sign 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.signum())))) },
 	    Sin 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.sin())))) },
 	    Sinh 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.sinh())))) },
+	    Skewness => { return Ok(Word::Num(moment_ratio(v, 3))) },
 	    Sqrt 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.sqrt())))) },
+	    Sum 	=> { return Ok(Word::Num(v.iter().filter(|f| !f.is_nan()).fold(0.0, |a, b| a + b))) },
+	    Summary => { return Ok(summary(v)) },
 	    Tan 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.tan())))) },
 	    Tanh 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.tanh())))) },
 	    Trunc 	=> { return Ok(Word::Vect(Box::new(v.map(|f| f.trunc())))) },
 	    Var 	=> { return Ok(Word::Num( var_vec(v) )) },
+	    _ 		=> { return error(ErrorType::Type, format!("Operation '{}' requires two arguments.", sprint_mathop(&op))) }
+	}
+}
+
+/* 'quantile' takes the data and a probability (or a vector of them); 'wmean' takes the data
+ * and a vector of weights. The data is below 'arg' on the stack. */
+fn do_binary(vm: &mut Vm, op: MathOp, arg: &Word, na_rm: bool) -> WordResult {
+	let data: Word;
+	match vm.stack.pop() {
+		Some(w) => { data = w },
+		None 	=> { return error(ErrorType::Underflow, format!("'{}' requires 2 objects on top of the stack. Only 1 found.", sprint_mathop(&op))) }
+	}
+	let v = to_vect(vm, &data, &op)?;
+	match op {
+		Quantile 	=> {
+			let s = match missing(&v) {
+				Some(m) if !na_rm 	=> { Array1::from_elem(1, m) },
+				_ => { sorted_vec(&v) }
+			};
+			let check = |p: f64| if p >= 0.0 && p <= 1.0 { Ok(quantile_sorted(&s, p)) } else {
+				error(ErrorType::Domain, format!("Probabilities must be between 0 and 1. Given: {}.", p))
+			};
+			match arg {
+				Word::Int(_) | Word::Num(_) 	=> { return Ok(Word::Num(check(toF64(arg))?)) },
+				_ => {
+					let ps = to_vect(vm, arg, &op)?;
+					let mut qs = Vec::with_capacity(ps.len());
+					for p in ps.iter() { qs.push(check(*p)?) }
+					return Ok(Word::Vect(Box::new(Array1::from_vec(qs))))
+				}
+			}
+		},
+		_ 	=> {
+			let w = to_vect(vm, arg, &op)?;
+			if w.len() != v.len() {
+				return error(ErrorType::Index, format!("'wmean' requires as many weights as values. Given: {} and {}.", w.len(), v.len()))
+			}
+			if !na_rm {
+				if let Some(m) = missing(&v).or(missing(&w)) { return Ok(Word::Num(m)) }
+			}
+			let (mut num, mut den) = (0.0, 0.0);
+			for (x, w) in v.iter().zip(w.iter()) {
+				if x.is_nan() || w.is_nan() { continue }
+				num += x * w;
+				den += w;
+			}
+			return Ok(Word::Num(num / den))
+		}
+	}
+}
+
+/* 	== CORRELATION == */
+
+enum CorMethod { Pearson, Spearman, Kendall }
+
+/* 'x y cor' for two vectors or 'm cor' for the columns of a matrix, optionally followed by the
+ * method name. Each pair of variables uses its complete observations. */
+fn do_cor(vm: &mut Vm, op: MathOp, top: &Word) -> WordResult {
+	let (method, arg) = match top {
+		Word::Str(s) 	=> {
+			let method = match s.as_str() {
+				"pearson" 	=> { CorMethod::Pearson },
+				"spearman" 	=> { CorMethod::Spearman },
+				"kendall" 	=> { CorMethod::Kendall },
+				_ => { return error(ErrorType::Domain, format!("Method must be \"pearson\", \"spearman\" or \"kendall\". Given: \"{}\".", s)) }
+			};
+			(method, pop(vm, &op)?)
+		},
+		_ => { (CorMethod::Pearson, top.clone()) }
+	};
+	let cov = op == Cov;
+	match arg {
+		Word::Mat(m) 	=> {
+			let p = m.ncols();
+			let mut out = Array2::<f64>::zeros((p, p));
+			for i in 0 .. p {
+				for j in i .. p {
+					let v = pairwise(&m.column(i).to_owned(), &m.column(j).to_owned(), &method, cov);
+					out[[i, j]] = v;
+					out[[j, i]] = v;
+				}
+			}
+			return Ok(Word::Mat(Box::new(out)))
+		},
+		_ => {
+			let y = to_vect(vm, &arg, &op)?;
+			let x = pop(vm, &op)?;
+			let x = to_vect(vm, &x, &op)?;
+			if x.len() != y.len() {
+				return error(ErrorType::Index, format!("'{}' requires vectors of the same length. Given: {} and {}.", sprint_mathop(&op), x.len(), y.len()))
+			}
+			return Ok(Word::Num(pairwise(&x, &y, &method, cov)))
+		}
+	}
+}
+
+fn pairwise(x: &Array1<f64>, y: &Array1<f64>, method: &CorMethod, cov: bool) -> f64 {
+	let (x, y): (Vec<f64>, Vec<f64>) = x.iter().zip(y.iter()).filter(|(a, b)| !a.is_nan() && !b.is_nan()).map(|(a, b)| (*a, *b)).unzip();
+	let n = x.len();
+	if n < 2 { return NAN }
+	match method {
+		CorMethod::Kendall 	=> {
+			let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+			for i in 0 .. n {
+				for j in i + 1 .. n {
+					let (a, b) = (sign(x[i] - x[j]), sign(y[i] - y[j]));
+					sxy += a * b;
+					sxx += a * a;
+					syy += b * b;
+				}
+			}
+			if cov { return 2.0 * sxy }
+			return sxy / (sxx * syy).sqrt()
+		},
+		CorMethod::Spearman => { return moments(&rank_avg(&x).0, &rank_avg(&y).0, cov) },
+		CorMethod::Pearson 	=> { return moments(&x, &y, cov) }
 	}
 }
 
+fn moments(x: &[f64], y: &[f64], cov: bool) -> f64 {
+	let n = x.len() as f64;
+	let mx = x.iter().sum::<f64>() / n;
+	let my = y.iter().sum::<f64>() / n;
+	let sxy: f64 = x.iter().zip(y.iter()).map(|(a, b)| (a - mx) * (b - my)).sum();
+	if cov { return sxy / (n - 1.0) }
+	let sxx: f64 = x.iter().map(|a| (a - mx).powi(2)).sum();
+	let syy: f64 = y.iter().map(|b| (b - my).powi(2)).sum();
+	return sxy / (sxx * syy).sqrt()
+}
+
+fn sign(f: f64) -> f64 {
+	if f > 0.0 { return 1.0 }
+	if f < 0.0 { return -1.0 }
+	return 0.0
+}
+
+fn pop(vm: &mut Vm, op: &MathOp) -> WordResult {
+	match vm.stack.pop() {
+		Some(w) => { return Ok(w) },
+		None 	=> { return error(ErrorType::Underflow, format!("'{}' requires more objects on top of the stack.", sprint_mathop(op))) }
+	}
+}
+
+fn to_vect(vm: &mut Vm, w: &Word, op: &MathOp) -> Result<Array1<f64>, StaxError> {
+	match w {
+		Word::Int(_) | Word::Num(_) 	=> { return Ok(Array1::from_elem(1, toF64(w))) },
+		Word::List(l) 		=> { return Ok(list2array1(l)) },
+		Word::Quote(q) 		=> { return Ok(list2array1(&vm.run_newstack(q.to_vec())?)) },
+		Word::Vect(v) 		=> { return Ok(*v.clone()) },
+		Word::BoolVect(b) 	=> { return Ok(bool2array1(b)) },
+		_ => { return error(ErrorType::Type, format!("Operation '{}' cannot be completed on objects of type '{}'.", sprint_mathop(op), w.sprint_type())) }
+	}
+}
+
+fn bool2array1(b: &Array1<Option<bool>>) -> Array1<f64> {
+	return b.mapv(|x| match x { Some(x) => if x { 1.0 } else { 0.0 }, None => na() })
+}
+
 pub fn mean_vec(v: &Array1<f64>) -> f64 {
 	let n = v.dim();
 	if n == 0 { return NAN } 
@@ -126,3 +345,122 @@ pub fn var_vec(v: &Array1<f64>) -> f64 {
 }
 
 
+
+
+/* 	== DESCRIPTIVE STATISTICS == */
+
+/* The non-NaN values of 'v' in increasing order. */
+fn sorted_vec(v: &Array1<f64>) -> Array1<f64> {
+	let mut s: Vec<f64> = v.iter().cloned().filter(|f| !f.is_nan()).collect();
+	s.sort_by(|a, b| a.partial_cmp(b).unwrap());
+	return Array1::from_vec(s)
+}
+
+/* Quantile of sorted data by linear interpolation between order statistics (R's type 7). */
+pub fn quantile_sorted(s: &Array1<f64>, p: f64) -> f64 {
+	let n = s.len();
+	if n == 0 { return NAN }
+	let h = (n - 1) as f64 * p;
+	let lo = h.floor() as usize;
+	if lo + 1 >= n { return s[n - 1] }
+	return s[lo] + (h - lo as f64) * (s[lo + 1] - s[lo])
+}
+
+/* Most frequent value. Ties go to the smallest value. */
+fn mode_vec(v: &Array1<f64>) -> f64 {
+	let s = sorted_vec(v);
+	let (mut best, mut best_n, mut run) = (NAN, 0, 0);
+	for i in 0 .. s.len() {
+		run = if i > 0 && s[i] == s[i - 1] { run + 1 } else { 1 };
+		if run > best_n { best = s[i]; best_n = run }
+	}
+	return best
+}
+
+/* Standardised central moment 'm_k / m_2^(k/2)', as in the moment-based skewness and kurtosis. */
+fn moment_ratio(v: &Array1<f64>, k: i32) -> f64 {
+	let s = sorted_vec(v);
+	let n = s.len() as f64;
+	if n == 0.0 { return NAN }
+	let mean = s.sum() / n;
+	let m2 = s.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+	let mk = s.iter().map(|x| (x - mean).powi(k)).sum::<f64>() / n;
+	return mk / m2.powf(k as f64 / 2.0)
+}
+
+/* Running reduction with 'f'. NA and NaN entries are kept and skipped by the accumulator. */
+fn cumulate<F>(v: &Array1<f64>, f: F) -> Array1<f64>
+	where F: Fn(f64, f64) -> f64
+{
+	let mut acc: Option<f64> = None;
+	return v.map(|x| {
+		if x.is_nan() { return *x }
+		let next = match acc { Some(a) => f(a, *x), None => *x };
+		acc = Some(next);
+		next
+	})
+}
+
+fn summary(v: &Array1<f64>) -> Word {
+	let s = sorted_vec(v);
+	let mut dict: HashMap<String, Word> = HashMap::new();
+	dict.insert("min".to_string(), Word::Num(quantile_sorted(&s, 0.0)));
+	dict.insert("q1".to_string(), Word::Num(quantile_sorted(&s, 0.25)));
+	dict.insert("median".to_string(), Word::Num(quantile_sorted(&s, 0.5)));
+	dict.insert("mean".to_string(), Word::Num(mean_vec(&s)));
+	dict.insert("q3".to_string(), Word::Num(quantile_sorted(&s, 0.75)));
+	dict.insert("max".to_string(), Word::Num(quantile_sorted(&s, 1.0)));
+	let nas = v.iter().filter(|f| is_na(**f)).count();
+	dict.insert("n".to_string(), Word::Int(s.len() as i32));
+	dict.insert("na".to_string(), Word::Int(nas as i32));
+	dict.insert("nan".to_string(), Word::Int((v.len() - s.len() - nas) as i32));
+	return Word::Dict(Box::new(dict))
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn close(a: f64, b: f64) -> bool {
+		return (a - b).abs() < 1e-12
+	}
+
+	#[test]
+	fn quantile_type7() {
+		let s = Array1::from_vec((1 ..= 10).map(|i| i as f64).collect());
+		assert!(close(quantile_sorted(&s, 0.25), 3.25));
+		assert!(close(quantile_sorted(&s, 0.5), 5.5));
+		assert!(close(quantile_sorted(&s, 0.9), 9.1));
+		assert!(close(quantile_sorted(&s, 0.0), 1.0));
+		assert!(close(quantile_sorted(&s, 1.0), 10.0));
+		let s = Array1::from_vec(vec![1.0, 3.0, 7.0, 15.0]);
+		assert!(close(quantile_sorted(&s, 0.5), 5.0));
+		assert!(close(quantile_sorted(&s, 0.75), 9.0));
+		assert!(quantile_sorted(&Array1::from_vec(vec![]), 0.5).is_nan());
+	}
+
+	#[test]
+	fn correlation() {
+		use crate::vm::tests::run;
+		let num = |src: &str| match run(&format!("x = [1 2 3 4 5]; y = [2 4 5 4 5]; {}", src)).unwrap().pop() {
+			Some(Word::Num(f)) 	=> f,
+			w => panic!("{}: {:?}", src, w)
+		};
+		assert!(close(num("x y cor"), 6.0 / 60f64.sqrt()));
+		assert!(close(num("x y cov"), 1.5));
+		assert!(close(num("x y \"spearman\" cor"), 7.0 / 90f64.sqrt()));
+		assert!(close(num("x y \"kendall\" cor"), 6.0 / 80f64.sqrt()));
+		/* Incomplete pairs are dropped. */
+		assert!(close(num("[1 2 3 NA] [1 2 4 10] cor"), 3.0 / (2.0f64 * 42.0 / 9.0).sqrt()));
+		match run("[1 2 3 4 5] [2 4 5 4 5] cbind cor").unwrap().pop() {
+			Some(Word::Mat(m)) 	=> {
+				assert!(close(m[[0, 0]], 1.0) && close(m[[1, 1]], 1.0));
+				assert!(close(m[[0, 1]], 6.0 / 60f64.sqrt()) && m[[0, 1]] == m[[1, 0]]);
+			},
+			w => { panic!("{:?}", w) }
+		}
+		assert_eq!(run("[1 2 3] [1 2] cor").unwrap_err().kind, ErrorType::Index);
+		assert_eq!(run("[1 2 3] [1 2 3] \"tau\" cor").unwrap_err().kind, ErrorType::Domain);
+	}
+}
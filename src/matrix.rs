@@ -0,0 +1,219 @@
+/* matrix.rs
+ * --------- */
+use crate::code::{MatOp, sprint_matop};
+use crate::code::MatOp::*;
+use crate::error::{ErrorType, StaxError, error};
+use crate::math::{mean_vec, var_vec};
+use crate::vm::Vm;
+use crate::words::{BoolResult, Word, WordResult, list2array1, tighten};
+
+use ndarray::{Array1, Array2, ArrayView2, Axis, concatenate};
+
+pub fn do_matrix(vm: &mut Vm, op: MatOp) -> BoolResult {
+	let nargs = match op {
+		Reshape => 3,
+		Cbind | Col | Ones | Rbind | Row | Zeros => 2,
+		_ => 1
+	};
+	if vm.stack.len() < nargs {
+		return error(ErrorType::Underflow, format!("'{}' requires {} objects on top of the stack. Only {} found.", sprint_matop(&op), nargs, vm.stack.len()))
+	}
+	let mut args = vm.stack.split_off(vm.stack.len() - nargs);
+	let result: Word;
+	match op {
+		Cbind 	=> { result = bind(Axis(1), to_mat(vm, &args[0], false, &op)?.view(), to_mat(vm, &args[1], false, &op)?.view())? },
+		Rbind 	=> { result = bind(Axis(0), to_mat(vm, &args[0], true, &op)?.view(), to_mat(vm, &args[1], true, &op)?.view())? },
+		Col 	=> { result = lane(to_mat(vm, &args[0], false, &op)?, Axis(1), &args[1], &op)? },
+		Row 	=> { result = lane(to_mat(vm, &args[0], true, &op)?, Axis(0), &args[1], &op)? },
+		Ones 	=> { result = Word::Mat(Box::new(Array2::ones((count(&args[0], &op)?, count(&args[1], &op)?)))) },
+		Zeros 	=> { result = Word::Mat(Box::new(Array2::zeros((count(&args[0], &op)?, count(&args[1], &op)?)))) },
+		Reshape => {
+			let (r, c) = (count(&args[1], &op)?, count(&args[2], &op)?);
+			let data: Vec<f64> = to_mat(vm, &args[0], true, &op)?.iter().cloned().collect();
+			if data.len() != r * c {
+				return error(ErrorType::Index, format!("{} elements cannot be reshaped into a {}x{} matrix.", data.len(), r, c))
+			}
+			result = Word::Mat(Box::new(Array2::from_shape_vec((r, c), data).unwrap()))
+		},
+		_ => { result = do_unary(vm, args.pop().unwrap(), &op)? }
+	}
+	return vm.push_const(result)
+}
+
+fn do_unary(vm: &mut Vm, w: Word, op: &MatOp) -> WordResult {
+	match op {
+		Diag 	=> match flatten(vm, w)? {
+			Word::Vect(v) 	=> { return Ok(Word::Mat(Box::new(Array2::from_diag(&*v)))) },
+			Word::Mat(m) 	=> { return Ok(Word::Vect(Box::new(m.diag().to_owned()))) },
+			Word::List(l) 	=> { return Ok(Word::Vect(Box::new(from_rows(vm, l.to_vec(), op)?.diag().to_owned()))) },
+			w @ Word::Int(_) | w @ Word::Num(_) 	=> { return Ok(Word::Mat(Box::new(Array2::eye(count(&w, op)?)))) },
+			w => { return type_error(&w, op) }
+		},
+		Dim 	=> match w {
+			Word::Mat(m) 	=> { return Ok(Word::Vect(Box::new(Array1::from_vec(vec![m.nrows() as f64, m.ncols() as f64])))) },
+			Word::Vect(v) 	=> { return Ok(Word::Vect(Box::new(Array1::from_vec(vec![v.len() as f64])))) },
+			_ => { return type_error(&w, op) }
+		},
+		Eye 	=> { return Ok(Word::Mat(Box::new(Array2::eye(count(&w, op)?)))) },
+		Mat 	=> { return Ok(Word::Mat(Box::new(to_mat(vm, &w, true, op)?))) },
+		Colmeans | Colsds | Colsums | Rowmeans | Rowsds | Rowsums => {
+			let m = to_mat(vm, &w, true, op)?;
+			let axis = match op { Rowmeans | Rowsds | Rowsums => Axis(0), _ => Axis(1) };
+			let reduce = |v: &Array1<f64>| match op {
+				Colmeans | Rowmeans => { mean_vec(v) },
+				Colsds | Rowsds 	=> { var_vec(v).sqrt() },
+				_ 	=> { v.sum() }
+			};
+			return Ok(Word::Vect(Box::new(m.axis_iter(axis).map(|l| reduce(&l.to_owned())).collect())))
+		},
+		_ => { return error(ErrorType::Type, format!("Matrix operation '{}' not recognized!", sprint_matop(op))) }
+	}
+}
+
+/* Joins two matrices along 'axis': rows for 'rbind', columns for 'cbind'. */
+pub fn bind<'a>(axis: Axis, lhs: ArrayView2<'a, f64>, rhs: ArrayView2<'a, f64>) -> WordResult {
+	let other = if axis == Axis(0) { Axis(1) } else { Axis(0) };
+	if lhs.len_of(other) != rhs.len_of(other) {
+		return error(ErrorType::Index, format!("Matrices of shape {:?} and {:?} cannot be joined along axis {}.", lhs.shape(), rhs.shape(), axis.index()))
+	}
+	match concatenate(axis, &[lhs, rhs]) {
+		Ok(m) 	=> { return Ok(Word::Mat(Box::new(m))) },
+		Err(e) 	=> { return error(ErrorType::Index, format!("Matrices could not be joined. {}", e)) }
+	}
+}
+
+
+/* 	== HELPERS == */
+
+/* Converts 'w' to a matrix. A vector becomes a single row, or a single column if 'row' is false,
+ * and so does a list (or quote) of numbers. A list of vectors, lists or quotes of numbers gives
+ * one row per element. */
+fn to_mat(vm: &mut Vm, w: &Word, row: bool, op: &MatOp) -> Result<Array2<f64>, StaxError> {
+	match w {
+		Word::Mat(m) 	=> { return Ok(*m.clone()) },
+		Word::Vect(v) 	=> {
+			if row { return Ok(v.to_owned().insert_axis(Axis(0))) }
+			return Ok(v.to_owned().insert_axis(Axis(1)))
+		},
+		Word::List(_) | Word::Quote(_) 	=> match flatten(vm, w.clone())? {
+			Word::List(l) 	=> { return from_rows(vm, l.to_vec(), op) },
+			Word::Vect(v) 	=> { return to_mat(vm, &Word::Vect(v), row, op) },
+			x => { return type_error(&x, op).map(|_| Array2::zeros((0, 0))) }
+		},
+		_ => { return type_error(w, op).map(|_| Array2::zeros((0, 0))) }
+	}
+}
+
+/* Evaluates a quote, and turns a list of numbers into a vector, as 'tighten' does. */
+fn flatten(vm: &mut Vm, w: Word) -> WordResult {
+	match w {
+		Word::List(l) 	=> { return Ok(tighten(*l)) },
+		Word::Quote(q) 	=> { return Ok(tighten(vm.run_newstack(q.to_vec())?)) },
+		_ => { return Ok(w) }
+	}
+}
+
+fn from_rows(vm: &mut Vm, rows: Vec<Word>, op: &MatOp) -> Result<Array2<f64>, StaxError> {
+	let mut data = Vec::new();
+	let mut ncols = 0;
+	for (i, w) in rows.iter().enumerate() {
+		let r: Array1<f64> = match w {
+			Word::Vect(v) 	=> { *v.clone() },
+			Word::List(l) 	=> { list2array1(l) },
+			Word::Quote(q) 	=> { list2array1(&vm.run_newstack(q.to_vec())?) },
+			_ => { return error(ErrorType::Type, format!("'{}' requires rows of numbers. Row {} has type '{}'.", sprint_matop(op), i, w.sprint_type())) }
+		};
+		if i == 0 { ncols = r.len() }
+		if r.len() != ncols {
+			return error(ErrorType::Index, format!("All rows must have the same length. Row 0 has {} elements and row {} has {}.", ncols, i, r.len()))
+		}
+		data.extend(r.iter());
+	}
+	return Ok(Array2::from_shape_vec((rows.len(), ncols), data).unwrap())
+}
+
+/* Row or column 'i' of 'm'. Indices start at 0 and negative ones count from the end. */
+fn lane(m: Array2<f64>, axis: Axis, i: &Word, op: &MatOp) -> WordResult {
+	let n = m.len_of(axis) as i64;
+	let idx: i64;
+	match i {
+		Word::Int(x) 	=> { idx = if *x < 0 { n + *x as i64 } else { *x as i64 } },
+		_ => { return type_error(i, op) }
+	}
+	if idx < 0 || idx >= n {
+		return error(ErrorType::Index, format!("Index {} is out of bounds for a matrix with {} {}.", i.sprint(), n, if axis == Axis(0) { "rows" } else { "columns" }))
+	}
+	return Ok(Word::Vect(Box::new(m.index_axis(axis, idx as usize).to_owned())))
+}
+
+fn count(w: &Word, op: &MatOp) -> Result<usize, StaxError> {
+	match w {
+		Word::Int(i) if *i >= 0 	=> { return Ok(*i as usize) },
+		Word::Num(f) if *f >= 0.0 	=> { return Ok(*f as usize) },
+		Word::Int(_) | Word::Num(_) => { return error(ErrorType::Domain, format!("'{}' requires a non-negative size. Given: '{}'.", sprint_matop(op), w.sprint())) },
+		_ => { return type_error(w, op).map(|_| 0) }
+	}
+}
+
+fn type_error(w: &Word, op: &MatOp) -> WordResult {
+	return error(ErrorType::Type, format!("'{}' cannot be completed on objects of type '{}'.", sprint_matop(op), w.sprint_type()))
+}
+
+
+#[cfg(test)]
+mod tests {
+	use crate::compare::equal_words;
+	use crate::error::ErrorType;
+	use crate::vm::tests::run;
+	use crate::words::Word;
+	use ndarray::array;
+
+	const M: &str = "m = [[1 2] [3 4]] mat;";
+
+	fn check(src: &str, expected: Word) {
+		let top = run(&format!("{} {}", M, src)).unwrap().pop().unwrap();
+		assert!(equal_words(&top, &expected), "{}: {:?}", src, top);
+	}
+
+	fn mat(m: ndarray::Array2<f64>) -> Word { return Word::Mat(Box::new(m)) }
+
+	fn vect(v: ndarray::Array1<f64>) -> Word { return Word::Vect(Box::new(v)) }
+
+	#[test]
+	fn construct() {
+		check("m", mat(array![[1.0, 2.0], [3.0, 4.0]]));
+		check("2 3 zeros dim", vect(array![2.0, 3.0]));
+		check("2 2 ones", mat(array![[1.0, 1.0], [1.0, 1.0]]));
+		check("2 eye", mat(array![[1.0, 0.0], [0.0, 1.0]]));
+		check("[1 2] diag", mat(array![[1.0, 0.0], [0.0, 2.0]]));
+		check("m diag", vect(array![1.0, 4.0]));
+		check("[1 2 3 4 5 6] 2 3 reshape", mat(array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]));
+		check("[1 2 3] mat", mat(array![[1.0, 2.0, 3.0]]));
+	}
+
+	#[test]
+	fn bind_and_extract() {
+		check("m [5 6] rbind", mat(array![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]));
+		check("[1 2] [3 4] cbind", mat(array![[1.0, 3.0], [2.0, 4.0]]));
+		check("m 1 row", vect(array![3.0, 4.0]));
+		check("m -1 col", vect(array![2.0, 4.0]));
+	}
+
+	#[test]
+	fn reductions() {
+		check("m colsums", vect(array![4.0, 6.0]));
+		check("m rowmeans", vect(array![1.5, 3.5]));
+		check("m colsds", vect(array![2.0f64.sqrt(), 2.0f64.sqrt()]));
+	}
+
+	#[test]
+	fn errors() {
+		let kind = |src: &str| run(&format!("{} {}", M, src)).unwrap_err().kind;
+		assert_eq!(kind("[1 2 3] 2 2 reshape"), ErrorType::Index);
+		assert_eq!(kind("[[1 2] [3]] mat"), ErrorType::Index);
+		assert_eq!(kind("m 2 row"), ErrorType::Index);
+		assert_eq!(kind("m [1 2 3] rbind"), ErrorType::Index);
+		assert_eq!(kind("-1 2 zeros"), ErrorType::Domain);
+		assert_eq!(kind("\"a\" eye"), ErrorType::Type);
+	}
+}
@@ -0,0 +1,212 @@
+/* na.rs
+ * ----- */
+use crate::code::{NaOp, sprint_naop};
+use crate::code::NaOp::*;
+use crate::error::{ErrorType, StaxError, error};
+use crate::frame::Frame;
+use crate::vm::Vm;
+use crate::words::{BoolResult, Word, WordResult, tighten, toF64};
+
+use ndarray::Array1;
+use std::mem;
+
+/* Missing values. Numbers carry NA in-band as R does, as a NaN whose low word is 1954, so
+ * vector arithmetic keeps it without extra work while an ordinary NaN stays a NaN. Bool and
+ * string vectors have no spare value and hold 'None' instead. A lone NA is 'Word::Na'. */
+const NA_BITS: u64 = 0x7FF80000000007A2;
+
+pub fn na() -> f64 {
+	return f64::from_bits(NA_BITS)
+}
+
+pub fn is_na(f: f64) -> bool {
+	return f.is_nan() && f.to_bits() as u32 == 1954
+}
+
+pub fn do_na(vm: &mut Vm, op: NaOp) -> BoolResult {
+	if op == NaRm { return do_na_rm(vm) }
+	let nargs = match op { FillNa => 2, _ => 1 };
+	if vm.stack.len() < nargs {
+		return error(ErrorType::Underflow, format!("'{}' requires {} objects on top of the stack. Only {} found.", sprint_naop(&op), nargs, vm.stack.len()))
+	}
+	let args = vm.stack.split_off(vm.stack.len() - nargs);
+	let mut args: Vec<Word> = args.into_iter().map(|w| match w {
+		Word::Quote(q) 	=> { Ok(tighten(vm.run_newstack(q.to_vec())?)) },
+		_ => { Ok(w) }
+	}).collect::<Result<Vec<Word>, StaxError>>()?;
+	let result: Word;
+	match op {
+		DropNa 	=> match &args[0] {
+			Word::Frame(f) 	=> {
+				let masks = f.columns.iter().map(|c| na_mask(c).unwrap_or(vec![])).collect::<Vec<Vec<bool>>>();
+				let keep: Vec<usize> = (0 .. f.nrow()).filter(|i| !masks.iter().any(|m| m[*i])).collect();
+				result = Word::Frame(Box::new(f.rows(&keep)))
+			},
+			w => match na_mask(w) {
+				Some(mask) 	=> {
+					let keep: Vec<usize> = (0 .. mask.len()).filter(|i| !mask[*i]).collect();
+					result = crate::frame::take_rows(w, &keep)
+				},
+				None 	=> { return type_error(w, &op) }
+			}
+		},
+		FillNa 	=> {
+			let value = args.pop().unwrap();
+			match &args[0] {
+				Word::Frame(f) 	=> {
+					let columns = f.columns.iter().map(|c| fill(c, &value).unwrap_or(c.clone())).collect();
+					result = Word::Frame(Box::new(Frame::new(f.names.clone(), columns)?))
+				},
+				Word::Na 	=> { result = value },
+				w => match fill(w, &value) {
+					Some(filled) 	=> { result = filled },
+					None 	=> {
+						if na_mask(w).is_none() { return type_error(w, &op) }
+						return error(ErrorType::Type, format!("'fill_na' cannot fill objects of type '{}' with '{}'.", w.sprint_type(), value.sprint_type()))
+					}
+				}
+			}
+		},
+		IsNa 	=> match &args[0] {
+			Word::Frame(f) 	=> {
+				let columns = f.columns.iter().map(|c| mask_word(na_mask(c).unwrap_or(vec![]))).collect();
+				result = Word::Frame(Box::new(Frame::new(f.names.clone(), columns)?))
+			},
+			Word::Na 	=> { result = Word::Bool(true) },
+			Word::Num(x) 	=> { result = Word::Bool(is_na(*x)) },
+			Word::Bool(_) | Word::Int(_) | Word::Str(_) 	=> { result = Word::Bool(false) },
+			w => match na_mask(w) {
+				Some(mask) 	=> { result = mask_word(mask) },
+				None 	=> { return type_error(w, &op) }
+			}
+		},
+		NaRm 	=> { unreachable!() }
+	}
+	return vm.push_const(result)
+}
+
+/* '[fun] na_rm' runs 'fun' with the reductions in it skipping NA, as R's 'na.rm = TRUE'. */
+fn do_na_rm(vm: &mut Vm) -> BoolResult {
+	let fun = match vm.stack.pop() {
+		Some(w) => { w },
+		None 	=> { return error(ErrorType::Underflow, format!("'na_rm' requires 1 object on top of the stack. None found.")) }
+	};
+	let na_rm = mem::replace(&mut vm.na_rm, true);
+	let result = vm.exec_word(fun);
+	vm.na_rm = na_rm;
+	return result
+}
+
+
+/* 	== HELPERS == */
+
+/* Which elements of a vector or list are missing. */
+pub fn na_mask(w: &Word) -> Option<Vec<bool>> {
+	match w {
+		Word::BoolVect(v) 	=> { return Some(v.iter().map(|x| x.is_none()).collect()) },
+		Word::Factor(f) 	=> { return Some(f.codes.iter().map(|x| x.is_none()).collect()) },
+		Word::List(l) 		=> { return Some(l.iter().map(|x| match x { Word::Na => true, Word::Num(f) => is_na(*f), _ => false }).collect()) },
+		Word::StrVect(v) 	=> { return Some(v.iter().map(|x| x.is_none()).collect()) },
+		Word::Vect(v) 		=> { return Some(v.iter().map(|x| is_na(*x)).collect()) },
+		_ => { return None }
+	}
+}
+
+/* Replaces the NA elements of 'w' with 'value', when it has the element type of 'w'. */
+fn fill(w: &Word, value: &Word) -> Option<Word> {
+	match (w, value) {
+		(Word::BoolVect(v), Word::Bool(b)) 	=> { return Some(Word::BoolVect(Box::new(v.mapv(|x| x.or(Some(*b)))))) },
+		(Word::Factor(f), Word::Str(s)) 	=> {
+			let code = f.levels.iter().position(|l| l == &**s)?;
+			let mut f = f.clone();
+			f.codes.iter_mut().for_each(|c| if c.is_none() { *c = Some(code) });
+			return Some(Word::Factor(f))
+		},
+		(Word::List(l), _) 	=> {
+			return Some(Word::List(Box::new(l.iter().map(|x| match x {
+				Word::Na 	=> { value.clone() },
+				Word::Num(f) if is_na(*f) 	=> { value.clone() },
+				_ => { x.clone() }
+			}).collect())))
+		},
+		(Word::StrVect(v), Word::Str(s)) 	=> { return Some(Word::StrVect(Box::new(v.map(|x| x.clone().or(Some(*s.clone())))))) },
+		(Word::Vect(v), Word::Int(_)) | (Word::Vect(v), Word::Num(_)) 	=> {
+			let f = toF64(value);
+			return Some(Word::Vect(Box::new(v.mapv(|x| if is_na(x) { f } else { x }))))
+		},
+		_ => { return None }
+	}
+}
+
+fn mask_word(mask: Vec<bool>) -> Word {
+	return Word::BoolVect(Box::new(mask.into_iter().map(|b| Some(b)).collect::<Array1<Option<bool>>>()))
+}
+
+fn type_error(w: &Word, op: &NaOp) -> BoolResult {
+	return error(ErrorType::Type, format!("'{}' cannot be completed on objects of type '{}'.", sprint_naop(op), w.sprint_type()))
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compare::equal_words;
+	use crate::vm::tests::run;
+
+	fn check(src: &str, expected: Word) {
+		let stack = run(src).unwrap();
+		assert!(stack.len() == 1 && equal_words(&stack[0], &expected), "{}: {:?}", src, stack);
+	}
+
+	fn bools(v: &[bool]) -> Word {
+		return mask_word(v.to_vec())
+	}
+
+	fn strs(v: &[Option<&str>]) -> Word {
+		return Word::StrVect(Box::new(v.iter().map(|s| s.map(|s| s.to_string())).collect::<Array1<Option<String>>>()))
+	}
+
+	#[test]
+	fn na_is_not_nan() {
+		assert!(is_na(na()) && na().is_nan());
+		assert!(!is_na(f64::NAN));
+		check("[1 NA 0.0 0 div] is_na", bools(&[false, true, false]));
+	}
+
+	#[test]
+	fn is_na_each_type() {
+		check("NA is_na", Word::Bool(true));
+		check("2 is_na", Word::Bool(false));
+		check("[true NA] is_na", bools(&[false, true]));
+		check("[\"a\" NA \"b\"] is_na", bools(&[false, true, false]));
+		check("[1 \"a\" NA] is_na", bools(&[false, false, true]));
+		check("[\"a\" NA] factor is_na", bools(&[false, true]));
+		assert_eq!(run("\"a\" drop_na").unwrap_err().kind, ErrorType::Type);
+	}
+
+	#[test]
+	fn drop_and_fill() {
+		check("[1 NA 3] drop_na", Word::Vect(Box::new(ndarray::array![1.0, 3.0])));
+		check("[\"a\" NA \"b\"] drop_na", strs(&[Some("a"), Some("b")]));
+		check("[1 NA 3] 0 fill_na", Word::Vect(Box::new(ndarray::array![1.0, 0.0, 3.0])));
+		check("[true NA] false fill_na", Word::BoolVect(Box::new(ndarray::array![Some(true), Some(false)])));
+		check("[\"a\" NA] \"z\" fill_na", strs(&[Some("a"), Some("z")]));
+		check("NA 5 fill_na", Word::Int(5));
+		assert_eq!(run("[1 NA] \"x\" fill_na").unwrap_err().kind, ErrorType::Type);
+		assert_eq!(run("[\"a\" NA] factor \"q\" fill_na").unwrap_err().kind, ErrorType::Type);
+	}
+
+	/* Reductions skip NaN but not NA, unless they run inside 'na_rm'. */
+	#[test]
+	fn reductions() {
+		check("[1 0.0 0 div 3] mean", Word::Int(2));
+		check("[1 NA 3] mean", Word::Na);
+		check("[1 NA 3] [mean] na_rm", Word::Int(2));
+		check("[1 NA 0.0 0 div 3] [sum] na_rm", Word::Int(4));
+		check("[true false NA] [mean] na_rm", Word::Num(0.5));
+		check("[1 NA 3 5] 0.5 [quantile] na_rm", Word::Int(3));
+		let stack = run("[1 NA 3] [mean] na_rm [1 NA] mean").unwrap();
+		assert!(equal_words(&stack[0], &Word::Int(2)) && equal_words(&stack[1], &Word::Na));
+		assert_eq!(run("[1 2] true mean").unwrap_err().kind, ErrorType::Type);
+	}
+}
@@ -413,6 +413,7 @@ impl Parser {
             "true"  => { return Ok(Expr::Single(Opcode::Const(ConstCode::True))) },
             "false" => { return Ok(Expr::Single(Opcode::Const(ConstCode::False))) },
             "none"  => { return Ok(Expr::Single(Opcode::Const(ConstCode::Null))) },
+            "NA"    => { return Ok(Expr::Single(Opcode::Const(ConstCode::Na))) },
             _ => { }
         }
         return Ok(Expr::Single(Opcode::Sym(Box::new(s))));
@@ -0,0 +1,245 @@
+/* random.rs
+ * --------- */
+use crate::code::{RandOp, sprint_randop};
+use crate::code::RandOp::*;
+use crate::error::{ErrorType, StaxError, error};
+use crate::vm::Vm;
+use crate::words::{BoolResult, Word, WordResult, bool_word, list2array1, num_word, str_word, tighten, toF64};
+
+use ndarray::Array1;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/* xoshiro256** seeded through splitmix64. Each VM owns one, so runs are reproducible after
+ * 'seed' and independent across VMs. */
+pub struct Rng {
+	s: [u64; 4]
+}
+
+impl Rng {
+	pub fn new(seed: u64) -> Self {
+		let mut x = seed;
+		let mut s = [0u64; 4];
+		for i in 0 .. 4 {
+			x = x.wrapping_add(0x9E3779B97F4A7C15);
+			let mut z = x;
+			z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+			z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+			s[i] = z ^ (z >> 31);
+		}
+		return Rng { s: s }
+	}
+
+	/* Seeded from the clock, for VMs that never call 'seed'. */
+	pub fn from_time() -> Self {
+		let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+		return Rng::new(nanos)
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		let result = self.s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+		let t = self.s[1] << 17;
+		self.s[2] ^= self.s[0];
+		self.s[3] ^= self.s[1];
+		self.s[1] ^= self.s[2];
+		self.s[0] ^= self.s[3];
+		self.s[2] ^= t;
+		self.s[3] = self.s[3].rotate_left(45);
+		return result
+	}
+
+	/* Uniform on the open interval (0, 1). */
+	pub fn uniform(&mut self) -> f64 {
+		return ((self.next_u64() >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+	}
+
+	/* Uniform integer in 0 .. n. */
+	pub fn below(&mut self, n: usize) -> usize {
+		return ((self.uniform() * n as f64) as usize).min(n - 1)
+	}
+}
+
+pub fn do_random(vm: &mut Vm, op: RandOp) -> BoolResult {
+	let nargs = match op { Sample => 2, _ => 1 };
+	if vm.stack.len() < nargs {
+		return error(ErrorType::Underflow, format!("'{}' requires {} objects on top of the stack. Only {} found.", sprint_randop(&op), nargs, vm.stack.len()))
+	}
+	let arg = vm.stack.pop().unwrap();
+	let arg = match arg {
+		Word::Quote(q) 	=> { tighten(vm.run_newstack(q.to_vec())?) },
+		_ => { arg }
+	};
+	match op {
+		Choose 	=> {
+			let items = elements(&arg, &op)?;
+			if items.len() == 0 {
+				return error(ErrorType::Index, format!("'choose' cannot pick from an empty collection."))
+			}
+			let i = vm.rng.below(items.len());
+			return vm.push_const(items[i].clone())
+		},
+		Sample 	=> {
+			let result = do_sample(vm, arg)?;
+			return vm.push_const(result)
+		},
+		Seed 	=> match arg {
+			Word::Int(i) 	=> { vm.rng = Rng::new(i as i64 as u64); return Ok(false) },
+			_ => { return error(ErrorType::Type, format!("'seed' requires an object of type 'int'. Given: '{}'.", arg.sprint_type())) }
+		},
+		Shuffle => {
+			let mut items = elements(&arg, &op)?;
+			for i in (1 .. items.len()).rev() {
+				let j = vm.rng.below(i + 1);
+				items.swap(i, j);
+			}
+			return vm.push_const(collect(&arg, items))
+		}
+	}
+}
+
+/* 'x n sample' draws 'n' elements without replacement, 'x n replace sample' sets replacement
+ * with a bool, and 'x n replace weights sample' draws with the given weights. An integer 'x'
+ * samples from 1 to 'x', as in R. Quotes are evaluated into the collection they build. */
+fn do_sample(vm: &mut Vm, top: Word) -> WordResult {
+	let (replace, weights): (bool, Option<Array1<f64>>);
+	let size: Word;
+	match top {
+		Word::Bool(b) 	=> {
+			replace = b;
+			weights = None;
+			size = pop(vm)?;
+		},
+		Word::Vect(_) | Word::List(_) 	=> {
+			weights = Some(match top { Word::Vect(v) => *v, Word::List(l) => list2array1(&l), _ => Array1::zeros(0) });
+			match pop(vm)? {
+				Word::Bool(b) 	=> { replace = b },
+				w => { return error(ErrorType::Type, format!("'sample' with weights requires a replacement flag of type 'bool'. Given: '{}'.", w.sprint_type())) }
+			}
+			size = pop(vm)?;
+		},
+		_ => {
+			replace = false;
+			weights = None;
+			size = top;
+		}
+	}
+	let x = match pop(vm)? {
+		Word::Quote(q) 	=> { tighten(vm.run_newstack(q.to_vec())?) },
+		w => { w }
+	};
+	let n = match size {
+		Word::Int(i) if i >= 0 	=> { i as usize },
+		_ => { return error(ErrorType::Type, format!("'sample' requires a non-negative size of type 'int'. Given: '{}'.", size.sprint())) }
+	};
+	let items = match x {
+		Word::Int(k) 	=> { (1 ..= k.max(0)).map(|i| Word::Int(i)).collect() },
+		_ => { elements(&x, &Sample)? }
+	};
+	let mut w = match weights {
+		Some(w) 	=> {
+			if w.len() != items.len() {
+				return error(ErrorType::Index, format!("'sample' requires one weight per element. Given: {} and {}.", w.len(), items.len()))
+			}
+			if w.iter().any(|p| !(*p >= 0.0) || p.is_infinite()) {
+				return error(ErrorType::Domain, format!("Weights must be finite and non-negative."))
+			}
+			w.to_vec()
+		},
+		None 	=> { vec![1.0; items.len()] }
+	};
+	let available = w.iter().filter(|p| **p > 0.0).count();
+	if (!replace && n > available) || (n > 0 && available == 0) {
+		return error(ErrorType::Domain, format!("Cannot take a sample of {} from {} elements without replacement.", n, available))
+	}
+	let mut drawn = Vec::with_capacity(n);
+	for _ in 0 .. n {
+		let total: f64 = w.iter().sum();
+		let u = vm.rng.uniform() * total;
+		let (mut k, mut acc) = (0, 0.0);
+		for (i, p) in w.iter().enumerate() {
+			if *p == 0.0 { continue }
+			k = i;
+			acc += p;
+			if u < acc { break }
+		}
+		drawn.push(items[k].clone());
+		if !replace { w[k] = 0.0 }
+	}
+	return Ok(collect(&x, drawn))
+}
+
+
+/* 	== HELPERS == */
+
+fn pop(vm: &mut Vm) -> WordResult {
+	match vm.stack.pop() {
+		Some(w) => { return Ok(w) },
+		None 	=> { return error(ErrorType::Underflow, format!("'sample' requires more objects on top of the stack.")) }
+	}
+}
+
+fn elements(w: &Word, op: &RandOp) -> Result<Vec<Word>, StaxError> {
+	match w {
+		Word::BoolVect(v) 	=> { return Ok(v.iter().map(bool_word).collect()) },
+		Word::List(l) 		=> { return Ok(l.to_vec()) },
+		Word::StrVect(v) 	=> { return Ok(v.iter().map(str_word).collect()) },
+		Word::Vect(v) 		=> { return Ok(v.iter().map(|f| num_word(*f)).collect()) },
+		_ => { return error(ErrorType::Type, format!("'{}' cannot be completed on objects of type '{}'.", sprint_randop(op), w.sprint_type())) }
+	}
+}
+
+/* Packs drawn elements back into the type they came from. */
+fn collect(like: &Word, items: Vec<Word>) -> Word {
+	match like {
+		Word::BoolVect(_) 	=> { return Word::BoolVect(Box::new(items.iter().map(|w| match w { Word::Bool(b) => Some(*b), _ => None }).collect())) },
+		Word::List(_) 		=> { return Word::List(Box::new(items)) },
+		Word::StrVect(_) 	=> {
+			return Word::StrVect(Box::new(items.into_iter().map(|w| match w { Word::Str(s) => Some(*s), _ => None }).collect()))
+		},
+		_ => { return Word::Vect(Box::new(items.iter().map(toF64).collect())) }
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vm::tests::{new_vm, run, run_with};
+
+	fn nums(src: &str) -> Vec<f64> {
+		match run(src).unwrap().pop() {
+			Some(Word::Vect(v)) 	=> { return v.to_vec() },
+			w => { panic!("{}: {:?}", src, w) }
+		}
+	}
+
+	#[test]
+	fn seeded() {
+		assert_eq!(nums("42 seed 10 runif"), nums("42 seed 10 runif"));
+		assert_ne!(nums("42 seed 10 runif"), nums("43 seed 10 runif"));
+		assert_eq!(nums("7 seed [1 2 3 4 5] shuffle"), nums("7 seed [1 2 3 4 5] shuffle"));
+		/* Two VMs draw independently of each other. */
+		let (mut a, mut b) = (new_vm(), new_vm());
+		run_with(&mut a, "1 seed").unwrap();
+		run_with(&mut b, "1 seed 3 runif").unwrap();
+		let x = run_with(&mut a, "3 runif").unwrap().pop();
+		assert!(matches!((x, b.stack.pop()), (Some(Word::Vect(x)), Some(Word::Vect(y))) if x == y));
+		assert_eq!(run("1.5 seed").unwrap_err().kind, ErrorType::Type);
+	}
+
+	#[test]
+	fn sampling() {
+		let mut s = nums("1 seed [1 2 3 4 5] 5 sample");
+		s.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		assert_eq!(s, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+		assert!(nums("1 seed 3 20 true sample").iter().all(|x| [1.0, 2.0, 3.0].contains(x)));
+		/* Elements with no weight are never drawn. */
+		assert!(nums("1 seed [1 2 3] 50 true [0 1 0] sample").iter().all(|x| *x == 2.0));
+		let heavy = nums("1 seed [1 2] 2000 true [1 9] sample").iter().filter(|x| **x == 2.0).count();
+		assert!(heavy > 1700 && heavy < 1900, "{}", heavy);
+		assert_eq!(nums("1 seed [1 2 3] 2 false [1 0 1] sample").len(), 2);
+		assert_eq!(run("[1 2 3] 4 sample").unwrap_err().kind, ErrorType::Domain);
+		assert_eq!(run("[1 2 3] 3 false [1 0 1] sample").unwrap_err().kind, ErrorType::Domain);
+		assert_eq!(run("[1 2 3] 2 true [1 1] sample").unwrap_err().kind, ErrorType::Index);
+		assert_eq!(run("[] choose").unwrap_err().kind, ErrorType::Index);
+	}
+}
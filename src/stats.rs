@@ -0,0 +1,242 @@
+/* stats.rs
+ * -------- */
+use crate::code::{StatOp, sprint_statop};
+use crate::code::StatOp::*;
+use crate::dist::inc_beta;
+use crate::error::{ErrorType, StaxError, error};
+use crate::htest::do_htest;
+use crate::math::mean_vec;
+use crate::vm::Vm;
+use crate::words::{BoolResult, Word, WordResult};
+
+use ndarray::{Array1, Array2, Axis, s};
+
+use std::collections::HashMap;
+
+pub fn do_stats(vm: &mut Vm, op: StatOp) -> BoolResult {
+	match op {
+		Lm | Predict 	=> { },
+		_ => {
+			let result = do_htest(vm, &op)?;
+			return vm.push_const(result)
+		}
+	}
+	let nargs = 2;
+	if vm.stack.len() < nargs {
+		return error(ErrorType::Underflow, format!("'{}' requires {} objects on top of the stack. Only {} found.", sprint_statop(&op), nargs, vm.stack.len()))
+	}
+	let mut args = vm.stack.split_off(vm.stack.len() - nargs);
+	let result: Word;
+	match op {
+		Lm 		=> { result = do_lm(vm, &args[0], &args[1])? },
+		_ 	=> {
+			let newx = args.pop().unwrap();
+			result = do_predict(vm, &args[0], &newx)?
+		}
+	}
+	return vm.push_const(result)
+}
+
+
+/* 	== LINEAR MODELS == */
+
+/* Fits 'y = X b' by ordinary least squares. A matrix is used as the design matrix as is; a
+ * vector or a list (or quote) of vectors gives the predictors, and an intercept column is added. */
+fn do_lm(vm: &mut Vm, y: &Word, x: &Word) -> WordResult {
+	let y = match y {
+		Word::Vect(v) 	=> { *v.clone() },
+		_ => { return error(ErrorType::Type, format!("'lm' requires a response of type 'vec<num>'. Given: '{}'.", y.sprint_type())) }
+	};
+	let (x, added) = design(vm, x, &Lm)?;
+	let (n, p) = (x.nrows(), x.ncols());
+	if y.len() != n {
+		return error(ErrorType::Index, format!("Response has {} observations but the predictors have {}.", y.len(), n))
+	}
+	if n <= p {
+		return error(ErrorType::Domain, format!("'lm' requires more observations than coefficients. Given: {} and {}.", n, p))
+	}
+
+	let (r, qty) = householder(&x, &y);
+	for j in 0 .. p {
+		if r[[j, j]].abs() <= 1e-7 * x.column(j).dot(&x.column(j)).sqrt() {
+			return error(ErrorType::Domain, format!("Design matrix is singular. Predictors may be collinear."))
+		}
+	}
+	let coef = back_solve(&r, &qty.slice(s![.. p]).to_owned());
+	let fitted = x.dot(&coef);
+	let resid = &y - &fitted;
+	let df = (n - p) as f64;
+	let rss = resid.dot(&resid);
+	let sigma2 = rss / df;
+	/* (X'X)⁻¹ = R⁻¹ R⁻ᵀ, so the variance of each coefficient is a row of R⁻¹ squared. */
+	let mut rinv = Array2::<f64>::zeros((p, p));
+	for j in 0 .. p {
+		let mut e = Array1::<f64>::zeros(p);
+		e[j] = 1.0;
+		rinv.column_mut(j).assign(&back_solve(&r, &e));
+	}
+	let se = rinv.rows().into_iter().map(|row| (row.dot(&row) * sigma2).sqrt()).collect::<Array1<f64>>();
+	let tval = &coef / &se;
+	let pval = tval.mapv(|t| inc_beta(df / 2.0, 0.5, df / (df + t * t)));
+
+	/* As in R, R² is centered only when the model has an intercept. */
+	let intercept = added || x.axis_iter(Axis(1)).any(|c| c.iter().all(|v| *v == 1.0));
+	let tss = if intercept {
+		let m = mean_vec(&y);
+		y.mapv(|v| (v - m).powi(2)).sum()
+	} else { y.dot(&y) };
+	let r2 = 1.0 - rss / tss;
+	let adj_r2 = 1.0 - (1.0 - r2) * ((n - intercept as usize) as f64) / df;
+
+	let mut model: HashMap<String, Word> = HashMap::new();
+	model.insert("coefficients".to_string(), Word::Vect(Box::new(coef)));
+	model.insert("se".to_string(), Word::Vect(Box::new(se)));
+	model.insert("t".to_string(), Word::Vect(Box::new(tval)));
+	model.insert("p".to_string(), Word::Vect(Box::new(pval)));
+	model.insert("residuals".to_string(), Word::Vect(Box::new(resid)));
+	model.insert("fitted".to_string(), Word::Vect(Box::new(fitted)));
+	model.insert("r2".to_string(), Word::Num(r2));
+	model.insert("adj_r2".to_string(), Word::Num(adj_r2));
+	model.insert("sigma".to_string(), Word::Num(sigma2.sqrt()));
+	model.insert("df".to_string(), Word::Int(n as i32 - p as i32));
+	model.insert("intercept".to_string(), Word::Bool(added));
+	return Ok(Word::Dict(Box::new(model)))
+}
+
+/* Applies the coefficients of a model from 'lm' to new data given in the same form as the
+ * original predictors. */
+fn do_predict(vm: &mut Vm, model: &Word, x: &Word) -> WordResult {
+	let (coef, added) = match model {
+		Word::Dict(d) => match (d.get("coefficients"), d.get("intercept")) {
+			(Some(Word::Vect(c)), Some(Word::Bool(b))) 	=> { (c.clone(), *b) },
+			_ => { return error(ErrorType::Type, format!("'predict' requires a model fitted by 'lm'.")) }
+		},
+		_ => { return error(ErrorType::Type, format!("'predict' requires a model of type 'dict'. Given: '{}'.", model.sprint_type())) }
+	};
+	let (x, _) = match x {
+		Word::Mat(_) 	=> { design(vm, x, &Predict)? },
+		_ 	=> {
+			let (m, _) = design(vm, x, &Predict)?;
+			if added { (m, true) } else { (m.slice(s![.., 1..]).to_owned(), false) }
+		}
+	};
+	if x.ncols() != coef.len() {
+		return error(ErrorType::Index, format!("Model has {} coefficients but the new data has {} columns.", coef.len(), x.ncols()))
+	}
+	return Ok(Word::Vect(Box::new(x.dot(&*coef))))
+}
+
+/* Householder QR of 'x', without forming Q: the upper triangular R and Q'y. */
+fn householder(x: &Array2<f64>, y: &Array1<f64>) -> (Array2<f64>, Array1<f64>) {
+	let (mut a, mut qty) = (x.clone(), y.clone());
+	let p = a.ncols();
+	for j in 0 .. p {
+		let mut v = a.slice(s![j .., j]).to_owned();
+		let norm = v.dot(&v).sqrt();
+		if norm == 0.0 { continue }
+		v[0] += if v[0] < 0.0 { -norm } else { norm };
+		let vv = v.dot(&v);
+		for k in j .. p {
+			let f = 2.0 * v.dot(&a.slice(s![j .., k])) / vv;
+			a.slice_mut(s![j .., k]).scaled_add(-f, &v);
+		}
+		let f = 2.0 * v.dot(&qty.slice(s![j ..])) / vv;
+		qty.slice_mut(s![j ..]).scaled_add(-f, &v);
+	}
+	let mut r = a.slice(s![.. p, ..]).to_owned();
+	for i in 1 .. p {
+		r.slice_mut(s![i, .. i]).fill(0.0);
+	}
+	return (r, qty)
+}
+
+/* Solves 'r x = b' for an upper triangular 'r' by back substitution. */
+fn back_solve(r: &Array2<f64>, b: &Array1<f64>) -> Array1<f64> {
+	let p = b.len();
+	let mut x = Array1::<f64>::zeros(p);
+	for i in (0 .. p).rev() {
+		let sum = r.slice(s![i, i + 1 ..]).dot(&x.slice(s![i + 1 ..]));
+		x[i] = (b[i] - sum) / r[[i, i]];
+	}
+	return x
+}
+
+/* The design matrix for 'x', and whether an intercept column was added. */
+fn design(vm: &mut Vm, x: &Word, op: &StatOp) -> Result<(Array2<f64>, bool), StaxError> {
+	let columns: Vec<Array1<f64>> = match x {
+		Word::Mat(m) 	=> { return Ok((*m.clone(), false)) },
+		Word::Vect(v) 	=> { vec![*v.clone()] },
+		Word::List(_) | Word::Quote(_) 	=> {
+			let items = match x {
+				Word::List(l) 	=> { l.to_vec() },
+				Word::Quote(q) 	=> { vm.run_newstack(q.to_vec())? },
+				_ 	=> { vec![] }
+			};
+			let mut cols = Vec::new();
+			for w in items.iter() {
+				match w {
+					Word::Vect(v) 	=> { cols.push(*v.clone()) },
+					_ => { return error(ErrorType::Type, format!("'{}' requires predictors of type 'vec<num>'. Given: '{}'.", sprint_statop(op), w.sprint_type())) }
+				}
+			}
+			cols
+		},
+		_ => { return error(ErrorType::Type, format!("'{}' cannot use predictors of type '{}'.", sprint_statop(op), x.sprint_type())) }
+	};
+	let n = columns.first().map(|c| c.len()).unwrap_or(0);
+	if columns.iter().any(|c| c.len() != n) {
+		return error(ErrorType::Index, format!("All predictors must have the same length."))
+	}
+	let mut m = Array2::<f64>::ones((n, columns.len() + 1));
+	for (j, c) in columns.iter().enumerate() {
+		m.column_mut(j + 1).assign(c);
+	}
+	return Ok((m, true))
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vm::tests::new_vm;
+
+	fn get(w: &Word, key: &str) -> Word {
+		match w {
+			Word::Dict(d) 	=> { return d.get(key).cloned().unwrap() },
+			_ => { panic!("not a dict") }
+		}
+	}
+
+	fn close(w: &Word, e: &[f64]) -> bool {
+		match w {
+			Word::Num(f) 	=> { return (f - e[0]).abs() < 1e-10 },
+			Word::Vect(v) 	=> { return v.len() == e.len() && v.iter().zip(e.iter()).all(|(a, b)| (a - b).abs() < 1e-10) },
+			_ => { return false }
+		}
+	}
+
+	#[test]
+	fn lm_simple() {
+		/* Slope Sxy/Sxx = 8/10, RSS = 3.6 on 3 df, and TSS = 10. */
+		let mut vm = new_vm();
+		let y = Word::Vect(Box::new(Array1::from_vec(vec![1.0, 3.0, 2.0, 5.0, 4.0])));
+		let x = Word::Vect(Box::new(Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0])));
+		let m = do_lm(&mut vm, &y, &x).unwrap();
+		assert!(close(&get(&m, "coefficients"), &[0.6, 0.8]));
+		assert!(close(&get(&m, "se"), &[1.32f64.sqrt(), 0.12f64.sqrt()]));
+		assert!(close(&get(&m, "sigma"), &[1.2f64.sqrt()]));
+		assert!(close(&get(&m, "r2"), &[0.64]));
+		assert!(close(&get(&m, "adj_r2"), &[0.52]));
+	}
+
+	#[test]
+	fn lm_collinear() {
+		/* The second predictor is twice the first. */
+		let mut vm = new_vm();
+		let y = Word::Vect(Box::new(Array1::from_vec(vec![1.0, 3.0, 2.0, 5.0, 4.0])));
+		let x1 = Word::Vect(Box::new(Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0])));
+		let x2 = Word::Vect(Box::new(Array1::from_vec(vec![2.0, 4.0, 6.0, 8.0, 10.0])));
+		let e = do_lm(&mut vm, &y, &Word::List(Box::new(vec![x1, x2]))).unwrap_err();
+		assert_eq!(e.kind, ErrorType::Domain);
+	}
+}
@@ -2,6 +2,8 @@
  * ------- */
 use crate::code::UnOp;
 use crate::code::UnOp::*;
+use crate::compare::do_not;
+use crate::error::StaxError;
 use crate::words::{StaxResult, Word};
 
 use std::io::prelude::*;
@@ -9,15 +11,12 @@ use std::io;
 
 pub fn do_un(op: UnOp, w: Word) -> StaxResult {
 	match op {
+		Not 	=> { return Ok(Some(do_not(w)?)) },
 		Print 	=> {  
 			w.print(); 
 			io::stdout().flush().ok().expect("stdout could not be flushed.");
 			return Ok(None) 
 		},
-		Input  => {
-			let mut s = String::new();
-			stdin().read_line(&mut s).expect("String could not be read.");
-			
-		}
+		Throw 	=> { return Err(StaxError::thrown(w)) }
 	}
 }
\ No newline at end of file
@@ -1,30 +1,55 @@
 /* vm.rs
  * ----- */
-use crate::code::Opcode;
+use crate::code::{GenOp, Opcode, sprint_opcode_short};
+use crate::compiler::compile;
 use crate::env::Env;
+use crate::error::{ErrorType, Frame, StaxError, error};
 use crate::eval::eval_inst;
-use crate::words::Word;
+use crate::lexer::{Lexer, Span};
+use crate::random::Rng;
+use crate::words::{BoolResult, Word};
 
+use std::collections::{HashMap, HashSet};
 use std::mem;
 
 pub const ENV_SIZE : usize = 16;
+pub const MAX_DEPTH : usize = 10000;
+pub const STACK_RED_ZONE : usize = 256 * 1024;
 pub const STACK_SIZE : usize = 2048;
 
+/* Nested calls recurse in 'eval', so two limits stop runaway recursion with an Overflow error
+ * instead of a crash: 'max_depth' call frames, and 'STACK_RED_ZONE' bytes left on the native
+ * stack of the running thread. The second one holds on any thread, whatever its stack size; a
+ * thread with a larger stack simply reaches 'max_depth' first. */
 pub struct Vm {
+	pub brk 	: bool,
 	pub envs 	: Vec<Env>,
 	pub ep 		: usize,
 	pub last 	: Option<Word>,
 	pub line 	: u16,
+	pub loops 	: usize,
+	pub max_depth 	: usize,
+	pub na_rm 		: bool,
+	pub next_span 	: usize,
+	pub rng 		: Rng,
+	pub spans 	: HashMap<usize, Vec<Span>>,
 	pub stack 	: Vec<Word>
 }
 
 impl Vm {
 	pub fn new() -> Self {
 		return Vm {
+			brk 	: false,
 			envs 	: Vec::with_capacity(ENV_SIZE),
 			ep 		: 0,
 			last 	: None,
 			line 	: 0,
+			loops 	: 0,
+			max_depth 	: MAX_DEPTH,
+			na_rm 		: false,
+			next_span 	: 0,
+			rng 		: Rng::from_time(),
+			spans 	: HashMap::new(),
 			stack 	: Vec::with_capacity(STACK_SIZE)
 		}
 	}
@@ -38,6 +63,29 @@ impl Vm {
 		self.last = None;
 	}
 
+	/* Compiles the lexer's input and keeps its span tables for error reporting. */
+	pub fn compile(&mut self, lexer: &mut Lexer) -> Result<Vec<Vec<Opcode>>, StaxError> {
+		lexer.table_base = self.next_span;
+		let code = compile(lexer)?;
+		for table in lexer.tables.drain(..) {
+			self.spans.insert(self.next_span, table);
+			self.next_span += 1;
+		}
+		return Ok(code)
+	}
+
+	/* Drops the span tables numbered 'from' and up that no word on the stack or in an Env still
+	 * refers to. The REPL calls it after each line, so the tables of code that has finished
+	 * running are freed while those of the quotes and programs it stored are kept. */
+	pub fn free_spans(&mut self, from: usize) {
+		let mut live = HashSet::new();
+		for w in self.stack.iter().chain(self.last.iter()) { word_spans(w, &mut live) }
+		for env in self.envs.iter() {
+			for w in env.dict.values() { word_spans(w, &mut live) }
+		}
+		self.spans.retain(|id, _| *id < from || live.contains(id));
+	}
+
 	pub fn curr(&self) -> &Env {
 		return self.envs.last().unwrap()
 	}
@@ -59,19 +107,50 @@ impl Vm {
 		self.ep += 1;
 	}
 
-	pub fn eval(&mut self) -> Result<bool, String> {
-		let n = self.curr().inst.len();
-		while self.curr().ip < n {
+	pub fn eval(&mut self) -> BoolResult {
+		while self.curr().ip < self.curr().inst.len() {
 			let code = self.curr_mut().adv_get(1);
+			/* A 'Sym' naming a 'Program' as the last instruction of a call frame replaces the
+			 * frame's code instead of pushing a new Env. Its dict is kept, so the callee still
+			 * sees what the caller could see. */
+			if self.in_tail() {
+				match &code {
+					Opcode::Sym(s) => match self.get(s) {
+						Some(Word::Program(p)) => {
+							let ops = *p.clone();
+							self.curr_mut().reset(ops);
+							continue
+						},
+						_ => { }
+					},
+					_ => { }
+				}
+			}
 			match eval_inst(code, self) {
 				Ok(_) => { },
-				Err(e) => { return Err(e) }
+				Err(mut e) => {
+					let ip  = self.curr().ip - 1;
+					let op  = self.curr().inst[ip].clone();
+					let loc = self.span_at(self.curr(), ip);
+					if e.op.is_none() {
+						e.op  = Some(op);
+						e.loc = loc;
+					} else {
+						let name = match op {
+							Opcode::Sym(s) 	=> { *s },
+							_ 	=> { sprint_opcode_short(&op) }
+						};
+						e.trace.push(Frame{ name: name, loc: loc });
+					}
+					return Err(e)
+				}
 			}
+			if self.brk { return Ok(true) } // unwind to the enclosing loop
 		}
 		return Ok(false)
 	}
 
-	pub fn exec_word(&mut self, w : Word) -> Result<bool, String> {
+	pub fn exec_word(&mut self, w : Word) -> BoolResult {
 		/* Similar to 'run_word' except quotes are executed too! */
 		match w {
 			Word::Program(m) => { return self.run_opcodes(*m.clone()) },
@@ -83,6 +162,32 @@ impl Vm {
 		}
 	}
 
+	/* Like 'exec_word', but a quote or program called from the last instruction of a call
+	 * frame replaces that frame's code. Used by the conditional combinators. */
+	pub fn exec_tail(&mut self, w: Word) -> BoolResult {
+		if self.in_tail() {
+			match w {
+				Word::Program(p) | Word::Quote(p) => {
+					self.curr_mut().reset(*p);
+					return Ok(false)
+				},
+				_ => { }
+			}
+		}
+		return self.exec_word(w)
+	}
+
+	/* Source span of instruction 'ip' in 'env', if its block was compiled with spans. */
+	pub fn span_at(&self, env: &Env, ip: usize) -> Option<Span> {
+		match env.inst.first() {
+			Some(Opcode::GenOp(GenOp::Spans(id))) => match self.spans.get(&(*id as usize)) {
+				Some(table) => { return table.get(ip).cloned() },
+				None 		=> { return None }
+			},
+			_ => { return None }
+		}
+	}
+
 	pub fn get(&self, key: &String) -> Option<&Word> {
 		for i in 0 .. self.ep {
 			match self.envs[self.ep-1-i].dict.get(key) {
@@ -97,12 +202,17 @@ impl Vm {
 		return self.envs.get_mut(1).unwrap()
 	}
 
+	/* True when the instruction just fetched was the last one of a reusable call frame. */
+	pub fn in_tail(&self) -> bool {
+		return self.curr().reuse && self.curr().ip == self.curr().inst.len()
+	}
+
 	pub fn print_stack(&self) {
 		println!("{}\n", self.stack.iter().map(|x| x.sprint()).collect::<Vec<String>>().join("\n"));
 	}
 
-	pub fn push_const(&mut self, w: Word) -> Result<bool, String> {
-		if self.stack.len() >= STACK_SIZE { return Err(format!("Stack overflow.")) }
+	pub fn push_const(&mut self, w: Word) -> BoolResult {
+		if self.stack.len() >= STACK_SIZE { return error(ErrorType::Overflow, format!("Stack overflow.")) }
 		self.stack.push(w);
 		return Ok(false);
 	}
@@ -111,10 +221,34 @@ impl Vm {
 		self.stack.push(w);
 	}
 
-	pub fn run_newstack(&mut self, ops: Vec<Opcode>) -> Result<Vec<Word>, String> {
+	/* Executes 'w' on a copy of the stack and returns the copy, leaving the stack untouched. */
+	pub fn run_copystack(&mut self, w: Word) -> Result<Vec<Word>, StaxError> {
+		let mut s = self.stack.clone();
+		mem::swap(&mut s, &mut self.stack);
+		let result = self.without_loops(|vm| vm.exec_word(w));
+		mem::swap(&mut s, &mut self.stack);
+		match result {
+			Ok(_) 	=> { return Ok(s) },
+			Err(e) 	=> { return Err(e) }
+		}
+	}
+
+	/* Executes 'w' on a fresh stack holding only 'args' and returns that stack. */
+	pub fn run_withstack(&mut self, args: Vec<Word>, w: Word) -> Result<Vec<Word>, StaxError> {
+		let mut s = args;
+		mem::swap(&mut s, &mut self.stack);
+		let result = self.without_loops(|vm| vm.exec_word(w));
+		mem::swap(&mut s, &mut self.stack);
+		match result {
+			Ok(_) 	=> { return Ok(s) },
+			Err(e) 	=> { return Err(e) }
+		}
+	}
+
+	pub fn run_newstack(&mut self, ops: Vec<Opcode>) -> Result<Vec<Word>, StaxError> {
 		let mut s = Vec::<Word>::with_capacity(STACK_SIZE);
 		mem::swap(&mut s, &mut self.stack);
-		let result = self.run_opcodes(ops);
+		let result = self.without_loops(|vm| vm.run_opcodes(ops));
 		mem::swap(&mut s, &mut self.stack);
 		match result {
 			Ok(_) 	=> { return Ok(s) },
@@ -122,14 +256,45 @@ impl Vm {
 		}
 	}
 
-	pub fn run_opcodes(&mut self, ops : Vec<Opcode>) -> Result<bool, String> {
+	pub fn run_opcodes(&mut self, ops : Vec<Opcode>) -> BoolResult {
+		if self.envs.len() >= self.max_depth {
+			return error(ErrorType::Overflow, format!("Recursion limit of {} calls exceeded.", self.max_depth))
+		}
+		if stacker::remaining_stack().map_or(false, |r| r < STACK_RED_ZONE) {
+			return error(ErrorType::Overflow, format!("Recursion limit exceeded. The thread ran out of stack after {} calls.", self.envs.len()))
+		}
 		self.env_push(ops);
+		self.curr_mut().reuse = true;
 		let result = self.eval();
 		self.env_pop();
 		return result
 	}
 
-	pub fn run_word(&mut self, w : Word) -> Result<bool, String> {
+	/* Runs 'ops' in the current Env without pushing a new one, so assignments stay in
+	 * the caller's scope. Used by the looping combinators on every iteration. */
+	pub fn run_inplace(&mut self, ops: &mut Vec<Opcode>) -> BoolResult {
+		let ip = self.curr().ip;
+		let reuse = self.curr().reuse;
+		mem::swap(&mut self.curr_mut().inst, ops);
+		self.curr_mut().ip = 0;
+		self.curr_mut().reuse = false; // 'ops' must come back unchanged
+		let result = self.eval();
+		mem::swap(&mut self.curr_mut().inst, ops);
+		self.curr_mut().ip = ip;
+		self.curr_mut().reuse = reuse;
+		return result
+	}
+
+	/* Runs 'f' as if no loop were open. Code run on another stack, or by a combinator that is
+	 * not a loop, cannot be left half done, so a 'break' inside it is an error. */
+	pub fn without_loops<F, T>(&mut self, f: F) -> Result<T, StaxError> where F: FnOnce(&mut Vm) -> Result<T, StaxError> {
+		let loops = mem::replace(&mut self.loops, 0);
+		let result = f(self);
+		self.loops = loops;
+		return result
+	}
+
+	pub fn run_word(&mut self, w : Word) -> BoolResult {
 		match w {
 			Word::Program(m) => { return self.run_opcodes(*m.clone()) },
 			Word::MacroOp(op) => {
@@ -138,4 +303,122 @@ impl Vm {
 			_ 	=> { return self.push_const(w.clone()) }
 		}
 	}
-} 
\ No newline at end of file
+} 
+
+/* Ids of the span tables used by the code inside 'w'. */
+fn word_spans(w: &Word, live: &mut HashSet<usize>) {
+	match w {
+		Word::Dict(d) 		=> { d.values().for_each(|x| word_spans(x, live)) },
+		Word::Frame(f) 		=> { f.columns.iter().for_each(|x| word_spans(x, live)) },
+		Word::List(l) 		=> { l.iter().for_each(|x| word_spans(x, live)) },
+		Word::MacroOp(op) 	=> { op_spans(op, live) },
+		Word::Program(p) | Word::Quote(p) 	=> { p.iter().for_each(|op| op_spans(op, live)) },
+		_ => { }
+	}
+}
+
+fn op_spans(op: &Opcode, live: &mut HashSet<usize>) {
+	match op {
+		Opcode::GenOp(GenOp::Spans(id)) 	=> { live.insert(*id as usize); },
+		Opcode::Prog(p) | Opcode::Quote(p) 	=> { p.iter().for_each(|op| op_spans(op, live)) },
+		_ => { }
+	}
+}
+
+
+#[cfg(test)]
+pub mod tests {
+	use super::*;
+	use crate::base::create_base;
+	use std::thread;
+
+	/* A Vm set up as the binary does, with the base words and a global Env. */
+	pub fn new_vm() -> Vm {
+		let mut vm = Vm::new();
+		vm.envs.push(create_base());
+		vm.ep += 1;
+		vm.env_push(vec![]);
+		return vm
+	}
+
+	/* Runs 'src' statement by statement and returns the stack left by the last one. */
+	pub fn run_with(vm: &mut Vm, src: &str) -> Result<Vec<Word>, StaxError> {
+		let mut lexer = Lexer::new(src.to_string(), false, "test");
+		for statement in vm.compile(&mut lexer)? {
+			if statement.len() > 0 {
+				vm.global().reset(statement);
+				vm.eval()?;
+			}
+		}
+		return Ok(vm.stack.clone())
+	}
+
+	pub fn run(src: &str) -> Result<Vec<Word>, StaxError> {
+		return run_with(&mut new_vm(), src)
+	}
+
+	const COUNT: &str = "f := 0 eq [] [1 sub f 1 add] ifelse;";
+
+	#[test]
+	fn max_depth() {
+		let mut vm = new_vm();
+		vm.max_depth = 100;
+		assert!(run_with(&mut vm, &format!("{} 50 f", COUNT)).is_ok());
+		let e = run_with(&mut vm, &format!("{} 500 f", COUNT)).unwrap_err();
+		assert_eq!(e.kind, ErrorType::Overflow);
+	}
+
+	/* A call in tail position replaces its frame, so it is not limited by 'max_depth'. */
+	#[test]
+	fn tail_call() {
+		let mut vm = new_vm();
+		vm.max_depth = 20;
+		let depth = vm.envs.len();
+		let stack = run_with(&mut vm, "g := 0 eq [] [1 sub g] ifelse; 10000 g").unwrap();
+		assert_eq!(stack.len(), 1);
+		assert_eq!(vm.envs.len(), depth);
+		assert!(run_with(&mut vm, &format!("{} 10000 f", COUNT)).is_err());
+	}
+
+	/* The default limit must fail cleanly on a thread with Rust's default 2 MiB stack. */
+	#[test]
+	fn stack_limit() {
+		let child = thread::Builder::new().stack_size(2 << 20).spawn(|| {
+			let mut vm = new_vm();
+			vm.max_depth = usize::MAX;
+			return run_with(&mut vm, &format!("{} 1000000 f", COUNT)).map_err(|e| e.kind)
+		}).unwrap();
+		assert_eq!(child.join().unwrap().unwrap_err(), ErrorType::Overflow);
+	}
+
+	/* Tables of a line are freed once it has run, unless a stored quote or program uses them. */
+	#[test]
+	fn free_spans() {
+		let mut vm = new_vm();
+		for line in ["1 2 add", "f := 1 add;", "q = [2 mul];", "[1 2] [1 add] map"].iter() {
+			let base = vm.next_span;
+			run_with(&mut vm, line).unwrap();
+			vm.clear();
+			vm.free_spans(base);
+		}
+		assert_eq!(vm.spans.len(), 2);
+		let e = run_with(&mut vm, "f \"x\" q").unwrap_err();
+		assert!(e.loc.is_some());
+	}
+
+	/* Base words live in their own Env below the global one, so an assignment shadows a word
+	 * such as 'df' or 'range' only in the scope that made it. A name used before it is assigned
+	 * still runs the base word, and the error names that word. */
+	#[test]
+	fn shadow_base_words() {
+		let stack = run("df = 3; range = [1 2]; head := 1 add; df 2 head range").unwrap();
+		assert!(matches!(stack[..], [Word::Int(3), Word::Int(3), Word::Quote(_)]));
+		let mut vm = new_vm();
+		let stack = run_with(&mut vm, "f := [dt = 5; dt] do; f 0 1 dt").unwrap();
+		assert!(matches!(stack[..], [Word::Int(5), Word::Num(_)]));
+		assert!(vm.get(&format!("dt")).map_or(false, |w| matches!(w, Word::MacroOp(_))));
+		let e = run("x = 1 2 df").unwrap_err();
+		assert_eq!(e.kind, ErrorType::Underflow);
+		assert!(e.msg.contains("'df'"), "{}", e.msg);
+	}
+}
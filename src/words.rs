@@ -1,30 +1,36 @@
 /* words.rs
  * -------- */
 use crate::code::Opcode;
+use crate::error::StaxError;
+use crate::factor::Factor;
+use crate::frame::Frame;
+use crate::na::{is_na, na};
 
-use core::f64::NAN;
 use ndarray::{Array1, Array2};
 use std::collections::HashMap;
 
-pub type BoolResult = Result<bool, String>;
-pub type StaxResult = Result<Option<Word>, String>;
-pub type WordResult = Result<Word, String>;
+pub type BoolResult = Result<bool, StaxError>;
+pub type StaxResult = Result<Option<Word>, StaxError>;
+pub type WordResult = Result<Word, StaxError>;
 
 #[derive(Debug, Clone)]
 pub enum Word {
 	Null,
 	Bool(bool),
-	BoolVect(Box<Array1<bool>>),
+	BoolVect(Box<Array1<Option<bool>>>),
 	Dict(Box<HashMap<String, Word>>),
+	Factor(Box<Factor>),
+	Frame(Box<Frame>),
 	Int(i32),
 	List(Box<Vec<Word>>),
 	MacroOp(Opcode),
 	Mat(Box<Array2<f64>>),
+	Na,
 	Num(f64),
 	Program(Box<Vec<Opcode>>),
 	Quote(Box<Vec<Opcode>>),
 	Str(Box<String>),
-	StrVect(Box<Array1<String>>),
+	StrVect(Box<Array1<Option<String>>>),
 	Sym(Box<String>),
 	Vect(Box<Array1<f64>>)
 }
@@ -35,19 +41,25 @@ impl Word {
 		match self {
 			Word::Null 			=> { return format!("none") },
 			Word::Bool(b) 		=> { return format!("{}", b) },
-			Word::BoolVect(b)	=> { return format!("{}", b) },
+			Word::BoolVect(b)	=> { return sprint_items(b.iter().map(|x| x.map(|b| b.to_string()))) },
 			Word::Dict(_) 		=> { return format!("dict") },
+			Word::Factor(f) 	=> { return f.sprint() },
+			Word::Frame(f) 		=> { return f.sprint() },
 			Word::Int(i) 		=> { return format!("{}", i) },
 			Word::List(l) 		=> { return format!("[{}]", l.iter().map(|x| x.sprint_short(0)).collect::<Vec<String>>().join(" ")) },
 			Word::MacroOp(_)	=> { return format!("macro_op") },
 			Word::Mat(m) 		=> { return format!("{}", m) },
-			Word::Num(f) 		=> { return format!("{}", f) },
+			Word::Na 			=> { return format!("NA") },
+			Word::Num(f) 		=> { if is_na(*f) { return format!("NA") } else { return format!("{}", f) } },
 			Word::Program(_) 		=> { return format!("program") },
 			Word::Quote(_) 		=> { return format!("quote") },
 			Word::Str(s) 		=> { return format!("\"{}\"", s) }
-			Word::StrVect(s) 	=> { return format!("{}", s) }
+			Word::StrVect(s) 	=> { return sprint_items(s.iter().map(|x| x.clone())) }
 			Word::Sym(s) 		=> { return format!("{}", s) },
-			Word::Vect(v)		=> { return format!("{}", v) }
+			Word::Vect(v)		=> {
+				if !v.iter().any(|f| is_na(*f)) { return format!("{}", v) }
+				return sprint_items(v.iter().map(|f| if is_na(*f) { None } else { Some(f.to_string()) }))
+			}
 		}
 	}
 
@@ -58,11 +70,14 @@ impl Word {
 			Word::Bool(b) 		=> { return format!("{}{}", rep, b) },
 			Word::BoolVect(_)	=> { return format!("{}vec<bool>", rep) },
 			Word::Dict(_) 		=> { return format!("{}dict", rep) },
+			Word::Factor(f) 	=> { return format!("{}factor, {} levels", rep, f.levels.len()) },
+			Word::Frame(f) 		=> { return format!("{}frame, {}x{}", rep, f.nrow(), f.ncol()) },
 			Word::Int(i) 		=> { return format!("{}{}", rep, i) },
 			Word::List(l) 		=> { return format!("{}[{}:...]", rep, l.len()) },	
 			Word::MacroOp(_)	=> { return format!("{}macro_op", rep) },
 			Word::Mat(m) 		=> { return format!("{}mat, {}x{}", rep, m.nrows(), m.ncols()) },
-			Word::Num(f) 		=> { return format!("{}{}", rep, f) },
+			Word::Na 			=> { return format!("{}NA", rep) },
+			Word::Num(f) 		=> { if is_na(*f) { return format!("{}NA", rep) } else { return format!("{}{}", rep, f) } },
 			Word::Program(_)	=> { return format!("{}program", rep) }, 	
 			Word::Quote(_) 		=> { return format!("{}quote", rep) },
 			Word::Str(s) 		=> { return format!("{}\"{}\"", rep, s) }, // SHORTEN! REMOVE "\n"
@@ -78,10 +93,13 @@ impl Word {
 			Word::Bool(_) 		=> { return format!("bool") },
 			Word::BoolVect(_)	=> { return format!("vec<bool>") },
 			Word::Dict(_) 		=> { return format!("dict") },
+			Word::Factor(_) 	=> { return format!("factor") },
+			Word::Frame(_) 		=> { return format!("frame") },
 			Word::Int(_) 		=> { return format!("int") },
 			Word::List(_) 		=> { return format!("list") },
 			Word::MacroOp(_)	=> { return format!("macro") },
 			Word::Mat(_) 		=> { return format!("mat<num>") },
+			Word::Na 			=> { return format!("na") },
 			Word::Num(_) 		=> { return format!("num") },
 			Word::Program(_)	=> { return format!("program") },
 			Word::Quote(_) 		=> { return format!("quote") },
@@ -100,18 +118,64 @@ impl Word {
 	}
 }
 
+/* Anything that does not hold a single number converts to NA. */
 pub fn toF64(w: &Word) -> f64 {
 	match w {
 		Word::Bool(b) 		=> { if *b { return 1.0 } else { return 0.0 } },
 		Word::Int(i) 		=> { return *i as f64 },
-		Word::Mat(m) 		=> { if m.nrows() == 1 && m.ncols() == 1 { return *m.get((0, 0)).unwrap() } else { return NAN } },
+		Word::Mat(m) 		=> { if m.nrows() == 1 && m.ncols() == 1 { return *m.get((0, 0)).unwrap() } else { return na() } },
 		Word::Num(f) 		=> { return *f },
 		Word::Str(s) 		=> match s.parse::<f64>() {
 			Ok(x)	=> { return x },
-			Err(_)	=> { return NAN }
+			Err(_)	=> { return na() }
 		},
-		Word::Vect(v)		=> { if v.dim() == 1 { return *v.get(0).unwrap() } else { return NAN } },
-		_ 	=> { return NAN }
+		Word::Vect(v)		=> { if v.dim() == 1 { return *v.get(0).unwrap() } else { return na() } },
+		_ 	=> { return na() }
+	}
+}
+
+/* Packs words into the tightest collection: 'Vect' for numbers, 'BoolVect' for booleans,
+ * 'StrVect' for strings and a 'List' for anything else. NA fits in any of the vectors. */
+pub fn tighten(list: Vec<Word>) -> Word {
+	if list.len() == 0 { return Word::List(Box::new(list)) }
+	if list.iter().all(|w| match w { Word::Int(_) | Word::Num(_) | Word::Na => true, _ => false }) {
+		return Word::Vect(Box::new(list2array1(&list)))
+	}
+	if list.iter().all(|w| match w { Word::Bool(_) | Word::Na => true, _ => false }) {
+		return Word::BoolVect(Box::new(list.iter().map(|w| match w { Word::Bool(b) => Some(*b), _ => None }).collect::<Array1<Option<bool>>>()))
+	}
+	if list.iter().all(|w| match w { Word::Str(_) | Word::Na => true, _ => false }) {
+		return Word::StrVect(Box::new(list.into_iter().map(|w| match w { Word::Str(s) => Some(*s), _ => None }).collect::<Array1<Option<String>>>()))
+	}
+	return Word::List(Box::new(list))
+}
+
+/* Vector elements in ndarray's layout, with NA for missing ones. */
+fn sprint_items<I: Iterator<Item = Option<String>>>(items: I) -> String {
+	return format!("[{}]", items.map(|x| x.unwrap_or(format!("NA"))).collect::<Vec<String>>().join(", "))
+}
+
+pub fn str_vect(items: Vec<String>) -> Word {
+	return Word::StrVect(Box::new(items.into_iter().map(|s| Some(s)).collect::<Array1<Option<String>>>()))
+}
+
+/* Vector elements as words, with missing ones as 'Word::Na'. */
+pub fn num_word(f: f64) -> Word {
+	if is_na(f) { return Word::Na }
+	return Word::Num(f)
+}
+
+pub fn bool_word(b: &Option<bool>) -> Word {
+	match b {
+		Some(b) => { return Word::Bool(*b) },
+		None 	=> { return Word::Na }
+	}
+}
+
+pub fn str_word(s: &Option<String>) -> Word {
+	match s {
+		Some(s) => { return Word::Str(Box::new(s.clone())) },
+		None 	=> { return Word::Na }
 	}
 }
 